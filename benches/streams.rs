@@ -0,0 +1,69 @@
+// Run with `cargo bench --bench streams`. Wall-clock times are noisy; the reallocation counts are
+// what show whether collecting a stream had to keep growing its Vec.
+extern crate noulith;
+use noulith::simple_eval;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+struct CountingAlloc;
+
+static REALLOCS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        REALLOCS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+fn bench(name: &str, code: &str) {
+    let reallocs = REALLOCS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    simple_eval(code);
+    let elapsed = start.elapsed();
+    println!(
+        "{:<48} {:>10.2?} {:>8} reallocs",
+        name,
+        elapsed,
+        REALLOCS.load(Ordering::Relaxed) - reallocs
+    );
+}
+
+fn main() {
+    // a range knows its length, so force allocates once; the same million elements from a stream
+    // that can't tell grows the Vec as it goes
+    bench("force 1M-element range", "list(0 til 1000000); null");
+    bench(
+        "force 1M elements without a size hint",
+        "list(dedup(0 til 1000000)); null",
+    );
+    // ranges skip ahead with advance_by, so the first of these is mostly setting up the
+    // interpreter; dedup can only step through one element at a time
+    bench(
+        "drop 1M elements from a range",
+        "list(take(1, drop(1000000, 0 til 2000000)))",
+    );
+    bench(
+        "drop 1M elements stepping one at a time",
+        "list(take(1, drop(1000000, dedup(0 til 2000000))))",
+    );
+    // pforce has no parallel path yet, so these should match
+    bench(
+        "list of a 100k-element map",
+        "list((0 til 100000) lazy_map (\\x -> x * x % 7)); null",
+    );
+    bench(
+        "pforce of a 100k-element map",
+        "pforce((0 til 100000) lazy_map (\\x -> x * x % 7), 4); null",
+    );
+}
@@ -3,7 +3,10 @@ use std::io;
 use std::io::BufReader;
 use std::rc::Rc;
 
-use noulith::{evaluate, initialize, lex, parse, type_of, Env, Obj, Token, TopEnv};
+use noulith::{
+    evaluate, initialize, lex, parse, type_of, ElementBudgetScope, Env, Obj, Token, TopEnv,
+    DEFAULT_STREAM_PREVIEW,
+};
 
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
@@ -164,9 +167,12 @@ pub fn repl() {
         backrefs: Vec::new(),
         input: Box::new(BufReader::new(io::stdin())),
         output: Box::new(io::stdout()),
+        stream_preview: DEFAULT_STREAM_PREVIEW,
+        element_budget: 0,
     });
     initialize(&mut env);
     let e = Rc::new(RefCell::new(env));
+    let _budget = ElementBudgetScope::enter(&e);
 
     let mut rl = Editor::<NoulithHelper>::new().expect("readline failed");
     match app_dir_root() {
@@ -107,11 +107,16 @@ pub fn pythonic_slice_obj<T>(xs: &[T], lo: Option<&Obj>, hi: Option<&Obj>) -> NR
 
 // for slicing
 pub fn clamped_pythonic_index<T>(xs: &[T], i: isize) -> usize {
+    clamped_pythonic_index_len(xs.len(), i)
+}
+
+// for slicing things that know their length but aren't slices
+pub fn clamped_pythonic_index_len(len: usize, i: isize) -> usize {
     if i >= 0 {
-        return (i as usize).min(xs.len());
+        return (i as usize).min(len);
     }
 
-    let i2 = i + (xs.len() as isize);
+    let i2 = i + (len as isize);
     if i2 < 0 {
         0
     } else {
@@ -141,13 +146,17 @@ pub fn pythonic_mut<'a, 'b>(xs: &'a mut Vec<Obj>, i: &'b Obj) -> NRes<&'a mut Ob
 }
 
 pub fn pythonic_slice<T>(xs: &[T], lo: Option<isize>, hi: Option<isize>) -> (usize, usize) {
+    pythonic_slice_len(xs.len(), lo, hi)
+}
+
+pub fn pythonic_slice_len(len: usize, lo: Option<isize>, hi: Option<isize>) -> (usize, usize) {
     let clo = match lo {
-        Some(lo) => clamped_pythonic_index(xs, lo),
+        Some(lo) => clamped_pythonic_index_len(len, lo),
         None => 0,
     };
     let chi = match hi {
-        Some(hi) => clamped_pythonic_index(xs, hi),
-        None => xs.len(),
+        Some(hi) => clamped_pythonic_index_len(len, hi),
+        None => len,
     };
     (clo, chi.max(clo))
 }
@@ -157,11 +166,22 @@ pub fn pythonic_slice<T>(xs: &[T], lo: Option<isize>, hi: Option<isize>) -> (usi
 //
 // more using this as "lazy, possibly infinite list" rn i.e. trying to support indexing etc.
 pub trait Stream: Iterator<Item = NRes<Obj>> + Display + Debug {
+    // A clone resumes from exactly where this stream is now, including anything it has buffered
+    // or any error it stopped on, and advancing either one never affects the other. (Clones may
+    // share work behind the scenes, like CachedStream, as long as that's not observable.)
     fn clone_box(&self) -> Box<dyn Stream>;
+    // Some(true) if provably infinite, Some(false) if provably finite, None if we can't tell
+    // (e.g. the stream runs a function that might break). Unlike len, this never iterates.
+    fn is_infinite(&self) -> Option<bool> {
+        None
+    }
     // FIXME: this used to mean "length or infinity" but it increasingly looks like we actually
     // want streams where we can't determine their length by inspection, so this type doesn't make
     // sense any more. is it NErr<usize>? Option<NErr<usize>>?
     fn len(&self) -> Option<usize> {
+        if self.is_infinite() == Some(true) {
+            return None;
+        }
         let mut s = self.clone_box();
         let mut ret = 0;
         while let Some(_) = s.next() {
@@ -170,50 +190,58 @@ pub trait Stream: Iterator<Item = NRes<Obj>> + Display + Debug {
         Some(ret)
     }
     fn force(&self) -> NRes<Vec<Obj>> {
-        self.clone_box().collect()
+        if self.is_infinite() == Some(true) {
+            return Err(NErr::value_error(format!(
+                "Cannot force {} because it's infinite",
+                self
+            )));
+        }
+        let it = self.clone_box();
+        let mut ret = Vec::with_capacity(it.size_hint().0);
+        for x in it {
+            check_element_budget(ret.len())?;
+            ret.push(x?);
+        }
+        Ok(ret)
     }
     fn pythonic_index_isize(&self, i0: isize) -> NRes<Obj> {
-        let mut i = i0;
-        if i >= 0 {
-            let mut it = self.clone_box();
-            while let Some(e) = it.next() {
-                if i == 0 {
-                    return e;
-                }
-                i -= 1;
-            }
-            Err(NErr::index_error(format!("Index out of bounds: {}", i0)))
-        } else {
-            let mut v = self.force()?;
-            let i2 = (i + (v.len() as isize)) as usize;
-            if i2 < v.len() {
-                Ok(v.swap_remove(i2))
-            } else {
-                Err(NErr::index_error(format!("Index out of bounds: {}", i0)))
+        walk_to_index(self, i0)
+    }
+    // What indexing goes through. Streams that can jump straight to an element override
+    // pythonic_index_isize; everything else steps through a clone.
+    fn nth(&self, i: isize) -> NRes<Obj> {
+        self.pythonic_index_isize(i)
+    }
+    // For display: up to n elements, and whether they're all of them, but only if they can be
+    // had without running user code or doing any real work. None shows the structure instead.
+    fn cheap_prefix(&self, _n: usize) -> Option<(Vec<Obj>, bool)> {
+        None
+    }
+    // Skips the next n elements, or all of them if there are fewer. Streams that can just move a
+    // counter override this; the rest step through. Call it as Stream::advance_by, since Iterator
+    // has an unstable method of the same name.
+    fn advance_by(&mut self, n: usize) -> NRes<()> {
+        for _ in 0..n {
+            match self.next() {
+                Some(Err(e)) => return Err(e),
+                Some(Ok(_)) => {}
+                None => break,
             }
         }
+        Ok(())
     }
     fn pythonic_slice(&self, lo: Option<isize>, hi: Option<isize>) -> NRes<Seq> {
         let lo = lo.unwrap_or(0);
         match (lo, hi) {
             (lo, None) if lo >= 0 => {
                 let mut it = self.clone_box();
-                for _ in 0..lo {
-                    if it.next().is_none() {
-                        return Ok(Seq::Stream(Rc::from(it)));
-                    }
-                }
+                Stream::advance_by(&mut *it, lo as usize)?;
                 Ok(Seq::Stream(Rc::from(it)))
             }
             (lo, Some(hi)) if lo >= 0 && hi >= 0 => {
                 let mut it = self.clone_box();
                 let mut v = Vec::new();
-                for _ in 0..lo {
-                    match it.next() {
-                        Some(_) => {}
-                        None => break,
-                    }
-                }
+                Stream::advance_by(&mut *it, lo as usize)?;
                 for _ in lo..hi {
                     match it.next() {
                         Some(x) => v.push(x?),
@@ -229,13 +257,143 @@ pub trait Stream: Iterator<Item = NRes<Obj>> + Display + Debug {
             }
         }
     }
+    // Walks to the end keeping only the latest element. Streams that can index from the end
+    // cheaply override this.
+    fn last(&self) -> NRes<Obj> {
+        if self.is_infinite() == Some(true) {
+            return Err(NErr::value_error(format!(
+                "Cannot take the last element of {} because it's infinite",
+                self
+            )));
+        }
+        let mut ret = None;
+        for x in self.clone_box() {
+            ret = Some(x?);
+        }
+        ret.ok_or_else(|| NErr::value_error("empty stream".to_string()))
+    }
+    // How many elements there are, without keeping any of them. Unlike len, this walks streams
+    // whose length can't be computed up front, so only provably infinite ones are refused, and
+    // errors from the walk propagate.
+    fn count(&self) -> NRes<BigInt> {
+        if self.is_infinite() == Some(true) {
+            return Err(NErr::value_error(format!(
+                "Cannot count the elements of {} because it's infinite",
+                self
+            )));
+        }
+        if let Some(n) = cheap_len(self) {
+            return Ok(BigInt::from(n));
+        }
+        let mut n = BigInt::from(0);
+        for x in self.clone_box() {
+            x?;
+            n += 1;
+        }
+        Ok(n)
+    }
+    // Index of the first element satisfying pred, stopping as soon as it's found. Never returns
+    // on an infinite stream without a match.
+    fn position(&self, pred: &mut dyn FnMut(&Obj) -> NRes<bool>) -> NRes<Option<usize>> {
+        for (i, x) in self.clone_box().enumerate() {
+            if pred(&x?)? {
+                return Ok(Some(i));
+            }
+        }
+        Ok(None)
+    }
+    // Same caveat as position; streams that can tell by inspection override this.
+    fn contains(&self, x: &Obj) -> NRes<bool> {
+        Ok(self.position(&mut |e| Ok(e == x))?.is_some())
+    }
+    // Streams that can reverse lazily override this; everything else has to be read in full.
     fn reversed(&self) -> NRes<Seq> {
-        let mut xs = self.force()?;
+        if self.is_infinite() == Some(true) {
+            return Err(NErr::value_error(format!(
+                "Cannot reverse {} because it's infinite",
+                self
+            )));
+        }
+        let mut xs = Vec::with_capacity(self.len().unwrap_or(0));
+        for x in self.clone_box() {
+            check_element_budget(xs.len())?;
+            xs.push(x?);
+        }
         xs.reverse();
         Ok(Seq::List(Rc::new(xs)))
     }
 }
 
+// What a stream's Iterator::size_hint returns when its len is cheap to compute
+pub fn exact_size_hint(len: Option<usize>) -> (usize, Option<usize>) {
+    match len {
+        Some(n) => (n, Some(n)),
+        None => (0, None),
+    }
+}
+
+// Iterator's convention for "more than usize::MAX left", for streams that count what's left
+pub fn overflowing_size_hint() -> (usize, Option<usize>) {
+    (usize::MAX, None)
+}
+
+// For streams that keep count of what's left, None meaning that stopped fitting in a usize
+pub fn counted_size_hint(left: Option<usize>) -> (usize, Option<usize>) {
+    match left {
+        Some(k) => (k, Some(k)),
+        None => overflowing_size_hint(),
+    }
+}
+
+// The stream's length if it's known without iterating it, i.e. it reports an exact size hint.
+// Unlike Stream::len, this never runs user code.
+pub fn cheap_len<S: Stream + ?Sized>(s: &S) -> Option<usize> {
+    match s.size_hint() {
+        (lo, Some(hi)) if lo == hi => Some(lo),
+        _ => None,
+    }
+}
+
+// Whether the stream knows it has more left than fit in a usize, see overflowing_size_hint
+pub fn len_overflows<S: Stream + ?Sized>(s: &S) -> bool {
+    s.size_hint() == overflowing_size_hint()
+}
+
+// cheap_prefix for streams whose elements are trivial to produce
+pub fn cloned_prefix<S: Stream + ?Sized>(s: &S, n: usize) -> Option<(Vec<Obj>, bool)> {
+    let mut xs = s
+        .clone_box()
+        .take(n.saturating_add(1))
+        .collect::<NRes<Vec<Obj>>>()
+        .ok()?;
+    let all = xs.len() <= n;
+    xs.truncate(n);
+    Some((xs, all))
+}
+
+// The default indexing, for overrides that can only sometimes do better to fall back on
+pub fn walk_to_index<S: Stream + ?Sized>(s: &S, i0: isize) -> NRes<Obj> {
+    let mut i = i0;
+    if i >= 0 {
+        let mut it = s.clone_box();
+        while let Some(e) = it.next() {
+            if i == 0 {
+                return e;
+            }
+            i -= 1;
+        }
+        Err(NErr::index_error(format!("Index out of bounds: {}", i0)))
+    } else {
+        let mut v = s.force()?;
+        let i2 = (i + (v.len() as isize)) as usize;
+        if i2 < v.len() {
+            Ok(v.swap_remove(i2))
+        } else {
+            Err(NErr::index_error(format!("Index out of bounds: {}", i0)))
+        }
+    }
+}
+
 // Sequences: iterators
 pub enum ObjToCloningIter<'a> {
     List(std::slice::Iter<'a, Obj>),
@@ -286,7 +444,8 @@ pub enum MutObjIntoIter<'a> {
     String(RcStringIter<'a>),
     Vector(RcVecIter<'a, NNum>),
     Bytes(RcVecIter<'a, u8>),
-    Stream(&'a mut Rc<dyn Stream>),
+    // counts elements taken if the iterator is meant to be finite, for the element budget
+    Stream(&'a mut Rc<dyn Stream>, Option<usize>),
 }
 
 // iterates over (index, value) or (key, value)
@@ -306,7 +465,7 @@ pub fn mut_seq_into_iter(seq: &mut Seq) -> MutObjIntoIter<'_> {
         Seq::String(s) => MutObjIntoIter::String(RcStringIter::of(s)),
         Seq::Vector(v) => MutObjIntoIter::Vector(RcVecIter::of(v)),
         Seq::Bytes(v) => MutObjIntoIter::Bytes(RcVecIter::of(v)),
-        Seq::Stream(v) => MutObjIntoIter::Stream(v),
+        Seq::Stream(v) => MutObjIntoIter::Stream(v, None),
     }
 }
 
@@ -314,13 +473,17 @@ pub fn mut_seq_into_finite_iter<'a, 'b>(
     seq: &'a mut Seq,
     purpose: &'b str,
 ) -> NRes<MutObjIntoIter<'a>> {
-    if seq.len().is_none() {
+    // only refuse streams we know are infinite; asking for len would consume unknown ones
+    if matches!(seq, Seq::Stream(s) if s.is_infinite() == Some(true)) {
         Err(NErr::value_error(format!(
             "{}: infinite, will not terminate",
             purpose
         )))
     } else {
-        Ok(mut_seq_into_iter(seq))
+        match mut_seq_into_iter(seq) {
+            MutObjIntoIter::Stream(s, _) => Ok(MutObjIntoIter::Stream(s, Some(0))),
+            it => Ok(it),
+        }
     }
 }
 
@@ -335,6 +498,17 @@ pub fn mut_obj_into_iter<'a, 'b>(obj: &'a mut Obj, purpose: &'b str) -> NRes<Mut
     }
 }
 
+pub fn mut_obj_into_finite_iter<'a>(obj: &'a mut Obj, purpose: &str) -> NRes<MutObjIntoIter<'a>> {
+    match obj {
+        Obj::Seq(s) => mut_seq_into_finite_iter(s, purpose),
+        e => Err(NErr::type_error(format!(
+            "{}: not iterable: {}",
+            purpose,
+            FmtObj::debug(e)
+        ))),
+    }
+}
+
 impl Iterator for MutObjIntoIter<'_> {
     type Item = NRes<Obj>;
 
@@ -345,15 +519,23 @@ impl Iterator for MutObjIntoIter<'_> {
             MutObjIntoIter::String(it) => Some(Ok(Obj::from(it.next()?))),
             MutObjIntoIter::Vector(it) => Some(Ok(Obj::Num(it.next()?.clone()))),
             MutObjIntoIter::Bytes(it) => Some(Ok(Obj::from(it.next()? as usize))),
-            MutObjIntoIter::Stream(it) => match Rc::get_mut(it) {
-                Some(it) => it.next(),
-                None => {
-                    let mut it2 = it.clone_box();
-                    let ret = it2.next();
-                    **it = Rc::from(it2);
-                    ret
+            MutObjIntoIter::Stream(it, count) => {
+                if let Some(count) = count {
+                    if let Err(e) = check_element_budget(*count) {
+                        return Some(Err(e));
+                    }
+                    *count += 1;
                 }
-            },
+                match Rc::get_mut(it) {
+                    Some(it) => it.next(),
+                    None => {
+                        let mut it2 = it.clone_box();
+                        let ret = it2.next();
+                        **it = Rc::from(it2);
+                        ret
+                    }
+                }
+            }
         }
     }
 }
@@ -555,7 +737,7 @@ pub fn expect_one(args: Vec<Obj>, msg: &str) -> NRes<Obj> {
     }
 }
 
-pub fn call_type(ty: &ObjType, arg: Vec<Obj>) -> NRes<Obj> {
+pub fn call_type(env: &REnv, ty: &ObjType, arg: Vec<Obj>) -> NRes<Obj> {
     match ty {
         ObjType::Int => match expect_one(arg, "int")? {
             Obj::Num(n) => Ok(Obj::Num(
@@ -595,23 +777,27 @@ pub fn call_type(ty: &ObjType, arg: Vec<Obj>) -> NRes<Obj> {
         },
         ObjType::List => match expect_one(arg, "list")? {
             Obj::Seq(Seq::List(xs)) => Ok(Obj::Seq(Seq::List(xs))),
+            Obj::Seq(Seq::Stream(s)) => Ok(Obj::list(s.force()?)),
             mut arg => Ok(Obj::list(
                 mut_obj_into_iter(&mut arg, "list conversion")?.collect::<NRes<Vec<Obj>>>()?,
             )),
         },
-        ObjType::String => Ok(Obj::from(format!("{}", expect_one(arg, "str")?))),
+        ObjType::String => {
+            let flags = try_borrow_nres(env, "str", "")?.fmt_flags();
+            Ok(Obj::from(format!("{}", FmtObj(&expect_one(arg, "str")?, &flags))))
+        }
         ObjType::Bytes => match expect_one(arg, "bytes")? {
             Obj::Seq(Seq::Bytes(xs)) => Ok(Obj::Seq(Seq::Bytes(xs))),
             Obj::Seq(Seq::String(s)) => Ok(Obj::Seq(Seq::Bytes(Rc::new(s.as_bytes().to_vec())))),
             mut arg => Ok(Obj::Seq(Seq::Bytes(Rc::new(
-                mut_obj_into_iter(&mut arg, "bytes conversion")?
+                mut_obj_into_finite_iter(&mut arg, "bytes conversion")?
                     .map(|e| to_byte(e?, "bytes conversion"))
                     .collect::<NRes<Vec<u8>>>()?,
             )))),
         },
         ObjType::Vector => match expect_one(arg, "vector")? {
             Obj::Seq(Seq::Vector(s)) => Ok(Obj::Seq(Seq::Vector(s))),
-            mut arg => to_obj_vector(mut_obj_into_iter(&mut arg, "vector conversion")?),
+            mut arg => to_obj_vector(mut_obj_into_finite_iter(&mut arg, "vector conversion")?),
         },
         ObjType::Dict => match expect_one(arg, "dict")? {
             Obj::Seq(Seq::Dict(x, d)) => Ok(Obj::Seq(Seq::Dict(x, d))),
@@ -621,7 +807,7 @@ pub fn call_type(ty: &ObjType, arg: Vec<Obj>) -> NRes<Obj> {
                     Rc::new(mut_obj_into_iter_pairs(&mut arg, "dict conversion")?.collect::<HashMap<ObjKey, Obj>>()), None)),
                     */
             mut arg => Ok(Obj::dict(
-                mut_obj_into_iter(&mut arg, "dict conversion")?
+                mut_obj_into_finite_iter(&mut arg, "dict conversion")?
                     .map(|p| match p? {
                         Obj::Seq(Seq::List(xs)) => match few2(unwrap_or_clone(xs)) {
                             Few2::Two(k, v) => Ok((to_key(k)?, v)),
@@ -1047,6 +1233,82 @@ impl PartialEq for Seq {
     }
 }
 
+// Streams equal lists or other streams with the same elements, but only when both are provably
+// finite, so comparing never hangs. Each element is computed once, and an element erroring is an
+// error. Only the == and != builtins do this; PartialEq stays structural, so it never runs user
+// code and streams are never equal to anything there.
+fn finite_stream_eq(a: &dyn Stream, b: &Seq) -> NRes<bool> {
+    if a.is_infinite() != Some(false) {
+        return Ok(false);
+    }
+    // lengths usually aren't known up front, so walk both and require they run out together
+    let mut b: Box<dyn Iterator<Item = NRes<Obj>>> = match b {
+        Seq::List(b) => {
+            if cheap_len(a).is_some_and(|n| n != b.len()) {
+                return Ok(false);
+            }
+            Box::new(b.iter().cloned().map(Ok))
+        }
+        Seq::Stream(b) => {
+            if b.is_infinite() != Some(false)
+                || matches!((cheap_len(a), cheap_len(&**b)), (Some(m), Some(n)) if m != n)
+            {
+                return Ok(false);
+            }
+            b.clone_box()
+        }
+        _ => return Ok(false),
+    };
+    let mut a = a.clone_box();
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => return Ok(true),
+            (Some(Err(e)), _) | (_, Some(Err(e))) => return Err(e),
+            (Some(Ok(x)), Some(Ok(y))) => {
+                if !obj_eq(&x, &y)? {
+                    return Ok(false);
+                }
+            }
+            _ => return Ok(false),
+        }
+    }
+}
+
+// == for the builtins, which unlike PartialEq can report a stream erroring while it's compared.
+// Lists and dict values are compared element by element with this too, so streams nested in them
+// get the same treatment.
+pub fn obj_eq(a: &Obj, b: &Obj) -> NRes<bool> {
+    match (a, b) {
+        (Obj::Seq(Seq::Stream(s)), Obj::Seq(t)) | (Obj::Seq(t), Obj::Seq(Seq::Stream(s))) => {
+            finite_stream_eq(&**s, t)
+        }
+        (Obj::Seq(Seq::List(a)), Obj::Seq(Seq::List(b))) => {
+            if a.len() != b.len() {
+                return Ok(false);
+            }
+            for (x, y) in a.iter().zip(b.iter()) {
+                if !obj_eq(x, y)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        (Obj::Seq(Seq::Dict(a, _)), Obj::Seq(Seq::Dict(b, _))) => {
+            if a.len() != b.len() {
+                return Ok(false);
+            }
+            for (k, x) in a.iter() {
+                match b.get(k) {
+                    Some(y) if obj_eq(x, y)? => {}
+                    _ => return Ok(false),
+                }
+            }
+            Ok(true)
+        }
+        _ => Ok(a == b),
+    }
+}
+
 impl PartialOrd for Obj {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         match (self, other) {
@@ -1070,6 +1332,67 @@ impl PartialOrd for Seq {
     }
 }
 
+pub fn ncmp(aa: &Obj, bb: &Obj) -> NRes<Ordering> {
+    match (aa, bb) {
+        (Obj::Num(a), Obj::Num(b)) => a.partial_cmp(b).ok_or(NErr::type_error(format!(
+            "Can't compare nums {} and {}",
+            FmtObj::debug(aa),
+            FmtObj::debug(bb)
+        ))),
+        (Obj::Seq(a), Obj::Seq(b)) => a.partial_cmp(b).ok_or(NErr::type_error(format!(
+            "Can't compare seqs {} and {}",
+            FmtObj::debug(aa),
+            FmtObj::debug(bb)
+        ))),
+        _ => Err(NErr::type_error(format!(
+            "Can't compare {} and {}",
+            FmtObj::debug(aa),
+            FmtObj::debug(bb)
+        ))),
+    }
+}
+
+// A total order on every value, for sorting and heaps, where an error or an inconsistent answer
+// is worse than an arbitrary one. Agrees with ncmp wherever ncmp succeeds, except that NaNs are
+// equal to each other and smaller than every other number. Otherwise values are ordered by kind:
+// null, numbers, then sequences (lists, strings, vectors, bytes, dicts by size, streams), then
+// functions and struct instances, which are all equal to each other.
+pub fn total_cmp(a: &Obj, b: &Obj) -> Ordering {
+    fn kind(obj: &Obj) -> u8 {
+        match obj {
+            Obj::Null => 0,
+            Obj::Num(_) => 1,
+            Obj::Seq(Seq::List(_)) => 2,
+            Obj::Seq(Seq::String(_)) => 3,
+            Obj::Seq(Seq::Vector(_)) => 4,
+            Obj::Seq(Seq::Bytes(_)) => 5,
+            Obj::Seq(Seq::Dict(..)) => 6,
+            Obj::Seq(Seq::Stream(_)) => 7,
+            Obj::Func(..) => 8,
+            Obj::Instance(..) => 9,
+        }
+    }
+    match (a, b) {
+        (Obj::Num(a), Obj::Num(b)) => a.total_cmp_small_nan(b),
+        (Obj::Seq(Seq::List(a)), Obj::Seq(Seq::List(b))) => a
+            .iter()
+            .zip(b.iter())
+            .map(|(x, y)| total_cmp(x, y))
+            .find(|o| o.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        (Obj::Seq(Seq::String(a)), Obj::Seq(Seq::String(b))) => a.cmp(b),
+        (Obj::Seq(Seq::Vector(a)), Obj::Seq(Seq::Vector(b))) => a
+            .iter()
+            .zip(b.iter())
+            .map(|(x, y)| x.total_cmp_small_nan(y))
+            .find(|o| o.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        (Obj::Seq(Seq::Bytes(a)), Obj::Seq(Seq::Bytes(b))) => a.cmp(b),
+        (Obj::Seq(Seq::Dict(a, _)), Obj::Seq(Seq::Dict(b, _))) => a.len().cmp(&b.len()),
+        _ => kind(a).cmp(&kind(b)),
+    }
+}
+
 // all thins formatting
 
 #[derive(Clone, Debug)]
@@ -1096,6 +1419,7 @@ pub struct MyFmtFlags {
     pub pad_length: usize,
     pub pad_align: FmtAlign,
     pub budget: usize,
+    pub stream_preview: usize,
 }
 
 impl MyFmtFlags {
@@ -1107,6 +1431,7 @@ impl MyFmtFlags {
             pad_length: 0,
             pad_align: FmtAlign::Right,
             budget: usize::MAX,
+            stream_preview: DEFAULT_STREAM_PREVIEW,
         }
     }
     pub const fn budgeted_repr(budget: usize) -> MyFmtFlags {
@@ -1277,6 +1602,76 @@ pub fn write_bytes(
     write!(formatter, "]")
 }
 
+// How many elements of a stream to show when displaying it, if it can show them cheaply (see
+// Stream::cheap_prefix). Each interpreter keeps its own in TopEnv.
+pub const DEFAULT_STREAM_PREVIEW: usize = 10;
+
+// The most elements any one operation that fully consumes a stream (forcing, summing, joining,
+// ...) may take before giving up, or 0 for no limit. This is the guard for streams like iterate
+// that can't know they're infinite. Each interpreter keeps its own in TopEnv; streams can't see
+// their env, so while an evaluation runs, ElementBudgetScope copies its budget here.
+thread_local! {
+    static ELEMENT_BUDGET: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+// Installs env's element budget for as long as it's alive, then puts back whatever was there
+// before. Hold one while running an interpreter's code.
+pub struct ElementBudgetScope(usize);
+
+impl ElementBudgetScope {
+    pub fn enter(env: &REnv) -> ElementBudgetScope {
+        let budget = env.borrow().mut_top_env(|t| t.element_budget);
+        ElementBudgetScope(ELEMENT_BUDGET.with(|b| b.replace(budget)))
+    }
+}
+
+impl Drop for ElementBudgetScope {
+    fn drop(&mut self) {
+        ELEMENT_BUDGET.with(|b| b.set(self.0));
+    }
+}
+
+// for set_element_budget, so the running evaluation sees the change too
+pub fn set_element_budget(env: &Env, budget: usize) {
+    env.mut_top_env(|t| t.element_budget = budget);
+    ELEMENT_BUDGET.with(|b| b.set(budget));
+}
+
+pub fn check_element_budget(consumed: usize) -> NRes<()> {
+    let budget = ELEMENT_BUDGET.with(|b| b.get());
+    if budget != 0 && consumed >= budget {
+        Err(NErr::value_error(format!(
+            "exceeded element budget of {}",
+            budget
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn write_stream_preview(
+    s: &dyn Stream,
+    formatter: &mut dyn fmt::Write,
+    flags: &mut MyFmtFlags,
+) -> fmt::Result {
+    let n = flags.stream_preview;
+    // a stream that fits in the preview would print just like a list, so show what it is instead
+    let xs = match s.cheap_prefix(n) {
+        Some((xs, false)) if n > 0 => xs,
+        _ => return write!(formatter, "{}", s),
+    };
+    write!(formatter, "[")?;
+    write_slice(xs.as_slice(), formatter, flags)?;
+    if !xs.is_empty() {
+        write!(formatter, ", ")?;
+    }
+    write!(formatter, "...")?;
+    if let Some(len) = cheap_len(s) {
+        write!(formatter, ", ({} elements)", len)?;
+    }
+    write!(formatter, "]")
+}
+
 // ????
 pub struct FmtObj<'a, 'b>(pub &'a Obj, pub &'b MyFmtFlags);
 impl<'a, 'b> Display for FmtObj<'a, 'b> {
@@ -1353,9 +1748,7 @@ impl MyDisplay for Obj {
                 write_slice(xs.as_slice(), formatter, flags)?;
                 write!(formatter, ")")
             }
-            Obj::Seq(Seq::Stream(x)) => {
-                write!(formatter, "{}", x)
-            }
+            Obj::Seq(Seq::Stream(x)) => write_stream_preview(&**x, formatter, flags),
             Obj::Seq(Seq::Bytes(xs)) => write_bytes(xs.as_slice(), formatter, flags),
             Obj::Func(f, p) => write!(formatter, "<{} p:{}>", f, p.0),
             Obj::Instance(_, fields) => write!(formatter, "<instance: {}>", CommaSeparated(fields)),
@@ -3970,6 +4363,8 @@ pub struct TopEnv {
     pub backrefs: Vec<Obj>,
     pub input: Box<dyn BufRead>,
     pub output: Box<dyn WriteMaybeExtractable>,
+    pub stream_preview: usize,
+    pub element_budget: usize,
 }
 
 impl Debug for TopEnv {
@@ -4105,6 +4500,8 @@ impl Env {
             backrefs: Vec::new(),
             input: Box::new(io::empty()),
             output: Box::new(io::sink()),
+            stream_preview: DEFAULT_STREAM_PREVIEW,
+            element_budget: 0,
         })
     }
     pub fn with_parent(env: &Rc<RefCell<Env>>) -> Rc<RefCell<Env>> {
@@ -4119,6 +4516,12 @@ impl Env {
             Err(t) => f(&mut t.borrow_mut()),
         }
     }
+    // default formatting, with this interpreter's settings
+    pub fn fmt_flags(&self) -> MyFmtFlags {
+        let mut flags = MyFmtFlags::new();
+        flags.stream_preview = self.mut_top_env(|t| t.stream_preview);
+        flags
+    }
 
     pub fn try_borrow_get_var(env: &Rc<RefCell<Env>>, s: &str) -> NRes<Obj> {
         let r = try_borrow_nres(env, "env", s)?;
@@ -496,8 +496,11 @@ pub fn evaluate(env: &Rc<RefCell<Env>>, expr: &LocExpr) -> NRes<Obj> {
                 match x {
                     Ok(c) => acc.push(*c),
                     Err((expr, flags)) => {
+                        let mut flags = flags.clone();
+                        flags.stream_preview = try_borrow_nres(env, "format string", "")?
+                            .mut_top_env(|t| t.stream_preview);
                         evaluate(env, &expr)?
-                            .fmt_with(&mut acc, flags.clone())
+                            .fmt_with(&mut acc, flags)
                             .map_err(|e| NErr::io_error(format!("format string issue: {}", e)))?
                     }
                 }
@@ -1353,7 +1356,7 @@ pub fn index(xr: Obj, ir: Obj) -> NRes<Obj> {
             Seq::Bytes(v) => Ok(Obj::from(v[pythonic_index(v, &ii)?] as usize)),
             Seq::Stream(v) => match ii {
                 Obj::Num(ii) => match ii.to_isize() {
-                    Some(n) => v.pythonic_index_isize(n),
+                    Some(n) => v.nth(n),
                     _ => Err(NErr::index_error(format!(
                         "Index out of bounds of isize or non-integer: {:?}",
                         ii
@@ -2439,7 +2442,7 @@ impl Func {
                 };
                 slice(x, lo, hi)
             }
-            Func::Type(t) => call_type(t, args),
+            Func::Type(t) => call_type(env, t, args),
             Func::StructField(struc, field_index) => match few(args) {
                 Few::One(Obj::Instance(s, fields)) => {
                     if *struc == s {
@@ -281,26 +281,6 @@ impl ComparisonOperator {
     }
 }
 
-fn ncmp(aa: &Obj, bb: &Obj) -> NRes<Ordering> {
-    match (aa, bb) {
-        (Obj::Num(a), Obj::Num(b)) => a.partial_cmp(b).ok_or(NErr::type_error(format!(
-            "Can't compare nums {} and {}",
-            FmtObj::debug(aa),
-            FmtObj::debug(bb)
-        ))),
-        (Obj::Seq(a), Obj::Seq(b)) => a.partial_cmp(b).ok_or(NErr::type_error(format!(
-            "Can't compare seqs {} and {}",
-            FmtObj::debug(aa),
-            FmtObj::debug(bb)
-        ))),
-        _ => Err(NErr::type_error(format!(
-            "Can't compare {} and {}",
-            FmtObj::debug(aa),
-            FmtObj::debug(bb)
-        ))),
-    }
-}
-
 fn clone_and_part_app_2(f: &(impl Builtin + Clone + 'static), arg: Obj) -> Obj {
     Obj::Func(
         Func::PartialApp2(Box::new(Func::Builtin(Rc::new(f.clone()))), Box::new(arg)),
@@ -460,7 +440,7 @@ impl Builtin for Extremum {
             Few::Zero => Err(NErr::type_error(format!("{}: at least 1 arg", self.name))),
             Few::One(Obj::Seq(mut s)) => {
                 let mut ret: Option<Obj> = None;
-                for b in mut_seq_into_iter(&mut s) {
+                for b in mut_seq_into_finite_iter(&mut s, &self.name)? {
                     let b = b?;
                     if match &ret {
                         None => true,
@@ -497,7 +477,7 @@ impl Builtin for Extremum {
                 match (i_func, few(t)) {
                     (None, Few::One(mut a)) => {
                         let mut ret: Option<Obj> = None;
-                        for b in mut_obj_into_iter(&mut a, &self.name)? {
+                        for b in mut_obj_into_finite_iter(&mut a, &self.name)? {
                             let b = b?;
                             if match &ret {
                                 None => true,
@@ -526,7 +506,7 @@ impl Builtin for Extremum {
                     }
                     (Some((_, f)), Few::One(mut a)) => {
                         let mut ret: Option<Obj> = None;
-                        for b in mut_obj_into_iter(&mut a, &self.name)? {
+                        for b in mut_obj_into_finite_iter(&mut a, &self.name)? {
                             let b = b?;
                             if match &ret {
                                 None => true,
@@ -578,6 +558,47 @@ impl Builtin for Extremum {
     }
 }
 
+// min_by_key or max_by_key: keys are computed once per element and compared by total_cmp, and
+// only a strictly better key replaces the current pick, so ties keep the first one seen
+fn extremum_by_key(env: &REnv, a: Obj, b: Obj, bias: Ordering, name: &str) -> NRes<Obj> {
+    let (mut a, f) = match (a, b) {
+        (a, Obj::Func(f, _)) | (Obj::Func(f, _), a) => (a, f),
+        (a, b) => return Err(NErr::argument_error_2(&a, &b)),
+    };
+    let mut ret: Option<(Obj, Obj)> = None;
+    for b in mut_obj_into_finite_iter(&mut a, name)? {
+        let b = b?;
+        let k = f.run(env, vec![b.clone()])?;
+        if match &ret {
+            None => true,
+            Some((rk, _)) => total_cmp(&k, rk) == bias,
+        } {
+            ret = Some((k, b))
+        }
+    }
+    Ok(ret.ok_or(NErr::empty_error("empty".to_string()))?.1)
+}
+
+// Index of the first element whose key (the element itself without f) is least or greatest under
+// total_cmp; later ties don't replace it.
+fn arg_extremum(env: &REnv, mut a: Obj, f: Option<Func>, bias: Ordering, name: &str) -> NRes<Obj> {
+    let mut ret: Option<(Obj, usize)> = None;
+    for (i, b) in mut_obj_into_finite_iter(&mut a, name)?.enumerate() {
+        let b = b?;
+        let k = match &f {
+            Some(f) => f.run(env, vec![b])?,
+            None => b,
+        };
+        if match &ret {
+            None => true,
+            Some((rk, _)) => total_cmp(&k, rk) == bias,
+        } {
+            ret = Some((k, i))
+        }
+    }
+    Ok(Obj::from(ret.ok_or(NErr::empty_error("empty".to_string()))?.1))
+}
+
 struct CataCounter(usize);
 impl Catamorphism for CataCounter {
     fn give(&mut self, arg: Obj) -> NRes<()> {
@@ -601,7 +622,7 @@ impl Builtin for Count {
             Few2::Zero => Err(NErr::type_error(format!("count: at least 1 arg"))),
             Few2::One(Obj::Seq(mut s)) => {
                 let mut c = 0usize;
-                for b in mut_seq_into_iter(&mut s) {
+                for b in mut_seq_into_finite_iter(&mut s, "count")? {
                     if b?.truthy() {
                         c += 1
                     }
@@ -610,7 +631,7 @@ impl Builtin for Count {
             }
             Few2::One(a) => Ok(clone_and_part_app_last(self, a)),
             Few2::Two(mut a, b) => {
-                let it = mut_obj_into_iter(&mut a, "count")?;
+                let it = mut_obj_into_finite_iter(&mut a, "count")?;
                 let mut c = 0usize;
                 match b {
                     Obj::Func(b, _) => {
@@ -737,11 +758,9 @@ impl Builtin for TilBuiltin {
             Few3::Two(Obj::Num(a), Obj::Num(b)) => {
                 let n1 = into_bigint_ok(a)?;
                 let n2 = into_bigint_ok(b)?;
-                Ok(Obj::Seq(Seq::Stream(Rc::new(Range(
-                    n1,
-                    Some(n2),
-                    BigInt::from(1),
-                )))))
+                Ok(Obj::Seq(Seq::Stream(
+                    Range(n1, Some(n2), BigInt::from(1)).into_stream(),
+                )))
             }
             Few3::Two(Obj::Seq(Seq::String(a)), Obj::Seq(Seq::String(b))) => {
                 let mut ac = a.chars();
@@ -762,7 +781,9 @@ impl Builtin for TilBuiltin {
                 let n1 = into_bigint_ok(a)?;
                 let n2 = into_bigint_ok(b)?;
                 let n3 = into_bigint_ok(c)?;
-                Ok(Obj::Seq(Seq::Stream(Rc::new(Range(n1, Some(n2), n3)))))
+                Ok(Obj::Seq(Seq::Stream(
+                    err_add_name(Range::new(n1, Some(n2), n3), "til")?.into_stream(),
+                )))
             }
             c => err_add_name(Err(NErr::argument_error_few3(&c)), "til"),
         }
@@ -787,17 +808,15 @@ impl Builtin for TilBuiltin {
 struct ToBuiltin;
 
 impl Builtin for ToBuiltin {
-    fn run(&self, _env: &REnv, args: Vec<Obj>) -> NRes<Obj> {
+    fn run(&self, env: &REnv, args: Vec<Obj>) -> NRes<Obj> {
         match few3(args) {
             Few3::One(a) => Ok(clone_and_part_app_2(self, a)),
             Few3::Two(Obj::Num(a), Obj::Num(b)) => {
                 let n1 = into_bigint_ok(a)?;
                 let n2 = into_bigint_ok(b)?;
-                Ok(Obj::Seq(Seq::Stream(Rc::new(Range(
-                    n1,
-                    Some(n2 + 1usize),
-                    BigInt::from(1),
-                )))))
+                Ok(Obj::Seq(Seq::Stream(
+                    Range(n1, Some(n2 + 1usize), BigInt::from(1)).into_stream(),
+                )))
             }
             Few3::Two(Obj::Seq(Seq::String(a)), Obj::Seq(Seq::String(b))) => {
                 let mut ac = a.chars();
@@ -818,20 +837,26 @@ impl Builtin for ToBuiltin {
                     ))),
                 }
             }
-            Few3::Two(a, Obj::Func(Func::Type(t), _)) => call_type(&t, vec![a]), // sugar lmao
+            Few3::Two(a, Obj::Func(Func::Type(t), _)) => call_type(env, &t, vec![a]), // sugar lmao
             Few3::Three(Obj::Num(a), Obj::Num(b), Obj::Num(c)) => {
                 let n1 = into_bigint_ok(a)?;
                 let n2 = into_bigint_ok(b)?;
                 let n3 = into_bigint_ok(c)?;
-                Ok(Obj::Seq(Seq::Stream(Rc::new(Range(
-                    n1,
-                    Some(if n3.is_negative() {
-                        n2 - 1usize
-                    } else {
-                        n2 + 1usize
-                    }),
-                    n3,
-                )))))
+                Ok(Obj::Seq(Seq::Stream(
+                    err_add_name(
+                        Range::new(
+                            n1,
+                            Some(if n3.is_negative() {
+                                n2 - 1usize
+                            } else {
+                                n2 + 1usize
+                            }),
+                            n3,
+                        ),
+                        "to",
+                    )?
+                    .into_stream(),
+                )))
             }
             c => err_add_name(Err(NErr::argument_error_few3(&c)), "to"),
         }
@@ -929,6 +954,93 @@ impl Builtin for Zip {
     }
 }
 
+// Like zip, but a length mismatch is an error rather than silently dropping the extras. Lengths
+// known without walking anything are compared up front; otherwise we notice when some run out
+// before others.
+#[derive(Debug, Clone)]
+struct ZipEq;
+
+impl Builtin for ZipEq {
+    fn run(&self, env: &REnv, args: Vec<Obj>) -> NRes<Obj> {
+        match few(args) {
+            Few::Zero => Err(NErr::argument_error("zip_eq: no args".to_string())),
+            Few::One(a) => Ok(clone_and_part_app_last(self, a)),
+            Few::Many(mut args) => {
+                let mut func = None;
+                let mut iterators: Vec<MutObjIntoIter<'_>> = Vec::new();
+                let mut known_len: Option<(usize, usize)> = None;
+                for arg in args.iter_mut() {
+                    match (arg, &mut func) {
+                        (Obj::Func(f, _), None) => {
+                            func = Some(f.clone());
+                        }
+                        (Obj::Func(..), Some(_)) => Err(NErr::argument_error(
+                            "zip_eq: more than one function".to_string(),
+                        ))?,
+                        (Obj::Seq(s), _) => {
+                            let i = iterators.len();
+                            let len = match &*s {
+                                Seq::Stream(t) => cheap_len(&**t),
+                                t => t.len(),
+                            };
+                            match (len, known_len) {
+                                (Some(n), Some((j, m))) if n != m => {
+                                    return Err(NErr::value_error(format!(
+                                        "zip_eq: argument {} has {} elements, but argument {} has {}",
+                                        j, m, i, n
+                                    )))
+                                }
+                                (Some(n), None) => known_len = Some((i, n)),
+                                _ => {}
+                            }
+                            iterators.push(mut_seq_into_finite_iter(s, "zip_eq")?)
+                        }
+                        (e, _) => {
+                            return Err(NErr::argument_error(format!(
+                                "zip_eq: not iterable: {}",
+                                FmtObj::debug(e)
+                            )))
+                        }
+                    }
+                }
+                if iterators.is_empty() {
+                    Err(NErr::argument_error("zip_eq: zero iterables".to_string()))?
+                }
+                let mut ret = Vec::new();
+                loop {
+                    let batch: Vec<Option<NRes<Obj>>> =
+                        iterators.iter_mut().map(|a| a.next()).collect();
+                    match (
+                        batch.iter().position(Option::is_none),
+                        batch.iter().position(Option::is_some),
+                    ) {
+                        (None, _) => {
+                            let batch = batch.into_iter().flatten().collect::<NRes<Vec<Obj>>>()?;
+                            ret.push(match &func {
+                                Some(f) => f.run(env, batch)?,
+                                None => Obj::list(batch),
+                            })
+                        }
+                        (Some(_), None) => return Ok(Obj::list(ret)),
+                        (Some(i), Some(j)) => {
+                            return Err(NErr::value_error(format!(
+                                "zip_eq: argument {} ended after {} elements, but argument {} has more",
+                                i,
+                                ret.len(),
+                                j
+                            )))
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn builtin_name(&self) -> &str {
+        "zip_eq"
+    }
+}
+
 // self-chainable
 #[derive(Debug, Clone)]
 struct ZipLongest;
@@ -1093,6 +1205,26 @@ impl Builtin for CartesianProduct {
     }
 }
 
+// The function can break to stop early, with the break value as the result (null without one).
+// The accumulator is moved into each call rather than kept around, so the function can update it
+// in place.
+fn fold_rest(
+    env: &REnv,
+    f: &Func,
+    mut cur: Obj,
+    it: impl Iterator<Item = NRes<Obj>>,
+) -> NRes<Obj> {
+    // not sure if any standard fallible rust methods work...
+    for e in it {
+        cur = match f.run(env, vec![cur, e?]) {
+            Ok(r) => r,
+            Err(NErr::Break(r)) => return Ok(r.unwrap_or(Obj::Null)),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(cur)
+}
+
 // takes an optional starting value
 #[derive(Debug, Clone)]
 struct Fold;
@@ -1102,33 +1234,23 @@ impl Builtin for Fold {
         match few3(args) {
             Few3::Zero => Err(NErr::argument_error("fold: no args".to_string())),
             Few3::One(arg) => Ok(clone_and_part_app_2(self, arg)),
-            Few3::Two(mut s, f) => {
+            // also accept the function first, like fold(f, seq) and fold(f, init, seq)
+            Few3::Two(f @ Obj::Func(..), s @ Obj::Seq(_)) | Few3::Two(s, f) => {
+                let mut s = s;
                 let mut it = mut_obj_into_iter(&mut s, "fold")?;
                 match f {
                     Obj::Func(f, _) => match it.next() {
-                        Some(cur0) => {
-                            let mut cur = cur0?;
-                            // not sure if any standard fallible rust methods work...
-                            for e in it {
-                                cur = f.run(env, vec![cur, e?])?;
-                            }
-                            Ok(cur)
-                        }
+                        Some(cur0) => fold_rest(env, &f, cur0?, it),
                         None => Err(NErr::empty_error("fold: empty seq".to_string())),
                     },
                     _ => Err(NErr::type_error("fold: not callable".to_string())),
                 }
             }
-            Few3::Three(mut s, mut cur, f) => {
+            Few3::Three(f @ Obj::Func(..), cur, s @ Obj::Seq(_)) | Few3::Three(s, cur, f) => {
+                let mut s = s;
                 let it = mut_obj_into_iter(&mut s, "fold")?;
                 match f {
-                    Obj::Func(f, _) => {
-                        // not sure if any standard fallible rust methods work...
-                        for e in it {
-                            cur = f.run(env, vec![cur, e?])?;
-                        }
-                        Ok(cur)
-                    }
+                    Obj::Func(f, _) => fold_rest(env, &f, cur, it),
                     _ => Err(NErr::type_error("fold: not callable".to_string())),
                 }
             }
@@ -1207,7 +1329,7 @@ impl Builtin for LazyScan {
                     Obj::Func(f, _) => {
                         match &s {
                             Obj::Seq(Seq::Stream(s)) => Ok(Obj::Seq(Seq::Stream(Rc::new(
-                                ScannedStream(Ok((s.clone_box(), init, f, Rc::clone(env))), None),
+                                ScannedStream(Ok((s.clone_box(), Some(init), f, Rc::clone(env))), None),
                             )))),
                             _ => Err(NErr::type_error("lazy_scan: init not a stream".to_string())),
                         }
@@ -1403,6 +1525,43 @@ impl Builtin for Scan {
         }
     }
 }
+// scanr(f, init, seq), Haskell-style: the suffix accumulations, longest suffix first, ending with
+// init. f gets (acc, element) like scan. Needs the whole sequence, so it's eager; a stream in gives
+// a stream out, but an infinite one is an error.
+#[derive(Debug, Clone)]
+struct ScanRight;
+
+impl Builtin for ScanRight {
+    fn run(&self, env: &REnv, args: Vec<Obj>) -> NRes<Obj> {
+        match few3(args) {
+            Few3::Three(Obj::Func(f, _), init, Obj::Seq(mut s)) => {
+                let is_stream = matches!(s, Seq::Stream(_));
+                let xs = mut_seq_into_finite_iter(&mut s, "scanr")?.collect::<NRes<Vec<Obj>>>()?;
+                let mut cur = init;
+                let mut acc = vec![cur.clone()];
+                for x in xs.into_iter().rev() {
+                    cur = f.run(env, vec![cur, x])?;
+                    acc.push(cur.clone());
+                }
+                acc.reverse();
+                if is_stream {
+                    Ok(Obj::Seq(Seq::Stream(Rc::new(ListStream(Rc::new(acc), 0)))))
+                } else {
+                    Ok(Obj::list(acc))
+                }
+            }
+            Few3::Three(_, _, _) => Err(NErr::type_error(
+                "scanr: expected a function, an initial value, and a sequence".to_string(),
+            )),
+            f => err_add_name(Err(NErr::argument_error_few3(&f)), "scanr"),
+        }
+    }
+
+    fn builtin_name(&self) -> &str {
+        "scanr"
+    }
+}
+
 // takes an optional starting value
 #[derive(Debug, Clone)]
 struct PartialFold;
@@ -1695,7 +1854,9 @@ impl Builtin for Sort {
         match few2(args) {
             Few2::One(Obj::Seq(s)) => Ok(Obj::Seq(multi_sort(s)?)),
             Few2::One(f @ Obj::Func(..)) => Ok(clone_and_part_app_2(self, f)),
-            Few2::Two(Obj::Seq(s), Obj::Func(f, _)) => Ok(Obj::Seq(multi_sort_by(env, f, s)?)),
+            Few2::Two(Obj::Seq(s), Obj::Func(f, _)) | Few2::Two(Obj::Func(f, _), Obj::Seq(s)) => {
+                Ok(Obj::Seq(multi_sort_by(env, f, s)?))
+            }
             _ => Err(NErr::type_error("sort: not number or func".to_string())),
         }
     }
@@ -1705,6 +1866,189 @@ impl Builtin for Sort {
     }
 }
 
+// with just a sequence, gives the pairs themselves, lazily if it's a stream
+#[derive(Debug, Clone)]
+struct Pairwise;
+
+impl Builtin for Pairwise {
+    fn run(&self, env: &REnv, args: Vec<Obj>) -> NRes<Obj> {
+        match few2(args) {
+            Few2::One(Obj::Seq(Seq::Stream(s))) => Ok(Obj::Seq(Seq::Stream(Rc::new(
+                PairwiseStream(s.clone_box(), None),
+            )))),
+            Few2::One(a @ Obj::Seq(_)) => Ok(Obj::list(
+                PairwiseStream(obj_to_stream(a)?, None).collect::<NRes<Vec<Obj>>>()?,
+            )),
+            Few2::One(f) => Ok(clone_and_part_app_2(self, f)),
+            Few2::Two(mut a, Obj::Func(b, _)) => {
+                let it = mut_obj_into_iter(&mut a, "pairwise")?;
+                let mut prev = None;
+                let mut acc = Vec::new();
+                for a in it {
+                    let a = a?;
+                    if let Some(prev) = prev.take() {
+                        acc.push(b.run(env, vec![prev, a.clone()])?);
+                    }
+                    prev = Some(a);
+                }
+                Ok(Obj::list(acc))
+            }
+            Few2::Two(_, _) => Err(NErr::type_error("pairwise: not callable".to_string())),
+            f => err_add_name(Err(NErr::argument_error_few2(&f)), "pairwise"),
+        }
+    }
+
+    fn builtin_name(&self) -> &str {
+        "pairwise"
+    }
+}
+
+// iterate(seed, f) or iterate(seed, f, count); the function can also come first
+#[derive(Debug, Clone)]
+struct IterateBuiltin;
+
+impl Builtin for IterateBuiltin {
+    fn run(&self, env: &REnv, args: Vec<Obj>) -> NRes<Obj> {
+        let (a, b, n) = match few3(args) {
+            Few3::One(a) => return Ok(clone_and_part_app_2(self, a)),
+            Few3::Two(a, b) => (a, b, None),
+            Few3::Three(a, b, Obj::Num(n)) => (a, b, Some(into_bigint_ok(n)?)),
+            c => return err_add_name(Err(NErr::argument_error_few3(&c)), "iterate"),
+        };
+        // a function seed is fine as long as the function comes second
+        let (seed, f) = match (a, b) {
+            (f @ Obj::Func(..), a) if !matches!(a, Obj::Func(..)) => (a, f),
+            (a, f) => (a, f),
+        };
+        let f = match f {
+            Obj::Func(f, _) => f,
+            f => return err_add_name(Err(NErr::argument_error_2(&seed, &f)), "iterate"),
+        };
+        let state = Ok((seed, f, env.clone()));
+        Ok(Obj::Seq(Seq::Stream(match n {
+            None => Rc::new(Iterate(state)),
+            Some(n) => Rc::new(IterateN(state, n)),
+        })))
+    }
+
+    fn builtin_name(&self) -> &str {
+        "iterate"
+    }
+}
+
+// cycle(seq) forever, or cycle(seq, n) for n copies
+#[derive(Debug, Clone)]
+struct CycleBuiltin;
+
+impl Builtin for CycleBuiltin {
+    fn run(&self, _env: &REnv, args: Vec<Obj>) -> NRes<Obj> {
+        match few2(args) {
+            Few2::One(a) => {
+                let ty = type_of(&a);
+                err_add_name(
+                    Cycle::new(to_rc_vec_obj(a)?).map(|c| Obj::Seq(Seq::Stream(Rc::new(c)))),
+                    &format!("cycle({})", ty.name()),
+                )
+            }
+            Few2::Two(a, Obj::Num(n)) => err_add_name(
+                CycleN::new(to_rc_vec_obj(a)?, to_usize_ok(&n)?)
+                    .map(|c| Obj::Seq(Seq::Stream(Rc::new(c)))),
+                "cycle",
+            ),
+            f => err_add_name(Err(NErr::argument_error_few2(&f)), "cycle"),
+        }
+    }
+
+    fn builtin_name(&self) -> &str {
+        "cycle"
+    }
+}
+
+// Brent's algorithm on x0, f(x0), f(f(x0)), ...: the pre-period mu and period lambda. Gives up
+// after limit calls of f, since a function might never repeat.
+fn brent_cycle(env: &REnv, f: &Func, x0: Obj, limit: usize) -> NRes<(usize, usize)> {
+    let mut calls = 0usize;
+    let mut step = |x: Obj| -> NRes<Obj> {
+        if calls >= limit {
+            return Err(NErr::value_error(format!(
+                "no cycle found within {} steps",
+                limit
+            )));
+        }
+        calls += 1;
+        f.run(env, vec![x])
+    };
+    let (mut power, mut lambda) = (1usize, 1usize);
+    let mut tortoise = x0.clone();
+    let mut hare = step(x0.clone())?;
+    while tortoise != hare {
+        if power == lambda {
+            tortoise = hare.clone();
+            power *= 2;
+            lambda = 0;
+        }
+        hare = step(hare)?;
+        lambda += 1;
+    }
+    let mut tortoise = x0.clone();
+    let mut hare = x0;
+    for _ in 0..lambda {
+        hare = step(hare)?;
+    }
+    let mut mu = 0;
+    while tortoise != hare {
+        tortoise = step(tortoise)?;
+        hare = step(hare)?;
+        mu += 1;
+    }
+    Ok((mu, lambda))
+}
+
+// takes an optional offset
+#[derive(Debug, Clone)]
+struct Stride;
+
+fn strided_list(s: &[Obj], stride: usize, offset: usize) -> NRes<Obj> {
+    check_stride(stride, offset)?;
+    Ok(Obj::list(s.iter().skip(offset).step_by(stride).cloned().collect()))
+}
+
+impl Builtin for Stride {
+    fn run(&self, _env: &REnv, args: Vec<Obj>) -> NRes<Obj> {
+        let (a, n, offset) = match few3(args) {
+            Few3::One(a) => return Ok(clone_and_part_app_2(self, a)),
+            Few3::Two(a, Obj::Num(n)) => (a, n, 0),
+            Few3::Three(a, Obj::Num(n), Obj::Num(o)) => (a, n, to_usize_ok(&o)?),
+            c => return err_add_name(Err(NErr::argument_error_few3(&c)), "stride"),
+        };
+        let stride = n.to_isize().ok_or(NErr::index_error(format!(
+            "Stride index out of bounds of isize or non-integer: {:?}",
+            n
+        )))?;
+        if stride <= 0 {
+            return err_add_name(
+                Err(NErr::value_error("stride must be positive".to_string())),
+                "stride",
+            );
+        }
+        let stride = stride as usize;
+        match a {
+            Obj::Seq(Seq::List(s)) => err_add_name(strided_list(&s, stride, offset), "stride"),
+            Obj::Seq(Seq::Stream(s)) => Ok(Obj::Seq(Seq::Stream(Rc::new(err_add_name(
+                StridedStream::new(s.clone_box(), stride, offset),
+                "stride",
+            )?)))),
+            _ => Err(NErr::type_error(
+                "stride first argument is not a sequence".to_string(),
+            )),
+        }
+    }
+
+    fn builtin_name(&self) -> &str {
+        "stride"
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Preposition(String);
 
@@ -1831,7 +2175,8 @@ impl Catamorphism for CataMapped {
 // it. If given only a sequence, runs it with the identity function.
 //
 // Requires the fold to have a valid identity (max/min aren't this)
-// Expects finite sequences only.
+// Expects finite sequences only, and refuses provably infinite ones unless short_circuits says the
+// body might break out partway.
 // body can return Break. But it can't break with null I think.
 
 #[derive(Clone)]
@@ -1839,16 +2184,27 @@ pub struct SeqAndMappedFoldBuiltin {
     name: String,
     identity: Obj,
     body: fn(state: Obj, next: Obj) -> NRes<Obj>,
+    short_circuits: bool,
 }
 standard_three_part_debug!(SeqAndMappedFoldBuiltin);
 
+impl SeqAndMappedFoldBuiltin {
+    fn iter<'a>(&self, s: &'a mut Seq) -> NRes<MutObjIntoIter<'a>> {
+        if self.short_circuits {
+            Ok(mut_seq_into_iter(s))
+        } else {
+            mut_seq_into_finite_iter(s, &self.name)
+        }
+    }
+}
+
 impl Builtin for SeqAndMappedFoldBuiltin {
     fn run(&self, env: &REnv, args: Vec<Obj>) -> NRes<Obj> {
         match few2(args) {
             // partial application, spicy
             Few2::One(Obj::Seq(mut s)) => {
                 let mut state = self.identity.clone();
-                for e in mut_seq_into_iter(&mut s) {
+                for e in self.iter(&mut s)? {
                     state = match (self.body)(state, e?) {
                         Ok(r) => r,
                         Err(NErr::Break(r)) => return Ok(r.unwrap_or(Obj::Null)),
@@ -1858,9 +2214,12 @@ impl Builtin for SeqAndMappedFoldBuiltin {
                 Ok(state)
             }
             Few2::One(f @ Obj::Func(..)) => Ok(clone_and_part_app_2(self, f)),
-            Few2::Two(Obj::Seq(mut s), Obj::Func(f, _)) => {
+            // either order, so all(f, seq) reads naturally; short-circuiting folds walk streams
+            // lazily, so any can find a hit in an infinite stream (but all that never fails won't end)
+            Few2::Two(Obj::Seq(mut s), Obj::Func(f, _))
+            | Few2::Two(Obj::Func(f, _), Obj::Seq(mut s)) => {
                 let mut state = self.identity.clone();
-                for e in mut_seq_into_iter(&mut s) {
+                for e in self.iter(&mut s)? {
                     state = match (self.body)(state, f.run(env, vec![e?])?) {
                         Ok(r) => r,
                         Err(NErr::Break(r)) => return Ok(r.unwrap_or(Obj::Null)),
@@ -2084,7 +2443,7 @@ fn linear_index_isize(xr: Seq, i: isize) -> NRes<Obj> {
             let i = pythonic_index_isize(bs, i)?;
             Ok(soft_from_utf8(bs[i..i + 1].to_vec()))
         }
-        Seq::Stream(v) => v.pythonic_index_isize(i),
+        Seq::Stream(v) => v.nth(i),
         Seq::Dict(..) => Err(NErr::type_error(
             "dict is not a linear sequence".to_string(),
         )),
@@ -2186,10 +2545,19 @@ fn safe_index(xr: Obj, ir: Obj) -> NRes<Obj> {
     }
 }
 
+// where locate finds b in a stream, either equal to it or satisfying it if it's a function
+fn stream_locate(env: &REnv, s: &dyn Stream, b: Obj) -> NRes<Option<usize>> {
+    match b {
+        Obj::Func(f, _) => s.position(&mut |x| Ok(f.run(env, vec![x.clone()])?.truthy())),
+        b => s.position(&mut |x| Ok(x == &b)),
+    }
+}
+
 fn obj_in(a: Obj, b: Obj) -> NRes<bool> {
     match (a, b) {
         (a, Obj::Seq(Seq::Dict(v, _))) => Ok(v.contains_key(&to_key(a)?)),
         (Obj::Seq(Seq::String(s)), Obj::Seq(Seq::String(v))) => Ok((*v).contains(&*s)),
+        (a, Obj::Seq(Seq::Stream(s))) => s.contains(&a),
         (a, Obj::Seq(mut s)) => {
             for e in mut_seq_into_iter(&mut s) {
                 if e? == a {
@@ -2224,18 +2592,28 @@ pub fn simple_eval(code: &str) -> Obj {
     initialize(&mut env);
 
     let e = Rc::new(RefCell::new(env));
+    let _budget = ElementBudgetScope::enter(&e);
     evaluate(&e, &parse(code).unwrap().unwrap()).unwrap()
 }
 
 fn simple_join(mut obj: Obj, joiner: &str) -> NRes<String> {
-    // this might coerce too hard but idk
+    // this might coerce too hard but idk. functions are where we draw the line
     let mut acc = String::new();
     let mut started = false;
-    for arg in mut_obj_into_iter(&mut obj, "join")? {
+    for arg in mut_obj_into_finite_iter(&mut obj, "join")? {
         if started {
             acc += joiner;
         }
-        acc += format!("{}", arg?).as_str();
+        match arg? {
+            arg @ Obj::Func(..) => {
+                return Err(NErr::type_error(format!(
+                    "can't join a non-string: {}",
+                    FmtObj::debug(&arg)
+                )))
+            }
+            arg => std::fmt::Write::write_fmt(&mut acc, format_args!("{}", arg))
+                .expect("writing to a String can't fail"),
+        }
         started = true;
     }
     Ok(acc)
@@ -2253,6 +2631,69 @@ fn to_rc_vec_obj(a: Obj) -> NRes<Rc<Vec<Obj>>> {
     }
 }
 
+fn obj_to_stream(a: Obj) -> NRes<Box<dyn Stream>> {
+    match a {
+        Obj::Seq(Seq::Stream(s)) => Ok(s.clone_box()),
+        a => Ok(Box::new(ListStream(to_rc_vec_obj(a)?, 0))),
+    }
+}
+
+// negative seeds are taken as their two's complement so every i64 is usable
+fn seed_to_u64(seed: &BigInt) -> NRes<u64> {
+    seed.to_u64()
+        .or_else(|| seed.to_i64().map(|x| x as u64))
+        .ok_or_else(|| NErr::value_error(format!("seed must fit in 64 bits: {}", seed)))
+}
+
+// random_range(lo, hi) is one integer from the thread rng; random_range(seed, lo, hi) is a
+// reproducible stream of them
+#[derive(Debug, Clone)]
+struct RandomRange;
+
+impl Builtin for RandomRange {
+    fn run(&self, _env: &REnv, args: Vec<Obj>) -> NRes<Obj> {
+        match few3(args) {
+            Few3::One(a) => Ok(clone_and_part_app_2(self, a)),
+            Few3::Two(Obj::Num(NNum::Int(lo)), Obj::Num(NNum::Int(hi))) => {
+                Ok(Obj::from(rand::thread_rng().gen_bigint_range(&lo, &hi)))
+            }
+            Few3::Three(
+                Obj::Num(NNum::Int(seed)),
+                Obj::Num(NNum::Int(lo)),
+                Obj::Num(NNum::Int(hi)),
+            ) => Ok(Obj::Seq(Seq::Stream(Rc::new(RandomStream::new(
+                seed_to_u64(&seed)?,
+                Some((lo, hi)),
+            )?)))),
+            c => err_add_name(Err(NErr::argument_error_few3(&c)), "random_range"),
+        }
+    }
+
+    fn builtin_name(&self) -> &str {
+        "random_range"
+    }
+}
+
+// For builtins written as a stream: a stream argument gets the stream back, still lazy, and any
+// other sequence is run through the same stream into a list.
+fn lazy_or_list(a: Obj, f: impl FnOnce(Box<dyn Stream>) -> NRes<Box<dyn Stream>>) -> NRes<Obj> {
+    match a {
+        Obj::Seq(Seq::Stream(s)) => Ok(Obj::Seq(Seq::Stream(Rc::from(f(s.clone_box())?)))),
+        a @ Obj::Seq(_) => Ok(Obj::list(f(obj_to_stream(a)?)?.collect::<NRes<Vec<Obj>>>()?)),
+        a => Err(NErr::argument_error_1(&a)),
+    }
+}
+
+// like obj_to_stream, for callers that need to reach the end
+fn finite_stream(a: Obj) -> NRes<Box<dyn Stream>> {
+    let s = obj_to_stream(a)?;
+    if s.is_infinite() == Some(true) {
+        Err(NErr::value_error(format!("{} is infinite, will not terminate", s)))
+    } else {
+        Ok(s)
+    }
+}
+
 fn datetime_to_obj<Tz: TimeZone>(dt: DateTime<Tz>) -> Obj {
     let m = vec![
         ("year", Obj::from(BigInt::from(dt.year()))),
@@ -2335,6 +2776,38 @@ fn sorted<T: PartialOrd>(mut v: Vec<T>) -> NRes<Vec<T>> {
 fn multi_sort(v: Seq) -> NRes<Seq> {
     multi!(v, sorted(v))
 }
+// so total_sort can use the total order on whatever multi! hands it
+trait TotalCmp {
+    fn total_cmp(&self, other: &Self) -> Ordering;
+}
+impl TotalCmp for Obj {
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        total_cmp(self, other)
+    }
+}
+impl TotalCmp for NNum {
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        self.total_cmp_small_nan(other)
+    }
+}
+impl TotalCmp for char {
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+}
+impl TotalCmp for u8 {
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+}
+
+fn total_sorted<T: TotalCmp>(mut v: Vec<T>) -> NRes<Vec<T>> {
+    v.sort_by(|a, b| a.total_cmp(b));
+    Ok(v)
+}
+fn multi_total_sort(v: Seq) -> NRes<Seq> {
+    multi!(v, total_sorted(v))
+}
 fn filtered<T: Clone + Into<Obj>>(env: &REnv, f: Func, v: Vec<T>, neg: bool) -> NRes<Vec<T>> {
     let mut ret = Vec::new();
     for x in v {
@@ -2373,6 +2846,18 @@ fn sorted_by<T: Clone + Into<Obj>>(env: &REnv, f: Func, mut v: Vec<T>) -> NRes<V
 fn multi_sort_by(env: &REnv, f: Func, v: Seq) -> NRes<Seq> {
     multi!(v, sorted_by(env, f, v))
 }
+// each key is computed once; stable, so equal keys keep their order
+fn sorted_by_key<T: Clone + Into<Obj>>(env: &REnv, f: Func, v: Vec<T>) -> NRes<Vec<T>> {
+    let mut keyed = v
+        .into_iter()
+        .map(|x| Ok((f.run(env, vec![x.clone().into()])?, x)))
+        .collect::<NRes<Vec<(Obj, T)>>>()?;
+    keyed.sort_by(|(a, _), (b, _)| total_cmp(a, b));
+    Ok(keyed.into_iter().map(|(_, x)| x).collect())
+}
+fn multi_sort_by_key(env: &REnv, f: Func, v: Seq) -> NRes<Seq> {
+    multi!(v, sorted_by_key(env, f, v))
+}
 fn uniqued<T: Clone + Into<Obj>>(v: Vec<T>) -> NRes<Vec<T>> {
     let mut seen = HashSet::new();
     let mut ret = Vec::new();
@@ -2476,17 +2961,56 @@ fn windowed<T: Clone>(mut it: impl Iterator<Item = NRes<T>>, n: usize) -> NRes<V
     Ok(acc)
 }
 
+// The predicate can `break` to end the output early; `break v` appends v (converted back to the
+// sequence's element type by `from_break`) as the last element.
+fn run_while_pred(f: &Func, env: &REnv, x: Obj) -> NRes<Result<bool, Option<Obj>>> {
+    match f.run(env, vec![x]) {
+        Ok(r) => Ok(Ok(r.truthy())),
+        Err(NErr::Break(v)) => Ok(Err(v)),
+        Err(e) => Err(e),
+    }
+}
+fn finish_with_break<T>(
+    mut acc: Vec<T>,
+    v: Option<Obj>,
+    from_break: impl Fn(Obj) -> NRes<Vec<T>>,
+) -> NRes<Vec<T>> {
+    if let Some(v) = v {
+        acc.extend(from_break(v)?);
+    }
+    Ok(acc)
+}
+fn obj_break(v: Obj) -> NRes<Vec<Obj>> {
+    Ok(vec![v])
+}
+fn char_break(v: Obj) -> NRes<Vec<char>> {
+    match v {
+        Obj::Seq(Seq::String(s)) => Ok(s.chars().collect()),
+        v => Err(NErr::type_error(format!(
+            "break value in string: not a string: {}",
+            FmtObj::debug(&v)
+        ))),
+    }
+}
+fn nnum_break(v: Obj) -> NRes<Vec<NNum>> {
+    Ok(vec![to_nnum(v, "break value in vector")?])
+}
+fn byte_break(v: Obj) -> NRes<Vec<u8>> {
+    Ok(vec![to_byte(v, "break value in bytes")?])
+}
+
 fn take_while_inner<T: Clone + Into<Obj>>(
     mut it: impl Iterator<Item = T>,
     env: &REnv,
     f: Func,
+    from_break: impl Fn(Obj) -> NRes<Vec<T>>,
 ) -> NRes<Vec<T>> {
     let mut acc = Vec::new();
     while let Some(x) = it.next() {
-        if f.run(env, vec![x.clone().into()])?.truthy() {
-            acc.push(x)
-        } else {
-            return Ok(acc);
+        match run_while_pred(&f, env, x.clone().into())? {
+            Ok(true) => acc.push(x),
+            Ok(false) => return Ok(acc),
+            Err(v) => return finish_with_break(acc, v, from_break),
         }
     }
     Ok(acc)
@@ -2495,23 +3019,50 @@ fn stop_at_inner<T: Clone + Into<Obj>>(
     mut it: impl Iterator<Item = T>,
     env: &REnv,
     f: Func,
+    from_break: impl Fn(Obj) -> NRes<Vec<T>>,
 ) -> NRes<Vec<T>> {
     let mut acc = Vec::new();
     while let Some(x) = it.next() {
         acc.push(x.clone());
-        if f.run(env, vec![x.into()])?.truthy() {
-            return Ok(acc);
+        match run_while_pred(&f, env, x.into())? {
+            Ok(false) => {}
+            Ok(true) => return Ok(acc),
+            Err(v) => return finish_with_break(acc, v, from_break),
         }
     }
     Ok(acc)
 }
 
+// Shared by find and find?: the function can go on either side. Lazy, so it stops at the first
+// hit even in an infinite stream; `break` ends the search, and `break v` is taken as the answer.
+fn find_first(env: &REnv, a: Obj, b: Obj, name: &str) -> NRes<Option<Obj>> {
+    let (mut a, f) = match (a, b) {
+        (a, Obj::Func(f, _)) | (Obj::Func(f, _), a) => (a, f),
+        (a, b) => return Err(NErr::argument_error_2(&a, &b)),
+    };
+    let it = mut_obj_into_iter(&mut a, name)?;
+    for x in it {
+        let x = x?;
+        match run_while_pred(&f, env, x.clone())? {
+            Ok(true) => return Ok(Some(x)),
+            Ok(false) => {}
+            Err(v) => return Ok(v),
+        }
+    }
+    Ok(None)
+}
+
 // weird lil guy that doesn't fit
 fn take_while(s: Seq, f: Func, env: &REnv) -> NRes<Obj> {
     match s {
-        Seq::List(mut s) => Ok(Obj::list(take_while_inner(RcVecIter::of(&mut s), env, f)?)),
-        Seq::String(mut s) => Ok(Obj::from(
-            take_while_inner(RcStringIter::of(&mut s), env, f)?
+        Seq::List(mut s) => Ok(Obj::list(take_while_inner(
+            RcVecIter::of(&mut s),
+            env,
+            f,
+            obj_break,
+        )?)),
+        Seq::String(mut s) => Ok(Obj::from(
+            take_while_inner(RcStringIter::of(&mut s), env, f, char_break)?
                 .into_iter()
                 .collect::<String>(),
         )),
@@ -2519,26 +3070,29 @@ fn take_while(s: Seq, f: Func, env: &REnv) -> NRes<Obj> {
             RcHashMapIter::of(&mut s).map(|(k, _v)| key_to_obj(k)),
             env,
             f,
+            obj_break,
         )?)),
         Seq::Vector(mut s) => Ok(Obj::Seq(Seq::Vector(Rc::new(take_while_inner(
             RcVecIter::of(&mut s),
             env,
             f,
+            nnum_break,
         )?)))),
         Seq::Bytes(mut s) => Ok(Obj::Seq(Seq::Bytes(Rc::new(take_while_inner(
             RcVecIter::of(&mut s),
             env,
             f,
+            byte_break,
         )?)))),
         Seq::Stream(s) => {
             let mut acc = Vec::new();
             let mut s = s.clone_box();
             while let Some(x) = s.next() {
                 let x = x?;
-                if f.run(env, vec![x.clone()])?.truthy() {
-                    acc.push(x)
-                } else {
-                    return Ok(Obj::list(acc));
+                match run_while_pred(&f, env, x.clone())? {
+                    Ok(true) => acc.push(x),
+                    Ok(false) => return Ok(Obj::list(acc)),
+                    Err(v) => return Ok(Obj::list(finish_with_break(acc, v, obj_break)?)),
                 }
             }
             Ok(Obj::list(acc))
@@ -2548,9 +3102,14 @@ fn take_while(s: Seq, f: Func, env: &REnv) -> NRes<Obj> {
 // how to remove duplication???
 fn stop_at(s: Seq, f: Func, env: &REnv) -> NRes<Obj> {
     match s {
-        Seq::List(mut s) => Ok(Obj::list(stop_at_inner(RcVecIter::of(&mut s), env, f)?)),
+        Seq::List(mut s) => Ok(Obj::list(stop_at_inner(
+            RcVecIter::of(&mut s),
+            env,
+            f,
+            obj_break,
+        )?)),
         Seq::String(mut s) => Ok(Obj::from(
-            stop_at_inner(RcStringIter::of(&mut s), env, f)?
+            stop_at_inner(RcStringIter::of(&mut s), env, f, char_break)?
                 .into_iter()
                 .collect::<String>(),
         )),
@@ -2558,16 +3117,19 @@ fn stop_at(s: Seq, f: Func, env: &REnv) -> NRes<Obj> {
             RcHashMapIter::of(&mut s).map(|(k, _v)| key_to_obj(k)),
             env,
             f,
+            obj_break,
         )?)),
         Seq::Vector(mut s) => Ok(Obj::Seq(Seq::Vector(Rc::new(stop_at_inner(
             RcVecIter::of(&mut s),
             env,
             f,
+            nnum_break,
         )?)))),
         Seq::Bytes(mut s) => Ok(Obj::Seq(Seq::Bytes(Rc::new(stop_at_inner(
             RcVecIter::of(&mut s),
             env,
             f,
+            byte_break,
         )?)))),
         Seq::Stream(s) => {
             let mut acc = Vec::new();
@@ -2575,8 +3137,10 @@ fn stop_at(s: Seq, f: Func, env: &REnv) -> NRes<Obj> {
             while let Some(x) = s.next() {
                 let x = x?;
                 acc.push(x.clone());
-                if f.run(env, vec![x])?.truthy() {
-                    return Ok(Obj::list(acc));
+                match run_while_pred(&f, env, x)? {
+                    Ok(false) => {}
+                    Ok(true) => return Ok(Obj::list(acc)),
+                    Err(v) => return Ok(Obj::list(finish_with_break(acc, v, obj_break)?)),
                 }
             }
             Ok(Obj::list(acc))
@@ -2584,63 +3148,69 @@ fn stop_at(s: Seq, f: Func, env: &REnv) -> NRes<Obj> {
     }
 }
 
+// A `break` while still dropping means nothing is kept except the break value, if any.
 fn drop_while_inner<T: Clone + Into<Obj>>(
     it: impl Iterator<Item = T>,
     env: &REnv,
     f: Func,
-) -> NRes<impl Iterator<Item = T>> {
+    from_break: impl Fn(Obj) -> NRes<Vec<T>>,
+) -> NRes<Vec<T>> {
     let mut it = it.peekable();
     while let Some(x) = it.peek() {
-        if f.run(env, vec![x.clone().into()])?.truthy() {
-            it.next();
-        } else {
-            return Ok(it);
+        match run_while_pred(&f, env, x.clone().into())? {
+            Ok(true) => {
+                it.next();
+            }
+            Ok(false) => return Ok(it.collect()),
+            Err(v) => return finish_with_break(Vec::new(), v, from_break),
         }
     }
-    Ok(it)
+    Ok(Vec::new())
 }
 
 fn drop_while(s: Seq, f: Func, env: &REnv) -> NRes<Obj> {
     match s {
-        Seq::List(mut s) => Ok(Obj::list(
-            drop_while_inner(RcVecIter::of(&mut s), env, f)?.collect(),
-        )),
+        Seq::List(mut s) => Ok(Obj::list(drop_while_inner(
+            RcVecIter::of(&mut s),
+            env,
+            f,
+            obj_break,
+        )?)),
         Seq::String(mut s) => Ok(Obj::from(
-            drop_while_inner(RcStringIter::of(&mut s), env, f)?.collect::<String>(),
-        )),
-        Seq::Dict(mut s, _def) => Ok(Obj::list(
-            drop_while_inner(
-                RcHashMapIter::of(&mut s).map(|(k, _v)| key_to_obj(k)),
-                env,
-                f,
-            )?
-            .collect(),
+            drop_while_inner(RcStringIter::of(&mut s), env, f, char_break)?
+                .into_iter()
+                .collect::<String>(),
         )),
-        Seq::Vector(mut s) => Ok(Obj::Seq(Seq::Vector(Rc::new(
-            drop_while_inner(RcVecIter::of(&mut s), env, f)?.collect(),
-        )))),
-        Seq::Bytes(mut s) => Ok(Obj::Seq(Seq::Bytes(Rc::new(
-            drop_while_inner(RcVecIter::of(&mut s), env, f)?.collect(),
-        )))),
+        Seq::Dict(mut s, _def) => Ok(Obj::list(drop_while_inner(
+            RcHashMapIter::of(&mut s).map(|(k, _v)| key_to_obj(k)),
+            env,
+            f,
+            obj_break,
+        )?)),
+        Seq::Vector(mut s) => Ok(Obj::Seq(Seq::Vector(Rc::new(drop_while_inner(
+            RcVecIter::of(&mut s),
+            env,
+            f,
+            nnum_break,
+        )?)))),
+        Seq::Bytes(mut s) => Ok(Obj::Seq(Seq::Bytes(Rc::new(drop_while_inner(
+            RcVecIter::of(&mut s),
+            env,
+            f,
+            byte_break,
+        )?)))),
         Seq::Stream(s) => {
-            println!("warning stream drop while expected behaviour doesn't work");
-            let mut t = s.clone_box();
-            while let Some(x) = t.next() {
-                if f.run(env, vec![x?.clone()])?.truthy() {
-                    break;
-                }
-            }
-            Ok(Obj::Seq(Seq::Stream(Rc::from(t))))
-            // Need seperate stream struct for drop_while
-            // Err(NErr::throw("drop_while not implemented for streams".to_string()))
-            // let mut t = s.clone_box();
-            // while let Some(x) = t.peek() {
-            //     let x = x?;
-            //     if f.run(env, vec![x.clone()])?.truthy() {
-            //         t.next();
-            //     }
-            // }
-            // Ok(Obj::Seq(Seq::Stream(Rc::from(t))))
+            let mut t = PeekableStream::new(s.clone_box());
+            while let Some(x) = t.peek() {
+                match run_while_pred(&f, env, x.clone()?)? {
+                    Ok(true) => {
+                        t.next();
+                    }
+                    Ok(false) => break,
+                    Err(v) => return Ok(Obj::list(finish_with_break(Vec::new(), v, obj_break)?)),
+                }
+            }
+            Ok(Obj::Seq(Seq::Stream(Rc::new(t))))
         }
     }
 }
@@ -2822,7 +3392,10 @@ fn json_encode(x: Obj) -> NRes<serde_json::Value> {
             if let Some(t) = x.to_i64() {
                 Ok(serde_json::Value::from(t))
             } else {
-                Ok(serde_json::Value::from(x.to_f64()))
+                // written out digit for digit; JSON numbers have no size limit
+                serde_json::from_str::<serde_json::Number>(&x.to_string())
+                    .map(serde_json::Value::Number)
+                    .map_err(|e| NErr::value_error(format!("json encoding {} failed: {}", x, e)))
             }
         }
         Obj::Num(x) => Ok(serde_json::Value::from(x.to_f64())),
@@ -2837,8 +3410,7 @@ fn json_encode(x: Obj) -> NRes<serde_json::Value> {
                     .collect::<serde_json::Map<String, serde_json::Value>>(),
             )),
             mut s => Ok(serde_json::Value::Array(
-                mut_seq_into_iter(&mut s)
-                    .into_iter()
+                mut_seq_into_finite_iter(&mut s, "json_encode")?
                     .map(|e| e.and_then(json_encode))
                     .collect::<NRes<Vec<serde_json::Value>>>()?,
             )),
@@ -2850,26 +3422,33 @@ fn json_encode(x: Obj) -> NRes<serde_json::Value> {
     }
 }
 
-fn json_decode(v: serde_json::Value) -> Obj {
+fn json_decode(v: serde_json::Value) -> NRes<Obj> {
     match v {
-        serde_json::Value::Null => Obj::Null,
-        serde_json::Value::Bool(x) => Obj::from(x),
-        serde_json::Value::Number(n) => match n.as_i64() {
-            Some(k) => Obj::from(BigInt::from(k)),
-            None => Obj::from(n.as_f64().expect("json number should be f64 or i64")),
-        },
-        serde_json::Value::String(s) => Obj::from(s),
-        serde_json::Value::Array(a) => {
-            Obj::list(a.into_iter().map(json_decode).collect::<Vec<Obj>>())
-        }
-        serde_json::Value::Object(d) => Obj::Seq(Seq::Dict(
+        serde_json::Value::Null => Ok(Obj::Null),
+        serde_json::Value::Bool(x) => Ok(Obj::from(x)),
+        serde_json::Value::Number(n) => match (n.as_i64(), n.to_string().parse::<BigInt>()) {
+            (Some(k), _) => Ok(Obj::from(BigInt::from(k))),
+            (None, Ok(k)) => Ok(Obj::from(k)),
+            (None, Err(_)) => match n.as_f64() {
+                Some(f) if f.is_finite() => Ok(Obj::from(f)),
+                _ => Err(NErr::value_error(format!(
+                    "json decoding failed: number out of range: {}",
+                    n
+                ))),
+            },
+        },
+        serde_json::Value::String(s) => Ok(Obj::from(s)),
+        serde_json::Value::Array(a) => Ok(Obj::list(
+            a.into_iter().map(json_decode).collect::<NRes<Vec<Obj>>>()?,
+        )),
+        serde_json::Value::Object(d) => Ok(Obj::Seq(Seq::Dict(
             Rc::new(
                 d.into_iter()
-                    .map(|(k, v)| (ObjKey::from(k), json_decode(v)))
-                    .collect::<HashMap<ObjKey, Obj>>(),
+                    .map(|(k, v)| Ok((ObjKey::from(k), json_decode(v)?)))
+                    .collect::<NRes<HashMap<ObjKey, Obj>>>()?,
             ),
             None,
-        )),
+        ))),
     }
 }
 
@@ -2976,6 +3555,12 @@ pub fn initialize(env: &mut Env) {
         .unwrap();
     env.insert("false".to_string(), ObjType::Int, Obj::zero())
         .unwrap();
+    env.insert(
+        "primes".to_string(),
+        ObjType::Any,
+        Obj::Seq(Seq::Stream(Rc::new(PrimesStream::new()))),
+    )
+    .unwrap();
     env.insert_builtin(TwoArgBuiltin {
         name: "and'".to_string(),
         body: |a, b| Ok(if a.truthy() { b } else { a }),
@@ -3020,14 +3605,16 @@ pub fn initialize(env: &mut Env) {
         name: "sum".to_string(),
         identity: Obj::zero(),
         body: |s, f| expect_nums_and_vectorize_2(|a, b| Ok(Obj::Num(a + b)), s, f, "inner +"),
+        short_circuits: false,
     });
     env.insert_builtin(SeqAndMappedFoldBuiltin {
         name: "product".to_string(),
         identity: Obj::one(),
         body: |s, f| expect_nums_and_vectorize_2(|a, b| Ok(Obj::Num(a * b)), s, f, "inner *"),
+        short_circuits: false,
     });
-    env.insert_builtin(ComparisonOperator::of("==", |a, b| Ok(a == b)));
-    env.insert_builtin(ComparisonOperator::of("!=", |a, b| Ok(a != b)));
+    env.insert_builtin(ComparisonOperator::of("==", obj_eq));
+    env.insert_builtin(ComparisonOperator::of("!=", |a, b| obj_eq(a, b).map(|r| !r)));
     env.insert_builtin(ComparisonOperator::of("=~", string_match));
     env.insert_builtin(ComparisonOperator::of("!~", |a, b| {
         string_match(a, b).map(|r| !r)
@@ -3280,13 +3867,22 @@ pub fn initialize(env: &mut Env) {
     });
     env.insert_builtin(OneNumBuiltin {
         name: "factorize".to_string(),
-        body: |a| {
-            Ok(Obj::list(
-                nnum::lazy_factorize(into_bigint_ok(a)?)
-                    .into_iter()
-                    .map(|(a, e)| Obj::list(vec![Obj::from(a), Obj::from(e)]))
-                    .collect(),
-            ))
+        body: |a| Ok(Obj::Seq(Seq::Stream(Rc::new(FactorsStream::new(into_bigint_ok(a)?)?)))),
+    });
+    env.insert_builtin(BasicBuiltin {
+        name: "digits".to_string(),
+        body: |_env, args| {
+            let (n, base, msb_first) = match args.as_slice() {
+                [Obj::Num(n)] => (n, BigInt::from(10), false),
+                [Obj::Num(n), Obj::Num(b)] => (n, into_bigint_ok(b.clone())?, false),
+                [Obj::Num(n), Obj::Num(b), flag] => (n, into_bigint_ok(b.clone())?, flag.truthy()),
+                _ => return Err(NErr::argument_error_args(&args)),
+            };
+            Ok(Obj::Seq(Seq::Stream(Rc::new(DigitsStream::new(
+                into_bigint_ok(n.clone())?,
+                base,
+                msb_first,
+            )?))))
         },
     });
     env.insert_builtin(TilBuiltin);
@@ -3321,6 +3917,9 @@ pub fn initialize(env: &mut Env) {
     env.insert_builtin(OneArgBuiltin {
         name: "len".to_string(),
         body: |arg| match arg {
+            Obj::Seq(Seq::Stream(s)) if len_overflows(&*s) => Err(NErr::value_error(
+                "too many elements to count".to_string(),
+            )),
             Obj::Seq(s) => match s.len() {
                 Some(n) => Ok(Obj::from(n)),
                 None => Ok(Obj::from(f64::INFINITY)),
@@ -3331,6 +3930,41 @@ pub fn initialize(env: &mut Env) {
             ))),
         },
     });
+    // Meant to force a mapped stream with the function running on several threads. But every
+    // function and value here is Rc-based, and none of them can be sent to another thread, so for
+    // now this always falls back to forcing on this thread, warning about it once per process.
+    env.insert_builtin(TwoArgBuiltin {
+        name: "pforce".to_string(),
+        body: |a, b| match (a, b) {
+            (Obj::Seq(s), Obj::Num(n)) => {
+                if to_usize_ok(&n)? == 0 {
+                    return Err(NErr::value_error("need at least one thread".to_string()));
+                }
+                static SEQUENTIAL_WARNING: std::sync::Once = std::sync::Once::new();
+                SEQUENTIAL_WARNING.call_once(|| {
+                    eprintln!("\x1b[1;33mWARNING\x1b[0;33m: pforce: function isn't thread-safe, forcing sequentially\x1b[0m")
+                });
+                match s {
+                    Seq::Stream(s) => Ok(Obj::list(s.force()?)),
+                    s => Ok(Obj::Seq(s)),
+                }
+            }
+            (a, b) => Err(NErr::argument_error_2(&a, &b)),
+        },
+    });
+    // like len, but null unless a stream knows its length without being walked, so it's safe
+    // to ask in the middle of iterating; combinatorial streams know how many are left
+    env.insert_builtin(OneArgBuiltin {
+        name: "remaining".to_string(),
+        body: |arg| match arg {
+            Obj::Seq(Seq::Stream(s)) if len_overflows(&*s) => Err(NErr::value_error(
+                "too many elements to count".to_string(),
+            )),
+            Obj::Seq(Seq::Stream(s)) => Ok(cheap_len(&*s).map_or(Obj::Null, Obj::from)),
+            Obj::Seq(s) => Ok(s.len().map_or(Obj::Null, Obj::from)),
+            e => Err(NErr::argument_error_1(&e)),
+        },
+    });
     env.insert_builtin(OneArgBuiltin {
         name: "only".to_string(),
         body: |arg| match arg {
@@ -3392,9 +4026,14 @@ pub fn initialize(env: &mut Env) {
         name: "∉".to_string(),
         body: |a, b| Ok(Obj::from(!obj_in(a, b)?)),
     });
+    // seq contains x, but also contains(x, seq) when only the second argument is a sequence.
+    // stops at the first match, so it only fails to return on an infinite stream without one
     env.insert_builtin(TwoArgBuiltin {
         name: "contains".to_string(),
-        body: |a, b| Ok(Obj::from(obj_in(b, a)?)),
+        body: |a, b| match (a, b) {
+            (a, b @ Obj::Seq(_)) if !matches!(a, Obj::Seq(_)) => Ok(Obj::from(obj_in(a, b)?)),
+            (a, b) => Ok(Obj::from(obj_in(b, a)?)),
+        },
     });
     env.insert_builtin(TwoArgBuiltin {
         name: "∋".to_string(),
@@ -3450,13 +4089,40 @@ pub fn initialize(env: &mut Env) {
             _ => Err(NErr::type_error("not function".to_string())),
         },
     });
-    env.insert_builtin(OneArgBuiltin {
+    env.insert_builtin(BasicBuiltin {
         name: "repeat".to_string(),
-        body: |a| Ok(Obj::Seq(Seq::Stream(Rc::new(Repeat(a))))),
+        body: |_env, args| match few2(args) {
+            Few2::One(a) => Ok(Obj::Seq(Seq::Stream(Rc::new(Repeat(a))))),
+            Few2::Two(a, Obj::Num(n)) => {
+                Ok(Obj::Seq(Seq::Stream(Rc::new(RepeatN(a, to_usize_ok(&n)?)))))
+            }
+            f => Err(NErr::argument_error_few2(&f)),
+        },
     });
-    env.insert_builtin(OneArgBuiltin {
-        name: "cycle".to_string(),
-        body: |a| Ok(Obj::Seq(Seq::Stream(Rc::new(Cycle(to_rc_vec_obj(a)?, 0))))),
+    env.insert_builtin(CycleBuiltin);
+    env.insert_builtin(TwoArgBuiltin {
+        name: "replicate".to_string(),
+        body: |a, b| match (a, b) {
+            (Obj::Num(n), Obj::Seq(Seq::Stream(s))) | (Obj::Seq(Seq::Stream(s)), Obj::Num(n)) => {
+                let r = ReplicateStream::new(s.clone_box(), to_usize_ok(&n)?)?;
+                Ok(Obj::Seq(Seq::Stream(Rc::new(r))))
+            }
+            (Obj::Num(n), s @ Obj::Seq(_)) | (s @ Obj::Seq(_), Obj::Num(n)) => {
+                let r = CycleN::new(to_rc_vec_obj(s)?, to_usize_ok(&n)?)?;
+                Ok(Obj::Seq(Seq::Stream(Rc::new(r))))
+            }
+            (a, b) => Err(NErr::argument_error_2(&a, &b)),
+        },
+    });
+    env.insert_builtin(TwoArgBuiltin {
+        name: "cycle_from".to_string(),
+        body: |a, b| match b {
+            Obj::Num(n) => Ok(Obj::Seq(Seq::Stream(Rc::new(Cycle::from_offset(
+                to_rc_vec_obj(a)?,
+                &into_bigint_ok(n)?,
+            )?)))),
+            b => Err(NErr::argument_error_2(&a, &b)),
+        },
     });
     env.insert_builtin(OneArgBuiltin {
         name: "iota".to_string(),
@@ -3469,12 +4135,36 @@ pub fn initialize(env: &mut Env) {
             e => Err(NErr::argument_error_1(&e)),
         },
     });
-    env.insert_builtin(OneArgBuiltin {
+    env.insert_builtin(BasicBuiltin {
         name: "permutations".to_string(),
+        body: |env, args| match few2(args) {
+            Few2::One(a) => Ok(Obj::Seq(Seq::Stream(Rc::new(Permutations::new(
+                to_rc_vec_obj(a)?,
+            ))))),
+            Few2::Two(a, Obj::Num(n)) => {
+                let v = to_rc_vec_obj(a)?;
+                let k = n
+                    .to_usize()
+                    .ok_or(NErr::value_error("bad permutation length".to_string()))?;
+                Ok(Obj::Seq(Seq::Stream(Rc::new(KPermutations::new(v, k)))))
+            }
+            Few2::Two(a, Obj::Func(f, _)) => {
+                // start from the smallest arrangement so we go through all of them
+                let v = sorted_by(env, f.clone(), to_rc_vec_obj(a)?.to_vec())?;
+                Ok(Obj::Seq(Seq::Stream(Rc::new(ComparatorPermutations(
+                    Ok((Rc::new(v), f, Rc::clone(env))),
+                    false,
+                )))))
+            }
+            f => Err(NErr::argument_error_few2(&f)),
+        },
+    });
+    env.insert_builtin(OneArgBuiltin {
+        name: "derangements".to_string(),
         body: |a| {
-            let v = to_rc_vec_obj(a)?;
-            let iv = Rc::new((0..v.len()).collect());
-            Ok(Obj::Seq(Seq::Stream(Rc::new(Permutations(v, Some(iv))))))
+            Ok(Obj::Seq(Seq::Stream(Rc::new(DerangementsStream::new(
+                to_rc_vec_obj(a)?,
+            )))))
         },
     });
     env.insert_builtin(TwoArgBuiltin {
@@ -3486,30 +4176,81 @@ pub fn initialize(env: &mut Env) {
                     let u = n
                         .to_usize()
                         .ok_or(NErr::value_error("bad combo".to_string()))?;
-                    let iv = Rc::new((0..u).collect());
-                    Ok(Obj::Seq(Seq::Stream(Rc::new(Combinations(v, Some(iv))))))
+                    Ok(Obj::Seq(Seq::Stream(Rc::new(Combinations::new(v, u)))))
+                }
+                b => Err(NErr::argument_error_second(&b)),
+            }
+        },
+    });
+    env.insert_builtin(TwoArgBuiltin {
+        name: "combinations_with_replacement".to_string(),
+        body: |a, b| {
+            let v = to_rc_vec_obj(a)?;
+            match b {
+                Obj::Num(n) => {
+                    let k = n
+                        .to_usize()
+                        .ok_or(NErr::value_error("bad combo".to_string()))?;
+                    Ok(Obj::Seq(Seq::Stream(Rc::new(
+                        CombinationsWithReplacement::new(v, k),
+                    ))))
                 }
                 b => Err(NErr::argument_error_second(&b)),
             }
         },
     });
+    env.insert_builtin(OneArgBuiltin {
+        name: "partitions".to_string(),
+        body: |a| match a {
+            Obj::Num(n) => Ok(Obj::Seq(Seq::Stream(Rc::new(PartitionsStream::new(
+                to_usize_ok(&n)?,
+            ))))),
+            a => Err(NErr::argument_error_1(&a)),
+        },
+    });
+    env.insert_builtin(OneArgBuiltin {
+        name: "set_partitions".to_string(),
+        body: |a| Ok(Obj::Seq(Seq::Stream(Rc::new(SetPartitions::new(to_rc_vec_obj(a)?))))),
+    });
+    env.insert_builtin(BasicBuiltin {
+        name: "compositions".to_string(),
+        body: |_env, args| match few2(args) {
+            Few2::One(Obj::Num(n)) => Ok(Obj::Seq(Seq::Stream(Rc::new(
+                CompositionsStream::new(to_usize_ok(&n)?, None),
+            )))),
+            Few2::Two(Obj::Num(n), Obj::Num(k)) => Ok(Obj::Seq(Seq::Stream(Rc::new(
+                CompositionsStream::new(to_usize_ok(&n)?, Some(to_usize_ok(&k)?)),
+            )))),
+            f => Err(NErr::argument_error_few2(&f)),
+        },
+    });
+    env.insert_builtin(OneArgBuiltin {
+        name: "gray_subsequences".to_string(),
+        body: |a| {
+            Ok(Obj::Seq(Seq::Stream(Rc::new(GrayCodeSubsequences::new(
+                to_rc_vec_obj(a)?,
+            )?))))
+        },
+    });
     env.insert_builtin(OneArgBuiltin {
         name: "subsequences".to_string(),
         body: |a| {
-            let v = to_rc_vec_obj(a)?;
-            let iv = Rc::new(vec![false; v.len()]);
-            Ok(Obj::Seq(Seq::Stream(Rc::new(Subsequences(v, Some(iv))))))
+            Ok(Obj::Seq(Seq::Stream(Rc::new(Subsequences::new(
+                to_rc_vec_obj(a)?,
+            )))))
         },
     });
-    env.insert_builtin(EnvTwoArgBuiltin {
-        name: "iterate".to_string(),
-        body: |env, a, f| match f {
-            Obj::Func(f, _) => Ok(Obj::Seq(Seq::Stream(Rc::new(Iterate(Ok((
-                a,
-                f,
-                env.clone(),
-            ))))))),
-            f => Err(NErr::argument_error_2(&a, &f)),
+    env.insert_builtin(IterateBuiltin);
+    env.insert_builtin(BasicBuiltin {
+        name: "cycle_detect".to_string(),
+        body: |env, args| {
+            let (f, x0, limit) = match few3(args) {
+                Few3::Two(Obj::Func(f, _), x0) => (f, x0, 1_000_000),
+                Few3::Three(Obj::Func(f, _), x0, Obj::Num(n)) => (f, x0, to_usize_ok(&n)?),
+                f => return Err(NErr::argument_error_few3(&f)),
+            };
+            let (mu, lambda) = brent_cycle(env, &f, x0, limit)?;
+            Ok(Obj::list(vec![Obj::from(mu), Obj::from(lambda)]))
         },
     });
     env.insert_builtin(EnvTwoArgBuiltin {
@@ -3536,7 +4277,120 @@ pub fn initialize(env: &mut Env) {
                 }
                 Ok(last)
             },
-            _ => Err(NErr::argument_error("take_last expects stream as first arg and function as second".to_string())),
+            (Obj::Num(n), s @ Obj::Seq(_)) | (s @ Obj::Seq(_), Obj::Num(n)) => {
+                let n = to_usize_ok(&n)?;
+                let mut last = VecDeque::new();
+                for x in finite_stream(s)? {
+                    let x = x?;
+                    if n == 0 {
+                        continue;
+                    }
+                    if last.len() == n {
+                        last.pop_front();
+                    }
+                    last.push_back(x);
+                }
+                Ok(Obj::list(last.into()))
+            }
+            _ => Err(NErr::argument_error("take_last expects a sequence and either a count or a function".to_string())),
+        },
+    });
+    env.insert_builtin(TwoArgBuiltin {
+        name: "rotate".to_string(),
+        body: |a, b| match (a, b) {
+            (a @ Obj::Seq(_), Obj::Num(k)) => {
+                let lazy = matches!(a, Obj::Seq(Seq::Stream(_)));
+                let s = finite_stream(a)?;
+                let n = BigInt::from(s.len().ok_or_else(|| {
+                    NErr::value_error(format!("can't tell the length of {}", s))
+                })?);
+                let k = if n.sign() == num::bigint::Sign::NoSign {
+                    n
+                } else {
+                    (into_bigint_ok(k)? % &n + &n) % &n
+                };
+                // the part from k on, then the part before it
+                let r = ChainStream(VecDeque::from(vec![
+                    Box::new(SkipStream::new(s.clone_box(), k.clone())?) as Box<dyn Stream>,
+                    Box::new(TakeStream::new(s, k)?),
+                ]));
+                if lazy {
+                    Ok(Obj::Seq(Seq::Stream(Rc::new(r))))
+                } else {
+                    Ok(Obj::list(r.collect::<NRes<Vec<Obj>>>()?))
+                }
+            }
+            (a, b) => Err(NErr::argument_error_2(&a, &b)),
+        },
+    });
+    env.insert_builtin(TwoArgBuiltin {
+        name: "drop_last".to_string(),
+        body: |a, b| match (a, b) {
+            (Obj::Num(n), s @ Obj::Seq(_)) | (s @ Obj::Seq(_), Obj::Num(n)) => {
+                let lazy = matches!(s, Obj::Seq(Seq::Stream(_)));
+                let d = DropLastStream(finite_stream(s)?, to_usize_ok(&n)?, VecDeque::new());
+                if lazy {
+                    Ok(Obj::Seq(Seq::Stream(Rc::new(d))))
+                } else {
+                    Ok(Obj::list(d.collect::<NRes<Vec<Obj>>>()?))
+                }
+            }
+            (a, b) => Err(NErr::argument_error_2(&a, &b)),
+        },
+    });
+    env.insert_builtin(BasicBuiltin {
+        name: "linspace".to_string(),
+        body: |_env, args| match args.as_slice() {
+            [Obj::Num(start), Obj::Num(stop), Obj::Num(n)] => {
+                Ok(Obj::Seq(Seq::Stream(Rc::new(LinspaceStream(
+                    to_f64_ok(start)?,
+                    to_f64_ok(stop)?,
+                    to_usize_ok(n)?,
+                    0,
+                )))))
+            }
+            _ => Err(NErr::argument_error_args(&args)),
+        },
+    });
+    env.insert_builtin(OneArgBuiltin {
+        name: "binomial_row".to_string(),
+        body: |a| match a {
+            Obj::Num(n) => Ok(Obj::Seq(Seq::Stream(Rc::new(BinomialRowStream::new(
+                to_usize_ok(&n)?,
+            ))))),
+            a => Err(NErr::argument_error_1(&a)),
+        },
+    });
+    env.insert_builtin(OneArgBuiltin {
+        name: "collatz".to_string(),
+        body: |a| match a {
+            Obj::Num(NNum::Int(n)) => Ok(Obj::Seq(Seq::Stream(Rc::new(CollatzStream::new(n)?)))),
+            a => Err(NErr::argument_error_1(&a)),
+        },
+    });
+    env.insert_builtin(BasicBuiltin {
+        name: "tabulate".to_string(),
+        body: |env, args| match few2(args) {
+            Few2::One(Obj::Func(f, _)) => Ok(Obj::Seq(Seq::Stream(Rc::new(TabulateStream(
+                Ok((f, Rc::clone(env))),
+                BigInt::from(0),
+                None,
+            ))))),
+            Few2::Two(Obj::Func(f, _), Obj::Num(n)) => Ok(Obj::Seq(Seq::Stream(Rc::new(
+                TabulateStream(Ok((f, Rc::clone(env))), BigInt::from(0), Some(into_bigint_ok(n)?)),
+            )))),
+            f => Err(NErr::argument_error_few2(&f)),
+        },
+    });
+    env.insert_builtin(EnvTwoArgBuiltin {
+        name: "unfold".to_string(),
+        body: |env, a, b| match (a, b) {
+            (Obj::Func(f, _), seed) => Ok(Obj::Seq(Seq::Stream(Rc::new(UnfoldStream(Ok((
+                seed,
+                f,
+                Rc::clone(env),
+            ))))))),
+            (a, b) => Err(NErr::argument_error_2(&a, &b)),
         },
     });
     // haxx!!!
@@ -3549,7 +4403,123 @@ pub fn initialize(env: &mut Env) {
             (a, b) => Err(NErr::argument_error_2(&a, &b)),
         },
     });
+    env.insert_builtin(EnvTwoArgBuiltin {
+        name: "group_by".to_string(),
+        body: |env, a, b| match (a, b) {
+            (Obj::Func(f, _), s @ Obj::Seq(_)) | (s @ Obj::Seq(_), Obj::Func(f, _)) => {
+                lazy_or_list(s, |s| Ok(Box::new(GroupByStream(Ok((s, f, Rc::clone(env))), None))))
+            }
+            (a, b) => Err(NErr::argument_error_2(&a, &b)),
+        },
+    });
+    env.insert_builtin(EnvTwoArgBuiltin {
+        name: "split_when".to_string(),
+        body: |env, a, b| match (a, b) {
+            (Obj::Func(f, _), s @ Obj::Seq(_)) | (s @ Obj::Seq(_), Obj::Func(f, _)) => {
+                lazy_or_list(s, |s| Ok(Box::new(SplitWhenStream(Ok((s, f, Rc::clone(env))), None))))
+            }
+            (a, b) => Err(NErr::argument_error_2(&a, &b)),
+        },
+    });
+    env.insert_builtin(BasicBuiltin {
+        name: "chain".to_string(),
+        body: |_env, args| {
+            Ok(Obj::Seq(Seq::Stream(Rc::new(ChainStream(
+                args.into_iter()
+                    .map(obj_to_stream)
+                    .collect::<NRes<VecDeque<Box<dyn Stream>>>>()?,
+            )))))
+        },
+    });
+    env.insert_builtin(BasicBuiltin {
+        name: "interleave".to_string(),
+        body: |_env, args| {
+            Ok(Obj::Seq(Seq::Stream(Rc::new(InterleaveStream(
+                args.into_iter()
+                    .map(|a| Ok(Some(obj_to_stream(a)?)))
+                    .collect::<NRes<Vec<Option<Box<dyn Stream>>>>>()?,
+                0,
+            )))))
+        },
+    });
+    // 0 always shows streams' structure
+    env.insert_builtin(BasicBuiltin {
+        name: "set_stream_preview".to_string(),
+        body: |env, args| match args.as_slice() {
+            [Obj::Num(n)] => {
+                let n = to_usize_ok(n)?;
+                try_borrow_nres(env, "set_stream_preview", "")?
+                    .mut_top_env(|t| t.stream_preview = n);
+                Ok(Obj::Null)
+            }
+            _ => Err(NErr::argument_error_args(&args)),
+        },
+    });
+    env.insert_builtin(BasicBuiltin {
+        name: "set_element_budget".to_string(),
+        body: |env, args| {
+            let n = match args.as_slice() {
+                [Obj::Null] => 0,
+                [Obj::Num(n)] => to_usize_ok(n)?,
+                _ => return Err(NErr::argument_error_args(&args)),
+            };
+            set_element_budget(&*try_borrow_nres(env, "set_element_budget", "")?, n);
+            Ok(Obj::Null)
+        },
+    });
+    env.insert_builtin(OneArgBuiltin {
+        name: "is_infinite".to_string(),
+        body: |a| match a {
+            Obj::Seq(Seq::Stream(s)) => Ok(match s.is_infinite() {
+                Some(b) => Obj::from(b),
+                None => Obj::Null,
+            }),
+            Obj::Seq(_) => Ok(Obj::from(false)),
+            a => Err(NErr::argument_error_1(&a)),
+        },
+    });
+    env.insert_builtin(OneArgBuiltin {
+        name: "cache".to_string(),
+        body: |a| match a {
+            Obj::Seq(Seq::Stream(s)) => Ok(Obj::Seq(Seq::Stream(Rc::new(CachedStream::new(
+                s.clone_box(),
+            ))))),
+            // everything else is already in memory
+            a @ Obj::Seq(_) => Ok(a),
+            e => Err(NErr::argument_error_1(&e)),
+        },
+    });
+    // n independent cursors over one stream, like itertools.tee: they share a cache, so the
+    // source is only pulled once per element however many of them read it
+    env.insert_builtin(TwoArgBuiltin {
+        name: "tee".to_string(),
+        body: |a, b| match (a, b) {
+            (Obj::Seq(Seq::Stream(s)), Obj::Num(n)) => {
+                let c = CachedStream::new(s.clone_box());
+                Ok(Obj::list(
+                    (0..to_usize_ok(&n)?)
+                        .map(|_| Obj::Seq(Seq::Stream(Rc::new(c.clone()))))
+                        .collect(),
+                ))
+            }
+            // everything else is already in memory
+            (a @ Obj::Seq(_), Obj::Num(n)) => Ok(Obj::list(vec![a; to_usize_ok(&n)?])),
+            (a, b) => Err(NErr::argument_error_2(&a, &b)),
+        },
+    });
+    env.insert_builtin(ScanRight);
     env.insert_builtin(LazyScan);
+    env.insert_builtin(EnvTwoArgBuiltin {
+        name: "scan1".to_string(),
+        body: |env, a, b| match (a, b) {
+            (Obj::Func(f, _), s @ Obj::Seq(_)) | (s @ Obj::Seq(_), Obj::Func(f, _)) => {
+                lazy_or_list(s, |s| {
+                    Ok(Box::new(ScannedStream(Ok((s, None, f, Rc::clone(env))), None)))
+                })
+            }
+            (a, b) => Err(NErr::argument_error_2(&a, &b)),
+        },
+    });
     env.insert_builtin(LazyPartialFold);
     // env.insert_builtin(EnvTwoArgBuiltin {
     //     name: "lazy_scan".to_string(),
@@ -3624,9 +4594,12 @@ pub fn initialize(env: &mut Env) {
     env.insert_builtin(OneArgBuiltin {
         name: "flatten".to_string(),
         body: |mut a| {
+            if let Obj::Seq(Seq::Stream(s)) = a {
+                return Ok(Obj::Seq(Seq::Stream(Rc::new(FlattenStream(s.clone_box(), None)))));
+            }
             let mut acc = Vec::new();
             for e in mut_obj_into_iter(&mut a, "flatten (outer)")? {
-                for k in mut_obj_into_iter(&mut e?, "flatten (inner)")? {
+                for k in mut_obj_into_finite_iter(&mut e?, "flatten (inner)")? {
                     acc.push(k?);
                 }
             }
@@ -3674,30 +4647,10 @@ pub fn initialize(env: &mut Env) {
         },
     });
     // name from python itertools?
-    env.insert_builtin(EnvTwoArgBuiltin {
-        name: "pairwise".to_string(),
-        body: |env, mut a, b| {
-            let it = mut_obj_into_iter(&mut a, "pairwise")?;
-            match b {
-                Obj::Func(b, _) => {
-                    let mut prev = None;
-                    let mut acc = Vec::new();
-                    for a in it {
-                        let a = a?;
-                        match prev.take() {
-                            Some(prev) => acc.push(b.run(env, vec![prev, a.clone()])?),
-                            None => (),
-                        };
-                        prev = Some(a);
-                    }
-                    Ok(Obj::list(acc))
-                }
-                _ => Err(NErr::type_error("not callable".to_string())),
-            }
-        },
-    });
+    env.insert_builtin(Pairwise);
     env.insert_builtin(Zip);
     env.insert_builtin(ZipLongest);
+    env.insert_builtin(ZipEq);
     env.insert_builtin(Parallel);
     env.insert_builtin(Fanout);
     env.insert_builtin(EnvOneArgBuiltin {
@@ -3705,21 +4658,28 @@ pub fn initialize(env: &mut Env) {
         body: |_env, a| {
             let mut v = to_rc_vec_obj(a)?;
             let v = Rc::make_mut(&mut v);
-            let mut iterators: Vec<MutObjIntoIter<'_>> = Vec::new();
-            for arg in v.iter_mut() {
-                iterators.push(mut_obj_into_iter(arg, "zip")?)
-            }
-            let mut ret = Vec::new();
-            loop {
-                let batch = iterators
-                    .iter_mut()
-                    .flat_map(|a| a.next())
-                    .collect::<NRes<Vec<Obj>>>()?;
-                if batch.is_empty() {
-                    return Ok(Obj::list(ret));
+            // read every row up front so ragged input is an error, not a truncated result
+            let mut rows: Vec<std::vec::IntoIter<Obj>> = Vec::with_capacity(v.len());
+            for (i, arg) in v.iter_mut().enumerate() {
+                let row = mut_obj_into_finite_iter(arg, "transpose")?.collect::<NRes<Vec<Obj>>>()?;
+                if let Some(first) = rows.first().map(ExactSizeIterator::len) {
+                    if row.len() != first {
+                        return Err(NErr::value_error(format!(
+                            "row {} has length {}, but row 0 has length {}",
+                            i,
+                            row.len(),
+                            first
+                        )));
+                    }
                 }
-                ret.push(Obj::list(batch))
+                rows.push(row.into_iter());
             }
+            let width = rows.first().map_or(0, |r| r.len());
+            Ok(Obj::list(
+                (0..width)
+                    .map(|_| Obj::list(rows.iter_mut().flat_map(|r| r.next()).collect()))
+                    .collect(),
+            ))
         },
     });
     env.insert_builtin(EnvTwoArgBuiltin {
@@ -3742,8 +4702,13 @@ pub fn initialize(env: &mut Env) {
     });
     env.insert_builtin(EnvTwoArgBuiltin {
         name: "partition".to_string(),
-        body: |env, mut a, b| {
-            let it = mut_obj_into_iter(&mut a, "partition")?;
+        body: |env, a, b| {
+            // either order; both halves are built in one pass, so the input has to end
+            let (mut a, b) = match (a, b) {
+                (f @ Obj::Func(..), s @ Obj::Seq(_)) => (s, f),
+                ab => ab,
+            };
+            let it = mut_obj_into_finite_iter(&mut a, "partition")?;
             match b {
                 Obj::Func(b, _) => {
                     let mut acc_t = Vec::new();
@@ -3772,44 +4737,220 @@ pub fn initialize(env: &mut Env) {
                 Ok(s)
             }
         },
+        short_circuits: true,
+    });
+    env.insert_builtin(SeqAndMappedFoldBuiltin {
+        name: "all".to_string(),
+        identity: Obj::from(true),
+        body: |s, f| {
+            if !f.truthy() {
+                Err(NErr::Break(Some(Obj::from(false))))
+            } else {
+                Ok(s)
+            }
+        },
+        short_circuits: true,
+    });
+    env.insert_builtin(Count);
+    env.insert_builtin(OneArgBuiltin {
+        name: "count_elements".to_string(),
+        body: |arg| match arg {
+            Obj::Seq(Seq::Stream(s)) => Ok(Obj::from(Stream::count(&*s)?)),
+            Obj::Seq(Seq::String(s)) => Ok(Obj::from(s.chars().count())),
+            Obj::Seq(s) => Ok(Obj::from(s.len().unwrap_or(0))),
+            a => Err(NErr::argument_error_1(&a)),
+        },
+    });
+    env.insert_builtin(Group { strict: false });
+    env.insert_builtin(Group { strict: true });
+    env.insert_builtin(Merge);
+    env.insert_builtin(OneArgBuiltin {
+        name: "unique".to_string(),
+        body: |a| match a {
+            Obj::Seq(Seq::Stream(s)) => Ok(Obj::Seq(Seq::Stream(Rc::new(UniqueStream(
+                s.clone_box(),
+                HashSet::new(),
+            ))))),
+            Obj::Seq(s) => Ok(Obj::Seq(multi_unique(s)?)),
+            a => Err(NErr::argument_error_1(&a)),
+        },
+    });
+    env.insert_builtin(TwoArgBuiltin {
+        name: "window".to_string(),
+        body: |a, b| match (a, b) {
+            (Obj::Seq(s), Obj::Num(n)) => match to_usize_ok(&n)? {
+                0 => Err(NErr::value_error("can't window 0".to_string())),
+                n => Ok(Obj::list(multi_window(s, n)?)),
+            },
+            _ => Err(NErr::value_error("not number".to_string())),
+        },
+    });
+    env.insert_builtin(TwoArgBuiltin {
+        name: "sliding_max".to_string(),
+        body: |a, b| match (a, b) {
+            (Obj::Seq(Seq::Stream(s)), Obj::Num(n)) => Ok(Obj::Seq(Seq::Stream(Rc::new(
+                SlidingMaxStream::new(s.clone_box(), to_usize_ok(&n)?)?,
+            )))),
+            (a @ Obj::Seq(_), Obj::Num(n)) => Ok(Obj::list(
+                SlidingMaxStream::new(obj_to_stream(a)?, to_usize_ok(&n)?)?
+                    .collect::<NRes<Vec<Obj>>>()?,
+            )),
+            (a, b) => Err(NErr::argument_error_2(&a, &b)),
+        },
+    });
+    env.insert_builtin(BasicBuiltin {
+        name: "window_reduce".to_string(),
+        body: |env, args| match few3(args) {
+            Few3::Three(Obj::Num(w), Obj::Func(f, _), s @ Obj::Seq(_)) => {
+                let w = to_usize_ok(&w)?;
+                lazy_or_list(s, |s| Ok(Box::new(WindowReduceStream::new(s, w, f, Rc::clone(env))?)))
+            }
+            f => Err(NErr::argument_error_few3(&f)),
+        },
+    });
+    env.insert_builtin(TwoArgBuiltin {
+        name: "chunk_by_size".to_string(),
+        body: |a, b| match (a, b) {
+            (Obj::Num(n), s @ Obj::Seq(_)) | (s @ Obj::Seq(_), Obj::Num(n)) => {
+                let n = to_usize_ok(&n)?;
+                lazy_or_list(s, |s| Ok(Box::new(ChunkBySizeStream(s, n, None))))
+            }
+            (a, b) => Err(NErr::argument_error_2(&a, &b)),
+        },
+    });
+    env.insert_builtin(TwoArgBuiltin {
+        name: "merge_sorted".to_string(),
+        body: |a, b| {
+            Ok(Obj::Seq(Seq::Stream(Rc::new(MergeSortedStream(
+                obj_to_stream(a)?,
+                obj_to_stream(b)?,
+                None,
+                None,
+            )))))
+        },
+    });
+    env.insert_builtin(TwoArgBuiltin {
+        name: "intersect_sorted".to_string(),
+        body: |a, b| {
+            Ok(Obj::Seq(Seq::Stream(Rc::new(SortedSetOpStream(
+                obj_to_stream(a)?,
+                obj_to_stream(b)?,
+                None,
+                true,
+            )))))
+        },
+    });
+    env.insert_builtin(TwoArgBuiltin {
+        name: "diff_sorted".to_string(),
+        body: |a, b| {
+            Ok(Obj::Seq(Seq::Stream(Rc::new(SortedSetOpStream(
+                obj_to_stream(a)?,
+                obj_to_stream(b)?,
+                None,
+                false,
+            )))))
+        },
+    });
+    env.insert_builtin(EnvTwoArgBuiltin {
+        name: "group_all".to_string(),
+        body: |env, a, b| match (a, b) {
+            (Obj::Seq(s), Obj::Func(f, _)) => Ok(Obj::list(multi_group_all_with(env, f, s)?)),
+            _ => Err(NErr::value_error("not number".to_string())),
+        },
+    });
+    env.insert_builtin(TwoArgBuiltin {
+        name: "intersperse".to_string(),
+        body: |sep, a| match a {
+            a @ Obj::Seq(_) => lazy_or_list(a, |s| Ok(Box::new(IntersperseStream::new(s, sep)))),
+            a => Err(NErr::argument_error_2(&sep, &a)),
+        },
+    });
+    env.insert_builtin(EnvTwoArgBuiltin {
+        name: "indices_where".to_string(),
+        body: |env, a, b| match (a, b) {
+            (Obj::Func(f, _), s @ Obj::Seq(_)) | (s @ Obj::Seq(_), Obj::Func(f, _)) => {
+                lazy_or_list(s, |s| {
+                    Ok(Box::new(IndicesWhereStream(Ok((s, f, Rc::clone(env))), BigInt::from(0))))
+                })
+            }
+            (a, b) => Err(NErr::argument_error_2(&a, &b)),
+        },
+    });
+    env.insert_builtin(EnvTwoArgBuiltin {
+        name: "intersperse_with".to_string(),
+        body: |env, a, b| match (a, b) {
+            (Obj::Func(f, _), s @ Obj::Seq(_)) | (s @ Obj::Seq(_), Obj::Func(f, _)) => {
+                lazy_or_list(s, |s| {
+                    let inner = PeekableStream::new(s);
+                    Ok(Box::new(IntersperseWithStream(Ok((inner, f, Rc::clone(env))), None)))
+                })
+            }
+            (a, b) => Err(NErr::argument_error_2(&a, &b)),
+        },
+    });
+    env.insert_builtin(OneArgBuiltin {
+        name: "dedup".to_string(),
+        body: |a| lazy_or_list(a, |s| Ok(Box::new(DedupStream(s, None)))),
+    });
+    env.insert_builtin(EnvTwoArgBuiltin {
+        name: "dedup_by".to_string(),
+        body: |env, a, b| match (a, b) {
+            (Obj::Func(f, _), s @ Obj::Seq(_)) | (s @ Obj::Seq(_), Obj::Func(f, _)) => {
+                lazy_or_list(s, |s| Ok(Box::new(DedupByStream(Ok((s, f, Rc::clone(env))), None))))
+            }
+            (a, b) => Err(NErr::argument_error_2(&a, &b)),
+        },
     });
-    env.insert_builtin(SeqAndMappedFoldBuiltin {
-        name: "all".to_string(),
-        identity: Obj::from(true),
-        body: |s, f| {
-            if !f.truthy() {
-                Err(NErr::Break(Some(Obj::from(false))))
-            } else {
-                Ok(s)
-            }
+    env.insert_builtin(OneArgBuiltin {
+        name: "running_min".to_string(),
+        body: |a| {
+            lazy_or_list(a, |s| Ok(Box::new(RunningExtremumStream(s, None, Ordering::Less))))
         },
     });
-    env.insert_builtin(Count);
-    env.insert_builtin(Group { strict: false });
-    env.insert_builtin(Group { strict: true });
-    env.insert_builtin(Merge);
     env.insert_builtin(OneArgBuiltin {
-        name: "unique".to_string(),
-        body: |a| match a {
-            Obj::Seq(s) => Ok(Obj::Seq(multi_unique(s)?)),
-            a => Err(NErr::argument_error_1(&a)),
+        name: "running_max".to_string(),
+        body: |a| {
+            lazy_or_list(a, |s| Ok(Box::new(RunningExtremumStream(s, None, Ordering::Greater))))
         },
     });
-    env.insert_builtin(TwoArgBuiltin {
-        name: "window".to_string(),
-        body: |a, b| match (a, b) {
-            (Obj::Seq(s), Obj::Num(n)) => match to_usize_ok(&n)? {
-                0 => Err(NErr::value_error("can't window 0".to_string())),
-                n => Ok(Obj::list(multi_window(s, n)?)),
-            },
-            _ => Err(NErr::value_error("not number".to_string())),
+    env.insert_builtin(OneArgBuiltin {
+        name: "prefix_sums".to_string(),
+        body: |a| lazy_or_list(a, |s| Ok(Box::new(PrefixSumsStream(s, None)))),
+    });
+    env.insert_builtin(OneArgBuiltin {
+        name: "run_length".to_string(),
+        body: |a| lazy_or_list(a, |s| Ok(Box::new(GroupRunsStream(s, None)))),
+    });
+    // the longest run of equal neighbors, in one pass without building the runs
+    env.insert_builtin(OneArgBuiltin {
+        name: "longest_run".to_string(),
+        body: |mut a| {
+            let (mut best, mut cur, mut prev) = (0usize, 0usize, None);
+            for x in mut_obj_into_finite_iter(&mut a, "longest_run")? {
+                let x = x?;
+                cur = if prev.as_ref() == Some(&x) { cur + 1 } else { 1 };
+                best = best.max(cur);
+                prev = Some(x);
+            }
+            Ok(Obj::from(best))
         },
     });
     env.insert_builtin(EnvTwoArgBuiltin {
-        name: "group_all".to_string(),
+        name: "longest_run_where".to_string(),
         body: |env, a, b| match (a, b) {
-            (Obj::Seq(s), Obj::Func(f, _)) => Ok(Obj::list(multi_group_all_with(env, f, s)?)),
-            _ => Err(NErr::value_error("not number".to_string())),
+            (Obj::Func(f, _), mut s @ Obj::Seq(_)) | (mut s @ Obj::Seq(_), Obj::Func(f, _)) => {
+                let (mut best, mut cur) = (0usize, 0usize);
+                for x in mut_obj_into_finite_iter(&mut s, "longest_run_where")? {
+                    if f.run(env, vec![x?])?.truthy() {
+                        cur += 1;
+                        best = best.max(cur);
+                    } else {
+                        cur = 0;
+                    }
+                }
+                Ok(Obj::from(best))
+            }
+            (a, b) => Err(NErr::argument_error_2(&a, &b)),
         },
     });
     env.insert_builtin(OneArgBuiltin {
@@ -3840,7 +4981,7 @@ pub fn initialize(env: &mut Env) {
             )))
         },
     });
-    env.insert_builtin(Fold);
+    env.insert_builtin_with_alias(Fold, "reduce");
     env.insert_builtin(Accumulate);
     env.insert_builtin(Then);
     env.insert_builtin(If);
@@ -3864,64 +5005,107 @@ pub fn initialize(env: &mut Env) {
             Err(e) => Err(e),
         },
     });
-    env.insert_builtin(Extremum {
-        name: "max".to_string(),
-        bias: Ordering::Greater,
+    env.insert_builtin_with_alias(
+        Extremum {
+            name: "max".to_string(),
+            bias: Ordering::Greater,
+        },
+        "maximum",
+    );
+    env.insert_builtin_with_alias(
+        Extremum {
+            name: "min".to_string(),
+            bias: Ordering::Less,
+        },
+        "minimum",
+    );
+    env.insert_builtin(EnvTwoArgBuiltin {
+        name: "min_by_key".to_string(),
+        body: |env, a, b| extremum_by_key(env, a, b, Ordering::Less, "min_by_key"),
+    });
+    env.insert_builtin(EnvTwoArgBuiltin {
+        name: "max_by_key".to_string(),
+        body: |env, a, b| extremum_by_key(env, a, b, Ordering::Greater, "max_by_key"),
+    });
+    env.insert_builtin(EnvOneArgBuiltin {
+        name: "argmin".to_string(),
+        body: |env, a| arg_extremum(env, a, None, Ordering::Less, "argmin"),
+    });
+    env.insert_builtin(EnvOneArgBuiltin {
+        name: "argmax".to_string(),
+        body: |env, a| arg_extremum(env, a, None, Ordering::Greater, "argmax"),
+    });
+    env.insert_builtin(EnvTwoArgBuiltin {
+        name: "argmin_by_key".to_string(),
+        body: |env, a, b| match (a, b) {
+            (a, Obj::Func(f, _)) | (Obj::Func(f, _), a) => {
+                arg_extremum(env, a, Some(f), Ordering::Less, "argmin_by_key")
+            }
+            (a, b) => Err(NErr::argument_error_2(&a, &b)),
+        },
     });
-    env.insert_builtin(Extremum {
-        name: "min".to_string(),
-        bias: Ordering::Less,
+    env.insert_builtin(EnvTwoArgBuiltin {
+        name: "argmax_by_key".to_string(),
+        body: |env, a, b| match (a, b) {
+            (a, Obj::Func(f, _)) | (Obj::Func(f, _), a) => {
+                arg_extremum(env, a, Some(f), Ordering::Greater, "argmax_by_key")
+            }
+            (a, b) => Err(NErr::argument_error_2(&a, &b)),
+        },
     });
     env.insert_builtin(BasicBuiltin {
         name: "print".to_string(),
         body: |env, args| {
-            try_borrow_nres(env, "print", &format!("{}", args.len()))?
-                .mut_top_env(|t| -> io::Result<()> {
-                    let mut started = false;
-                    for arg in args.iter() {
-                        if started {
-                            write!(t.output, " ")?;
-                        }
-                        started = true;
-                        write!(t.output, "{}", arg)?;
+            let env = try_borrow_nres(env, "print", &format!("{}", args.len()))?;
+            let flags = env.fmt_flags();
+            env.mut_top_env(|t| -> io::Result<()> {
+                let mut started = false;
+                for arg in args.iter() {
+                    if started {
+                        write!(t.output, " ")?;
                     }
-                    writeln!(t.output, "")?;
-                    Ok(())
-                })
-                .map_err(|e| NErr::io_error(format!("writing {}", e)))?;
+                    started = true;
+                    write!(t.output, "{}", FmtObj(arg, &flags))?;
+                }
+                writeln!(t.output)?;
+                Ok(())
+            })
+            .map_err(|e| NErr::io_error(format!("writing {}", e)))?;
             Ok(args.first().map(Obj::clone).unwrap_or(Obj::Null))
         },
     });
     env.insert_builtin(BasicBuiltin {
         name: "write".to_string(),
         body: |env, args| {
-            try_borrow_nres(env, "write", &format!("{}", args.len()))?
-                .mut_top_env(|t| -> io::Result<()> {
-                    for arg in args.iter() {
-                        write!(t.output, "{}", arg)?;
-                    }
-                    Ok(())
-                })
-                .map_err(|e| NErr::io_error(format!("writing {}", e)))?;
+            let env = try_borrow_nres(env, "write", &format!("{}", args.len()))?;
+            let flags = env.fmt_flags();
+            env.mut_top_env(|t| -> io::Result<()> {
+                for arg in args.iter() {
+                    write!(t.output, "{}", FmtObj(arg, &flags))?;
+                }
+                Ok(())
+            })
+            .map_err(|e| NErr::io_error(format!("writing {}", e)))?;
             Ok(Obj::Null)
         },
     });
     env.insert_builtin(BasicBuiltin {
         name: "echo".to_string(),
         body: |env, args| {
-            try_borrow_nres(env, "echo", &format!("{}", args.len()))?
-                .mut_top_env(|t| -> io::Result<()> {
-                    let mut started = false;
-                    for arg in args.iter() {
-                        if started {
-                            write!(t.output, " ")?;
-                        }
-                        started = true;
-                        write!(t.output, "{}", arg)?;
+            let env = try_borrow_nres(env, "echo", &format!("{}", args.len()))?;
+            let flags = env.fmt_flags();
+            env.mut_top_env(|t| -> io::Result<()> {
+                let mut started = false;
+                for arg in args.iter() {
+                    if started {
+                        write!(t.output, " ")?;
                     }
-                    Ok(())
-                })
-                .map_err(|e| NErr::io_error(format!("writing {}", e)))?;
+                    started = true;
+                    write!(t.output, "{}", FmtObj(arg, &flags))?;
+                }
+                Ok(())
+            })
+            .map_err(|e| NErr::io_error(format!("writing {}", e)))?;
             Ok(Obj::Null)
         },
     });
@@ -4054,25 +5238,35 @@ pub fn initialize(env: &mut Env) {
 
     env.insert_builtin(TwoArgBuiltin {
         name: "join".to_string(),
-        body: |mut a, b| match b {
-            Obj::Seq(Seq::Bytes(b)) => {
-                let mut acc: Vec<u8> = Vec::new();
-                let mut started = false;
-                for arg in mut_obj_into_iter(&mut a, "join (bytes)")? {
-                    if started {
-                        acc.extend(b.iter())
+        body: |a, b| {
+            // join(sep, seq) also works; a string is only taken as the separator that way if the
+            // other side isn't a string too
+            let (mut a, b) = match (a, b) {
+                (sep @ Obj::Seq(Seq::String(_)), s @ Obj::Seq(Seq::List(_) | Seq::Stream(_))) => {
+                    (s, sep)
+                }
+                ab => ab,
+            };
+            match b {
+                Obj::Seq(Seq::Bytes(b)) => {
+                    let mut acc: Vec<u8> = Vec::new();
+                    let mut started = false;
+                    for arg in mut_obj_into_iter(&mut a, "join (bytes)")? {
+                        if started {
+                            acc.extend(b.iter())
+                        }
+                        started = true;
+                        acc.extend(
+                            mut_obj_into_iter(&mut arg?, "join (bytes) byte conversion")?
+                                .map(|e| to_byte(e?, "join (bytes) byte conversion"))
+                                .collect::<NRes<Vec<u8>>>()?
+                                .iter(),
+                        );
                     }
-                    started = true;
-                    acc.extend(
-                        mut_obj_into_iter(&mut arg?, "join (bytes) byte conversion")?
-                            .map(|e| to_byte(e?, "join (bytes) byte conversion"))
-                            .collect::<NRes<Vec<u8>>>()?
-                            .iter(),
-                    );
+                    Ok(Obj::Seq(Seq::Bytes(Rc::new(acc))))
                 }
-                Ok(Obj::Seq(Seq::Bytes(Rc::new(acc))))
+                _ => Ok(Obj::from(simple_join(a, format!("{}", b).as_str())?)),
             }
-            _ => Ok(Obj::from(simple_join(a, format!("{}", b).as_str())?)),
         },
     });
     // TODO: split with limit
@@ -4382,6 +5576,16 @@ pub fn initialize(env: &mut Env) {
         body: |a, b| Ok(Obj::list(vec![a; obj_clamp_to_usize_ok(&b)?])),
     });
     env.insert_builtin_with_alias(CartesianProduct, "×");
+    env.insert_builtin(TwoArgBuiltin {
+        name: "de_bruijn".to_string(),
+        body: |a, b| match (a, b) {
+            (Obj::Num(k), Obj::Num(n)) => Ok(Obj::Seq(Seq::Stream(Rc::new(DeBruijn::new(
+                to_usize_ok(&k)?,
+                to_usize_ok(&n)?,
+            )?)))),
+            (a, b) => Err(NErr::argument_error_2(&a, &b)),
+        },
+    });
     env.insert_builtin(TwoArgBuiltin {
         name: "^^".to_string(),
         body: |a, b| {
@@ -4391,15 +5595,35 @@ pub fn initialize(env: &mut Env) {
                     let u = n
                         .to_usize()
                         .ok_or(NErr::value_error("bad lazy pow".to_string()))?;
-                    Ok(Obj::Seq(Seq::Stream(Rc::new(CartesianPower(
-                        v,
-                        Some(Rc::new(vec![0; u])),
-                    )))))
+                    Ok(Obj::Seq(Seq::Stream(Rc::new(CartesianPower::new(v, u)))))
                 }
                 b => Err(NErr::argument_error_second(&b)),
             }
         },
     });
+    env.insert_builtin(BasicBuiltin {
+        name: "cartesian_product".to_string(),
+        body: |_env, args| {
+            Ok(Obj::Seq(Seq::Stream(Rc::new(CartesianProductStream::new(
+                args.into_iter()
+                    .map(to_rc_vec_obj)
+                    .collect::<NRes<Vec<Rc<Vec<Obj>>>>>()?,
+            )))))
+        },
+    });
+    env.insert_builtin(BasicBuiltin {
+        name: "lazy_cartesian_product".to_string(),
+        body: |_env, args| {
+            Ok(Obj::Seq(Seq::Stream(Rc::new(LazyCartesianProduct::new(
+                args.into_iter()
+                    .map(|a| match a {
+                        Obj::Seq(Seq::Stream(s)) => Ok(s),
+                        a => Ok(Rc::new(ListStream(to_rc_vec_obj(a)?, 0)) as Rc<dyn Stream>),
+                    })
+                    .collect::<NRes<Vec<Rc<dyn Stream>>>>()?,
+            )?))))
+        },
+    });
     env.insert_builtin(IdBuiltin);
     env.insert_builtin(TwoArgBuiltin {
         name: "const".to_string(),
@@ -4436,6 +5660,7 @@ pub fn initialize(env: &mut Env) {
     env.insert_builtin(OneArgBuiltin {
         name: "last".to_string(),
         body: |a| match a {
+            Obj::Seq(Seq::Stream(s)) => Stream::last(&*s),
             Obj::Seq(s) => linear_index_isize(s, -1),
             a => Err(NErr::argument_error_1(&a)),
         },
@@ -4496,9 +5721,77 @@ pub fn initialize(env: &mut Env) {
         name: "take".to_string(),
         body: |env, a, b| match (a, b) {
             (Obj::Seq(s), Obj::Func(f, _)) => take_while(s, f, env),
+            // take(n, seq) is lazy and n can be any size
+            (Obj::Num(n), a @ Obj::Seq(_)) => Ok(Obj::Seq(Seq::Stream(Rc::new(TakeStream::new(
+                obj_to_stream(a)?,
+                into_bigint_ok(n)?,
+            )?)))),
             (a, b) => slice(a, None, Some(b)),
         },
     });
+    env.insert_builtin(TwoArgBuiltin {
+        name: "split_at".to_string(),
+        body: |a, b| {
+            let (s, n) = match (a, b) {
+                (Obj::Seq(s), Obj::Num(n)) | (Obj::Num(n), Obj::Seq(s)) => (s, n),
+                (a, b) => return Err(NErr::argument_error_2(&a, &b)),
+            };
+            match s {
+                Seq::Stream(s) => {
+                    let n = into_bigint_ok(n)?;
+                    let prefix = TakeStream::new(s.clone_box(), n.clone())?
+                        .collect::<NRes<Vec<Obj>>>()?;
+                    let suffix = SkipStream::new(s.clone_box(), n)?;
+                    // the rest of a finite stream is forced like the prefix; otherwise
+                    // it stays lazy
+                    let suffix = if s.is_infinite() == Some(false) {
+                        Obj::list(suffix.collect::<NRes<Vec<Obj>>>()?)
+                    } else {
+                        Obj::Seq(Seq::Stream(Rc::new(suffix)))
+                    };
+                    Ok(Obj::list(vec![Obj::list(prefix), suffix]))
+                }
+                s => Ok(Obj::list(vec![
+                    slice_seq(s.clone(), None, Some(Obj::Num(n.clone())))?,
+                    slice_seq(s, Some(Obj::Num(n)), None)?,
+                ])),
+            }
+        },
+    });
+    env.insert_builtin(TwoArgBuiltin {
+        name: "split_into".to_string(),
+        body: |a, b| {
+            let (s, k) = match (a, b) {
+                (Obj::Num(k), Obj::Seq(s)) | (Obj::Seq(s), Obj::Num(k)) => (s, k),
+                (a, b) => return Err(NErr::argument_error_2(&a, &b)),
+            };
+            let k = to_usize_ok(&k)?;
+            if k == 0 {
+                return Err(NErr::value_error("can't split into 0 parts".to_string()));
+            }
+            let s = match s {
+                Seq::Stream(s) => Seq::List(Rc::new(
+                    finite_stream(Obj::Seq(Seq::Stream(s)))?.collect::<NRes<Vec<Obj>>>()?,
+                )),
+                s => s,
+            };
+            let n = s.len().expect("non-stream seqs have lengths");
+            // always exactly k parts, the first n % k one longer; if k > n, the last ones are
+            // empty
+            let mut acc = Vec::new();
+            let mut start = 0;
+            for i in 0..k {
+                let end = start + n / k + usize::from(i < n % k);
+                acc.push(slice_seq(
+                    s.clone(),
+                    Some(Obj::from(start)),
+                    Some(Obj::from(end)),
+                )?);
+                start = end;
+            }
+            Ok(Obj::list(acc))
+        },
+    });
     env.insert_builtin(EnvTwoArgBuiltin {
         name: "stop_at".to_string(),
         body: |env, a, b| match (a, b) {
@@ -4507,6 +5800,22 @@ pub fn initialize(env: &mut Env) {
             //(a, Obj::Num(b)) => slice(a, None, Some(b.factorial().add(1))),
         },
     });
+    // every kth element, up to and including the first that satisfies f
+    env.insert_builtin(BasicBuiltin {
+        name: "step_until".to_string(),
+        body: |env, args| match few3(args) {
+            Few3::Three(Obj::Func(f, _), k @ Obj::Num(_), s @ Obj::Seq(_)) => {
+                match Stride.run(env, vec![s, k])? {
+                    Obj::Seq(s) => stop_at(s, f, env),
+                    s => Err(NErr::type_error(format!(
+                        "stride gave a non-sequence: {}",
+                        FmtObj::debug(&s)
+                    ))),
+                }
+            }
+            f => Err(NErr::argument_error_few3(&f)),
+        },
+    });
     env.insert_builtin(OneArgBuiltin {
         name: "stop_at_dup".to_string(),
         body: |a| match a {
@@ -4532,66 +5841,52 @@ pub fn initialize(env: &mut Env) {
         name: "drop".to_string(),
         body: |env, a, b| match (a, b) {
             (Obj::Seq(s), Obj::Func(f, _)) => drop_while(s, f, env),
+            // drop(n, seq) is lazy and n can be any size
+            (Obj::Num(n), a @ Obj::Seq(_)) => Ok(Obj::Seq(Seq::Stream(Rc::new(SkipStream::new(
+                obj_to_stream(a)?,
+                into_bigint_ok(n)?,
+            )?)))),
             (a, b) => slice(a, Some(b), None),
         },
     });
-    env.insert_builtin(TwoArgBuiltin {
-        name: "stride".to_string(),
-        body: |a, b| match (a, b) {
-            (Obj::Seq(Seq::List(s)), Obj::Num(n) ) => {
-                    let stride = n.to_isize().ok_or(NErr::index_error(format!(
-                            "Stride index out of bounds of isize or non-integer: {:?}", n)))?;
-                    match stride.cmp(&0) {
-                        Ordering::Less => Ok(Obj::list(s.iter().rev().step_by((-stride).to_usize().unwrap()).cloned().collect())),
-                        Ordering::Equal=> Err(NErr::type_error("Stride step cant be zero".to_string())),
-                        Ordering::Greater => Ok(Obj::list(s.iter().step_by(stride.to_usize().unwrap()).cloned().collect())),
-                    }
-                },
-            (Obj::Seq(Seq::Stream(s)), Obj::Num(n)) => {
-                Ok(Obj::Seq(Seq::Stream(Rc::new(StridedStream(Ok((s.clone_box(), n.to_usize().ok_or(
-                    NErr::index_error(format!(
-                        "Stride index out of bounds of usize or non-integer: {:?}", n)))?
-                , 0)))))))
-            },
-            _ => Err(NErr::type_error("stride first argument is not a sequence".to_string())),
-            //(a, b) => slice(a, Some(b), None),
-        },
-    });
+    env.insert_builtin_with_alias(Stride, "step_by");
     env.insert_builtin(EnvTwoArgBuiltin {
         name: "find".to_string(),
-        body: |env, mut a, b| match b {
-            Obj::Func(f, _) => {
-                let mut it = mut_obj_into_iter(&mut a, "find")?;
-                while let Some(x) = it.next() {
-                    let x = x?;
-                    if f.run(env, vec![x.clone()])?.truthy() {
-                        return Ok(x);
-                    }
-                }
-                Err(NErr::value_error("didn't find".to_string()))
-            }
-            b => Err(NErr::argument_error_2(&a, &b)),
+        body: |env, a, b| match find_first(env, a, b, "find")? {
+            Some(x) => Ok(x),
+            None => Err(NErr::value_error("didn't find".to_string())),
         },
     });
     env.insert_builtin(EnvTwoArgBuiltin {
         name: "find?".to_string(),
-        body: |env, mut a, b| match b {
-            Obj::Func(f, _) => {
-                let mut it = mut_obj_into_iter(&mut a, "find?")?;
-                while let Some(x) = it.next() {
-                    let x = x?;
-                    if f.run(env, vec![x.clone()])?.truthy() {
-                        return Ok(x);
-                    }
+        body: |env, a, b| Ok(find_first(env, a, b, "find?")?.unwrap_or(Obj::Null)),
+    });
+    env.insert_builtin(EnvTwoArgBuiltin {
+        name: "find_map".to_string(),
+        body: |env, a, b| {
+            let (mut a, f) = match (a, b) {
+                (a, Obj::Func(f, _)) | (Obj::Func(f, _), a) => (a, f),
+                (a, b) => return Err(NErr::argument_error_2(&a, &b)),
+            };
+            let it = mut_obj_into_iter(&mut a, "find_map")?;
+            for x in it {
+                match f.run(env, vec![x?]) {
+                    Ok(Obj::Null) => {}
+                    Ok(r) => return Ok(r),
+                    Err(NErr::Break(r)) => return Ok(r.unwrap_or(Obj::Null)),
+                    Err(e) => return Err(e),
                 }
-                Ok(Obj::Null)
             }
-            b => Err(NErr::argument_error_2(&a, &b)),
+            Ok(Obj::Null)
         },
     });
     env.insert_builtin(EnvTwoArgBuiltin {
         name: "locate".to_string(),
         body: |env, a, b| match (a, b) {
+            (Obj::Seq(Seq::Stream(s)), b) => match stream_locate(env, &*s, b)? {
+                Some(i) => Ok(Obj::from(i)),
+                None => Err(NErr::value_error("didn't find".to_string())),
+            },
             (mut a, Obj::Func(f, _)) => {
                 let mut it = mut_obj_into_iter(&mut a, "locate")?.enumerate();
                 while let Some((i, x)) = it.next() {
@@ -4622,6 +5917,10 @@ pub fn initialize(env: &mut Env) {
     env.insert_builtin(EnvTwoArgBuiltin {
         name: "locate?".to_string(),
         body: |env, a, b| match (a, b) {
+            (Obj::Seq(Seq::Stream(s)), b) => match stream_locate(env, &*s, b)? {
+                Some(i) => Ok(Obj::from(i)),
+                None => Ok(Obj::Null),
+            },
             (mut a, Obj::Func(f, _)) => {
                 let mut it = mut_obj_into_iter(&mut a, "locate?")?.enumerate();
                 while let Some((i, x)) = it.next() {
@@ -4649,7 +5948,24 @@ pub fn initialize(env: &mut Env) {
             }
         },
     });
-    env.insert_builtin(Sort);
+    env.insert_builtin_with_alias(Sort, "sort_by");
+    // like sort, but never fails: values sort can't compare are ordered by total_cmp
+    env.insert_builtin(OneArgBuiltin {
+        name: "total_sort".to_string(),
+        body: |a| match a {
+            Obj::Seq(s) => Ok(Obj::Seq(multi_total_sort(s)?)),
+            a => Err(NErr::argument_error_1(&a)),
+        },
+    });
+    env.insert_builtin(EnvTwoArgBuiltin {
+        name: "sort_by_key".to_string(),
+        body: |env, a, b| match (a, b) {
+            (Obj::Func(f, _), Obj::Seq(s)) | (Obj::Seq(s), Obj::Func(f, _)) => {
+                Ok(Obj::Seq(multi_sort_by_key(env, f, s)?))
+            }
+            (a, b) => Err(NErr::argument_error_2(&a, &b)),
+        },
+    });
     env.insert_builtin(OneArgBuiltin {
         name: "reverse".to_string(),
         body: |a| match a {
@@ -4754,6 +6070,44 @@ pub fn initialize(env: &mut Env) {
     });
     env.insert_builtin(Set);
     env.insert_builtin(CountDistinct);
+    // the inverse of items; a truthy second argument makes a repeated key an error instead of
+    // letting the later value win
+    env.insert_builtin(BasicBuiltin {
+        name: "to_dict".to_string(),
+        body: |_env, args| {
+            let (mut a, strict) = match few2(args) {
+                Few2::One(a) => (a, false),
+                Few2::Two(a, strict) => (a, strict.truthy()),
+                f => return Err(NErr::argument_error_few2(&f)),
+            };
+            let mut ret = HashMap::new();
+            for (i, p) in mut_obj_into_finite_iter(&mut a, "to_dict")?.enumerate() {
+                let (k, v) = match p? {
+                    Obj::Seq(Seq::List(xs)) if xs.len() == 2 => {
+                        let mut xs = unwrap_or_clone(xs).into_iter();
+                        (xs.next().unwrap(), xs.next().unwrap())
+                    }
+                    p => {
+                        return Err(NErr::type_error(format!(
+                            "element {} is not a [key, value] pair: {}",
+                            i,
+                            FmtObj::debug(&p)
+                        )))
+                    }
+                };
+                let k = to_key(k)?;
+                if strict && ret.contains_key(&k) {
+                    return Err(NErr::value_error(format!(
+                        "duplicate key at element {}: {}",
+                        i,
+                        FmtObj::debug(&key_to_obj(k))
+                    )));
+                }
+                ret.insert(k, v);
+            }
+            Ok(Obj::dict(ret, None))
+        },
+    });
     env.insert_builtin(OneArgBuiltin {
         name: "items".to_string(),
         body: |a| match a {
@@ -4770,25 +6124,32 @@ pub fn initialize(env: &mut Env) {
     });
     env.insert_builtin(BasicBuiltin {
         name: "enumerate".to_string(),
-        body: |_, mut a| match few2(a) {
-            Few2::One(mut a) => {
-                Ok(Obj::list(
-                    mut_obj_into_iter(&mut a, "enumerate conversion")?
-                        .enumerate()
-                        .map(|(k, v)| Ok(Obj::list(vec![Obj::from(k), v?])))
-                        .collect::<NRes<Vec<Obj>>>()?,
-                ))
-            },
-            Few2::Two(mut a, Obj::Num(start)) => {
-                Ok(Obj::list(
-                    mut_obj_into_iter(&mut a, "enumerate conversion")?
-                        .enumerate()
-                        .map(|(k, v)| Ok(Obj::list(vec![Obj::from(BigInt::from(k) +start.to_bigint().ok_or(
-                            NErr::index_error(format!("Enumerate start out of bounds of isize or non-integer: {:?}", start)))?), v?])))
-                        .collect::<NRes<Vec<Obj>>>()?,
-                ))
-            },
-            _ => Err(NErr::argument_error("Enumerate only accepts one or two arguments".to_string()))
+        // enumerate(seq), enumerate(seq, start), or enumerate(seq, start, step)
+        body: |_, args| {
+            let (a, start, step) = match few3(args) {
+                Few3::One(a) => (a, BigInt::from(0), BigInt::from(1)),
+                Few3::Two(a, Obj::Num(start)) => (a, into_bigint_ok(start)?, BigInt::from(1)),
+                Few3::Three(a, Obj::Num(start), Obj::Num(step)) => {
+                    (a, into_bigint_ok(start)?, into_bigint_ok(step)?)
+                }
+                f => return Err(NErr::argument_error_few3(&f)),
+            };
+            match a {
+                Obj::Seq(Seq::Stream(s)) => Ok(Obj::Seq(Seq::Stream(Rc::new(EnumerateStream(
+                    s.clone_box(),
+                    start,
+                    step,
+                ))))),
+                mut a => {
+                    let mut i = start;
+                    let mut ret = Vec::new();
+                    for x in mut_obj_into_iter(&mut a, "enumerate conversion")? {
+                        ret.push(Obj::list(vec![Obj::from(i.clone()), x?]));
+                        i += &step;
+                    }
+                    Ok(Obj::list(ret))
+                }
+            }
         },
     });
 
@@ -5140,18 +6501,21 @@ pub fn initialize(env: &mut Env) {
             _ => Err(NErr::type_error("must hex_encode bytes".to_string())),
         },
     });
-    env.insert_builtin(OneArgBuiltin {
-        name: "json_encode".to_string(),
-        body: |arg| match serde_json::to_string(&json_encode(arg)?) {
-            Ok(s) => Ok(Obj::from(s)),
-            Err(t) => Err(NErr::value_error(format!("json encoding failed: {}", t))),
+    env.insert_builtin_with_alias(
+        OneArgBuiltin {
+            name: "json_encode".to_string(),
+            body: |arg| match serde_json::to_string(&json_encode(arg)?) {
+                Ok(s) => Ok(Obj::from(s)),
+                Err(t) => Err(NErr::value_error(format!("json encoding failed: {}", t))),
+            },
         },
-    });
+        "to_json",
+    );
     env.insert_builtin(OneArgBuiltin {
         name: "json_decode".to_string(),
         body: |arg| match arg {
             Obj::Seq(Seq::String(s)) => match serde_json::from_str(&*s) {
-                Ok(k) => Ok(json_decode(k)),
+                Ok(k) => json_decode(k),
                 Err(t) => Err(NErr::value_error(format!("json decoding failed: {}", t))),
             },
             a => Err(NErr::argument_error_1(&a)),
@@ -5473,6 +6837,79 @@ pub fn initialize(env: &mut Env) {
             Few::Many(a) => Err(NErr::argument_error_args(&a)),
         },
     });
+    env.insert_builtin(OneArgBuiltin {
+        name: "random_stream".to_string(),
+        body: |a| match a {
+            Obj::Num(NNum::Int(seed)) => Ok(Obj::Seq(Seq::Stream(Rc::new(RandomStream::new(
+                seed_to_u64(&seed)?,
+                None,
+            )?)))),
+            a => Err(NErr::argument_error_1(&a)),
+        },
+    });
+    env.insert_builtin(BasicBuiltin {
+        name: "reservoir_sample".to_string(),
+        body: |_env, args| {
+            let (k, seq, seed, allow_fewer) = match args.as_slice() {
+                [Obj::Num(k), seq @ Obj::Seq(_), Obj::Num(NNum::Int(seed))] => {
+                    (k, seq, seed, false)
+                }
+                [Obj::Num(k), seq @ Obj::Seq(_), Obj::Num(NNum::Int(seed)), flag] => {
+                    (k, seq, seed, flag.truthy())
+                }
+                _ => return Err(NErr::argument_error_args(&args)),
+            };
+            let k = to_usize_ok(k)?;
+            let mut rng = SplitMix64(seed_to_u64(seed)?);
+            let it = obj_to_stream(seq.clone())?;
+            if it.is_infinite() == Some(true) {
+                return Err(NErr::value_error(format!(
+                    "can't sample from {} because it's infinite",
+                    it
+                )));
+            }
+            // Algorithm R: the i-th element replaces a random slot with probability k/(i+1)
+            let mut reservoir = Vec::with_capacity(k);
+            for (i, x) in it.enumerate() {
+                let x = x?;
+                if i < k {
+                    reservoir.push(x);
+                } else {
+                    let j = rng.below(i as u64 + 1) as usize;
+                    if j < k {
+                        reservoir[j] = x;
+                    }
+                }
+            }
+            if reservoir.len() < k && !allow_fewer {
+                return Err(NErr::value_error(format!(
+                    "wanted {} elements but only got {}",
+                    k,
+                    reservoir.len()
+                )));
+            }
+            Ok(Obj::list(reservoir))
+        },
+    });
+    // Unlike most stream builtins this isn't lazy: the last element out can be any element in, so
+    // the whole input gets forced and shuffled up front. It's handed back as a stream anyway so
+    // it composes the same way (e.g. with take).
+    env.insert_builtin(TwoArgBuiltin {
+        name: "shuffle".to_string(),
+        body: |a, b| match (a, b) {
+            (a @ Obj::Seq(_), Obj::Num(NNum::Int(seed))) => {
+                let mut rng = SplitMix64(seed_to_u64(&seed)?);
+                let mut xs = finite_stream(a)?.force()?;
+                // Fisher-Yates, from the back
+                for i in (1..xs.len()).rev() {
+                    let j = rng.below(i as u64 + 1) as usize;
+                    xs.swap(i, j);
+                }
+                Ok(Obj::Seq(Seq::Stream(Rc::new(ListStream(Rc::new(xs), 0)))))
+            }
+            (a, b) => Err(NErr::argument_error_2(&a, &b)),
+        },
+    });
     env.insert_builtin(OneArgBuiltin {
         name: "random_bytes".to_string(),
         body: |a| match a {
@@ -5485,15 +6922,7 @@ pub fn initialize(env: &mut Env) {
             a => Err(NErr::argument_error_1(&a)),
         },
     });
-    env.insert_builtin(TwoArgBuiltin {
-        name: "random_range".to_string(),
-        body: |a, b| match (a, b) {
-            (Obj::Num(NNum::Int(a)), Obj::Num(NNum::Int(b))) => {
-                Ok(Obj::from(rand::thread_rng().gen_bigint_range(&a, &b)))
-            }
-            (a, b) => Err(NErr::argument_error_2(&a, &b)),
-        },
-    });
+    env.insert_builtin(RandomRange);
 
     #[cfg(feature = "request")]
     env.insert_builtin(BasicBuiltin {
@@ -5520,7 +6949,7 @@ pub fn initialize(env: &mut Env) {
     env.insert_builtin(BasicBuiltin {
         name: "request_json".to_string(),
         body: |_env, args| match request_response(args)?.json() {
-            Ok(j) => Ok(json_decode(j)),
+            Ok(j) => json_decode(j),
             Err(e) => Err(NErr::io_error(format!("failed: {}", e))),
         },
     });
@@ -5698,10 +7127,13 @@ pub fn encapsulated_eval(code: &str, input: &[u8]) -> WasmOutputs {
         backrefs: Vec::new(),
         input: Box::new(io::Cursor::new(input.to_vec())),
         output: Box::new(Vec::new()),
+        stream_preview: DEFAULT_STREAM_PREVIEW,
+        element_budget: 0,
     });
     initialize(&mut env);
 
     let e = Rc::new(RefCell::new(env));
+    let _budget = ElementBudgetScope::enter(&e);
 
     match parse(code) {
         Err(p) => WasmOutputs {
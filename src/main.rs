@@ -1,4 +1,7 @@
-use noulith::{evaluate, initialize, parse, warn, Env, Expr, LocExpr, Obj, ObjType, TopEnv};
+use noulith::{
+    evaluate, initialize, parse, warn, ElementBudgetScope, Env, Expr, LocExpr, Obj, ObjType,
+    TopEnv, DEFAULT_STREAM_PREVIEW,
+};
 use std::cell::RefCell;
 use std::fs::File;
 use std::io;
@@ -29,9 +32,12 @@ fn repl() {
         backrefs: Vec::new(),
         input: Box::new(BufReader::new(io::stdin())),
         output: Box::new(io::stdout()),
+        stream_preview: DEFAULT_STREAM_PREVIEW,
+        element_budget: 0,
     });
     initialize(&mut env);
     let e = Rc::new(RefCell::new(env));
+    let _budget = ElementBudgetScope::enter(&e);
 
     let mut input = String::new();
     while prompt(&mut input) {
@@ -66,6 +72,8 @@ fn run_code(code: &str, args: Vec<String>, invoke_wrapper: Option<&'static str>,
         backrefs: Vec::new(),
         input: Box::new(BufReader::new(io::stdin())),
         output: Box::new(io::stdout()),
+        stream_preview: DEFAULT_STREAM_PREVIEW,
+        element_budget: 0,
     });
     initialize(&mut env);
     match env.insert(
@@ -105,6 +113,7 @@ fn run_code(code: &str, args: Vec<String>, invoke_wrapper: Option<&'static str>,
                 }
                 None => expr,
             };
+            let _budget = ElementBudgetScope::enter(&e);
             match evaluate(&e, &wrapped_expr) {
                 Ok(Obj::Null) => {}
                 Ok(e) => {
@@ -405,6 +405,22 @@ impl NNum {
         }
     }
 
+    // the int this compares equal to, if any; complex numbers never equal ints
+    pub fn to_integral(&self) -> Option<BigInt> {
+        match self {
+            NNum::Int(n) => Some(n.clone()),
+            NNum::Rational(r) => {
+                if r.is_integer() {
+                    Some(r.to_integer())
+                } else {
+                    None
+                }
+            }
+            NNum::Float(f) => to_bigint_if_int(*f),
+            NNum::Complex(_) => None,
+        }
+    }
+
     pub fn to_isize(&self) -> Option<isize> {
         match self {
             NNum::Int(n) => n.to_isize(),
@@ -566,7 +582,7 @@ impl PartialOrd for NNum {
 
 impl NNum {
     // (considers NaNs equal)
-    fn total_cmp_small_nan(&self, other: &Self) -> Ordering {
+    pub fn total_cmp_small_nan(&self, other: &Self) -> Ordering {
         let (ra, ia) = self.project_to_reals();
         let (rb, ib) = other.project_to_reals();
         ra.total_cmp_small_nan(&rb)
@@ -993,54 +1009,6 @@ pub fn lazy_is_prime(n: &BigInt) -> bool {
     }
 }
 
-pub fn lazy_factorize(mut a: BigInt) -> Vec<(BigInt, usize)> {
-    let mut acc = Vec::new();
-    match a.sign() {
-        Sign::Minus => {
-            a = -a;
-            acc.push((BigInt::from(-1), 1));
-        }
-        Sign::NoSign => {
-            return acc;
-        }
-        Sign::Plus => {}
-    }
-    let mut test = |f: &BigInt| -> bool {
-        if &(f * f) > &a {
-            if a > BigInt::from(1) {
-                acc.push((a.clone(), 1));
-            }
-            return true;
-        }
-        let mut mult = 0usize;
-        while (&a % f).is_zero() {
-            a /= f;
-            mult += 1;
-        }
-        if mult > 0 {
-            acc.push((f.clone(), mult));
-        }
-        false
-    };
-    if test(&BigInt::from(2)) {
-        return acc;
-    }
-    if test(&BigInt::from(3)) {
-        return acc;
-    }
-    let mut f6 = BigInt::from(5);
-    loop {
-        if test(&f6) {
-            return acc;
-        }
-        f6 += 2;
-        if test(&f6) {
-            return acc;
-        }
-        f6 += 4;
-    }
-}
-
 impl NNum {
     pub fn gcd(&self, other: &NNum) -> NNum {
         force_bi_binary_match!(self, other, gcd, Integer::gcd)
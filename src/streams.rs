@@ -1,3 +1,6 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt;
 use std::fmt::Debug;
 use std::fmt::Display;
@@ -7,6 +10,7 @@ use num::bigint::{BigInt, Sign};
 use num::ToPrimitive;
 
 use crate::core::*;
+use crate::nnum::NNum;
 
 #[derive(Debug, Clone)]
 pub struct Repeat(pub Obj);
@@ -25,6 +29,12 @@ impl Stream for Repeat {
     fn clone_box(&self) -> Box<dyn Stream> {
         Box::new(self.clone())
     }
+    fn cheap_prefix(&self, n: usize) -> Option<(Vec<Obj>, bool)> {
+        cloned_prefix(self, n)
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(true)
+    }
     fn len(&self) -> Option<usize> {
         None
     }
@@ -37,41 +47,104 @@ impl Stream for Repeat {
         Ok(self.0.clone())
     }
     fn pythonic_slice(&self, lo: Option<isize>, hi: Option<isize>) -> NRes<Seq> {
-        let lo = match lo {
-            Some(x) => {
-                if x < 0 {
-                    x - 1
-                } else {
-                    x
-                }
-            }
-            None => 0,
-        };
-        let hi = match hi {
-            Some(x) => {
-                if x < 0 {
-                    x - 1
-                } else {
-                    x
-                }
-            }
-            None => -1,
-        };
-        Ok(match (lo < 0, hi < 0) {
-            (true, true) | (false, false) => {
-                Seq::List(Rc::new(vec![self.0.clone(); (hi - lo).max(0) as usize]))
-            }
-            (true, false) => Seq::List(Rc::new(Vec::new())),
-            (false, true) => Seq::Stream(Rc::new(self.clone())),
-        })
+        // negative indices count back from an "end" that is infinitely far away, so they're always
+        // past every nonnegative index
+        let copies = |n: isize| Seq::List(Rc::new(vec![self.0.clone(); n.max(0) as usize]));
+        match (lo.unwrap_or(0), hi) {
+            (lo, Some(hi)) if (lo < 0) == (hi < 0) => Ok(copies(hi - lo)),
+            (lo, Some(_)) if lo < 0 => Ok(copies(0)),
+            (lo, _) if lo >= 0 => Ok(Seq::Stream(Rc::new(self.clone()))),
+            (lo, _) => Err(NErr::value_error(format!(
+                "cannot slice repeat from {} to the end, it's infinite",
+                lo
+            ))),
+        }
+    }
+}
+// x, n times: the finite repeat. The usize is how many are left.
+#[derive(Debug, Clone)]
+pub struct RepeatN(pub Obj, pub usize);
+impl Iterator for RepeatN {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        if self.1 == 0 {
+            None
+        } else {
+            self.1 -= 1;
+            Some(Ok(self.0.clone()))
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        exact_size_hint(Some(self.1))
+    }
+}
+impl Display for RepeatN {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "repeat({}, {})", self.0, self.1)
+    }
+}
+impl Stream for RepeatN {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn cheap_prefix(&self, n: usize) -> Option<(Vec<Obj>, bool)> {
+        cloned_prefix(self, n)
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+    fn len(&self) -> Option<usize> {
+        Some(self.1)
+    }
+    fn pythonic_index_isize(&self, i: isize) -> NRes<Obj> {
+        let j = if i >= 0 { i as usize } else { self.1.wrapping_sub(i.unsigned_abs()) };
+        if j < self.1 {
+            Ok(self.0.clone())
+        } else {
+            Err(NErr::index_error(format!("Index out of bounds: {}", i)))
+        }
+    }
+    fn advance_by(&mut self, n: usize) -> NRes<()> {
+        self.1 = self.1.saturating_sub(n);
+        Ok(())
+    }
+    fn pythonic_slice(&self, lo: Option<isize>, hi: Option<isize>) -> NRes<Seq> {
+        let (lo, hi) = pythonic_slice_len(self.1, lo, hi);
+        Ok(Seq::Stream(Rc::new(RepeatN(self.0.clone(), hi - lo))))
+    }
+    fn last(&self) -> NRes<Obj> {
+        if self.1 == 0 {
+            Err(NErr::value_error("empty stream".to_string()))
+        } else {
+            Ok(self.0.clone())
+        }
     }
     fn reversed(&self) -> NRes<Seq> {
         Ok(Seq::Stream(Rc::new(self.clone())))
     }
 }
 #[derive(Debug, Clone)]
-// just gonna say this has to be nonempty
+// nonempty; construct through Cycle::new
 pub struct Cycle(pub Rc<Vec<Obj>>, pub usize);
+impl Cycle {
+    pub fn new(v: Rc<Vec<Obj>>) -> NRes<Cycle> {
+        if v.is_empty() {
+            Err(NErr::value_error("cannot cycle an empty sequence".to_string()))
+        } else {
+            Ok(Cycle(v, 0))
+        }
+    }
+
+    // starting at v[offset], with the offset taken mod len (so negative ones count from the end)
+    pub fn from_offset(v: Rc<Vec<Obj>>, offset: &BigInt) -> NRes<Cycle> {
+        let mut c = Cycle::new(v)?;
+        let n = BigInt::from(c.0.len());
+        c.1 = (((offset % &n) + &n) % &n)
+            .to_usize()
+            .expect("cycle offset mod len fits");
+        Ok(c)
+    }
+}
 impl Iterator for Cycle {
     type Item = NRes<Obj>;
     fn next(&mut self) -> Option<NRes<Obj>> {
@@ -82,13 +155,27 @@ impl Iterator for Cycle {
 }
 impl Display for Cycle {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        write!(formatter, "cycle({})", CommaSeparated(&**self.0))
+        if self.1 == 0 {
+            write!(formatter, "cycle({})", CommaSeparated(&self.0))
+        } else {
+            write!(formatter, "cycle({}) from {}", CommaSeparated(&self.0), self.1)
+        }
     }
 }
 impl Stream for Cycle {
     fn clone_box(&self) -> Box<dyn Stream> {
         Box::new(self.clone())
     }
+    fn cheap_prefix(&self, n: usize) -> Option<(Vec<Obj>, bool)> {
+        cloned_prefix(self, n)
+    }
+    fn advance_by(&mut self, n: usize) -> NRes<()> {
+        self.1 = (self.1 + n % self.0.len()) % self.0.len();
+        Ok(())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(true)
+    }
     fn len(&self) -> Option<usize> {
         None
     }
@@ -108,9 +195,213 @@ impl Stream for Cycle {
         Ok(Seq::Stream(Rc::new(Cycle(Rc::new(v), i))))
     }
 }
+// v repeated a fixed number of times, as positions .1 up to .2 in the infinite repetition, so the
+// element at position k is v[k % len]. Empty once they meet, which also covers an empty v.
+#[derive(Debug, Clone)]
+pub struct CycleN(pub Rc<Vec<Obj>>, pub usize, pub usize);
+impl CycleN {
+    pub fn new(v: Rc<Vec<Obj>>, n: usize) -> NRes<CycleN> {
+        match v.len().checked_mul(n) {
+            Some(end) => Ok(CycleN(v, 0, end)),
+            None => Err(NErr::value_error(format!(
+                "cycle: {} copies of {} elements is too many",
+                n,
+                v.len()
+            ))),
+        }
+    }
+}
+impl Iterator for CycleN {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        if self.1 >= self.2 {
+            None
+        } else {
+            let ret = self.0[self.1 % self.0.len()].clone();
+            self.1 += 1;
+            Some(Ok(ret))
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        exact_size_hint(Some(self.2 - self.1))
+    }
+}
+impl Display for CycleN {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "cycle({}) from {} til {}", CommaSeparated(&self.0), self.1, self.2)
+    }
+}
+impl Stream for CycleN {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn cheap_prefix(&self, n: usize) -> Option<(Vec<Obj>, bool)> {
+        cloned_prefix(self, n)
+    }
+    fn advance_by(&mut self, n: usize) -> NRes<()> {
+        self.1 = self.1.saturating_add(n).min(self.2);
+        Ok(())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+    fn len(&self) -> Option<usize> {
+        Some(self.2 - self.1)
+    }
+    fn pythonic_index_isize(&self, i: isize) -> NRes<Obj> {
+        let len = self.2 - self.1;
+        let j = if i >= 0 { i as usize } else { len.wrapping_sub(i.unsigned_abs()) };
+        if j < len {
+            Ok(self.0[(self.1 + j) % self.0.len()].clone())
+        } else {
+            Err(NErr::index_error(format!("Index out of bounds: {}", i)))
+        }
+    }
+    fn pythonic_slice(&self, lo: Option<isize>, hi: Option<isize>) -> NRes<Seq> {
+        let (lo, hi) = pythonic_slice_len(self.2 - self.1, lo, hi);
+        Ok(Seq::Stream(Rc::new(CycleN(
+            Rc::clone(&self.0),
+            self.1 + lo,
+            self.1 + hi,
+        ))))
+    }
+    fn last(&self) -> NRes<Obj> {
+        if self.1 >= self.2 {
+            Err(NErr::value_error("empty stream".to_string()))
+        } else {
+            Ok(self.0[(self.2 - 1) % self.0.len()].clone())
+        }
+    }
+    fn reversed(&self) -> NRes<Seq> {
+        if self.1 >= self.2 {
+            return Ok(Seq::List(Rc::new(Vec::new())));
+        }
+        let mut v: Vec<Obj> = (*self.0).clone();
+        v.reverse();
+        // position k of the reversal should be v[(end - 1 - k) % len], which is reversed v at
+        // (k + len - end % len) % len
+        let start = (v.len() - self.2 % v.len()) % v.len();
+        Ok(Seq::Stream(Rc::new(CycleN(
+            Rc::new(v),
+            start,
+            start + (self.2 - self.1),
+        ))))
+    }
+}
+// A finite stream played .2 more times counting the current pass .1, restarting each pass from a
+// fresh clone of the original .0 rather than buffering, so memory stays constant but the inner
+// stream's work is redone every pass; cache() the inner stream first to trade the other way.
+// Vectors don't need this, they use CycleN. .3 is whether the current pass has yielded anything.
+#[derive(Debug)]
+pub struct ReplicateStream(pub Box<dyn Stream>, pub Box<dyn Stream>, pub usize, pub bool);
+impl ReplicateStream {
+    pub fn new(inner: Box<dyn Stream>, n: usize) -> NRes<ReplicateStream> {
+        if inner.is_infinite() == Some(true) {
+            return Err(NErr::value_error(format!(
+                "Cannot replicate {} because it's infinite",
+                inner
+            )));
+        }
+        let current = inner.clone_box();
+        Ok(ReplicateStream(inner, current, n, false))
+    }
+}
+impl Clone for ReplicateStream {
+    fn clone(&self) -> ReplicateStream {
+        ReplicateStream(self.0.clone_box(), self.1.clone_box(), self.2, self.3)
+    }
+}
+impl Iterator for ReplicateStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        while self.2 > 0 {
+            if let Some(x) = self.1.next() {
+                self.3 = true;
+                return Some(x);
+            }
+            // an empty inner stream would otherwise take n passes to get nowhere
+            if !self.3 {
+                return None;
+            }
+            self.2 -= 1;
+            self.1 = self.0.clone_box();
+            self.3 = false;
+        }
+        None
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = match self.2 {
+            0 => Some(0),
+            n => cheap_len(&*self.0)
+                .and_then(|m| m.checked_mul(n - 1))
+                .and_then(|m| m.checked_add(cheap_len(&*self.1)?)),
+        };
+        exact_size_hint(len)
+    }
+}
+impl Display for ReplicateStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match (self.2, self.3) {
+            (n, false) => write!(formatter, "replicate({}, {})", n, self.0),
+            (1, true) => write!(formatter, "{}", self.1),
+            (n, true) => write!(formatter, "{} ++ replicate({}, {})", self.1, n - 1, self.0),
+        }
+    }
+}
+impl Stream for ReplicateStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        match self.2 {
+            0 => Some(false),
+            1 => self.1.is_infinite(),
+            _ => self.0.is_infinite(),
+        }
+    }
+    // only what size_hint knows, so neither the inner stream nor the current pass is walked
+    fn len(&self) -> Option<usize> {
+        cheap_len(self)
+    }
+}
 #[derive(Debug, Clone)]
 pub struct Range(pub BigInt, pub Option<BigInt>, pub BigInt);
 impl Range {
+    pub fn new(start: BigInt, end: Option<BigInt>, step: BigInt) -> NRes<Range> {
+        if step.sign() == Sign::NoSign {
+            Err(NErr::value_error("range step cannot be zero".to_string()))
+        } else {
+            Ok(Range(start, end, step))
+        }
+    }
+
+    // Picks the native-integer fast path when everything fits, which saves a BigInt clone and
+    // addition on every step of tight loops.
+    pub fn into_stream(self) -> Rc<dyn Stream> {
+        match SmallRange::from_range(&self) {
+            Some(r) => Rc::new(r),
+            None => Rc::new(self),
+        }
+    }
+
+    // None if infinite
+    fn big_len(&self) -> Option<BigInt> {
+        let Range(start, end, step) = self;
+        let end = end.as_ref()?;
+        match step.sign() {
+            // Range::new rejects these, but agree with empty() anyway
+            Sign::NoSign => {
+                if start < end {
+                    None
+                } else {
+                    Some(BigInt::from(0))
+                }
+            }
+            Sign::Minus => Some((start - end - step - 1usize).max(BigInt::from(0)) / (-step)),
+            Sign::Plus => Some((end - start + step - 1usize).max(BigInt::from(0)) / step),
+        }
+    }
+
     fn empty(&self) -> bool {
         let Range(start, end, step) = self;
         match (step.sign(), end) {
@@ -132,6 +423,9 @@ impl Iterator for Range {
             Some(Ok(Obj::from(ret)))
         }
     }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        exact_size_hint(Stream::len(self))
+    }
 }
 impl Display for Range {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
@@ -145,39 +439,287 @@ impl Stream for Range {
     fn clone_box(&self) -> Box<dyn Stream> {
         Box::new(self.clone())
     }
+    fn cheap_prefix(&self, n: usize) -> Option<(Vec<Obj>, bool)> {
+        cloned_prefix(self, n)
+    }
+    fn advance_by(&mut self, n: usize) -> NRes<()> {
+        // overshooting the end is fine, it just leaves us empty
+        self.0 += &self.2 * n;
+        Ok(())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(self.1.is_none())
+    }
     fn len(&self) -> Option<usize> {
+        self.big_len()?.to_usize()
+    }
+    fn count(&self) -> NRes<BigInt> {
+        self.big_len().ok_or_else(|| {
+            NErr::value_error(format!(
+                "Cannot count the elements of {} because it's infinite",
+                self
+            ))
+        })
+    }
+    fn last(&self) -> NRes<Obj> {
+        match self.big_len() {
+            None => Err(NErr::value_error(format!(
+                "Cannot take the last element of {} because it's infinite",
+                self
+            ))),
+            Some(n) if n.sign() == Sign::NoSign => {
+                Err(NErr::value_error("empty stream".to_string()))
+            }
+            Some(_) => self.pythonic_index_isize(-1),
+        }
+    }
+    // we only yield ints, but floats and rationals can equal them
+    fn contains(&self, x: &Obj) -> NRes<bool> {
+        let Range(start, _, step) = self;
+        let d = match x {
+            Obj::Num(n) => match n.to_integral() {
+                Some(n) => n - start,
+                None => return Ok(false),
+            },
+            _ => return Ok(false),
+        };
+        if (&d % step).sign() != Sign::NoSign {
+            return Ok(false);
+        }
+        let k = d / step;
+        Ok(k.sign() != Sign::Minus && self.big_len().is_none_or(|len| k < len))
+    }
+    fn pythonic_index_isize(&self, i: isize) -> NRes<Obj> {
         let Range(start, end, step) = self;
-        let end = end.as_ref()?;
-        match step.sign() {
-            // weird
-            Sign::NoSign => {
-                if start < end {
-                    None
-                } else {
-                    Some(0)
-                }
+        let n = match (self.big_len(), i >= 0) {
+            (Some(len), true) if BigInt::from(i) < len => BigInt::from(i),
+            (Some(len), false) if len >= BigInt::from(i.unsigned_abs()) => len + i,
+            (None, true) => BigInt::from(i),
+            (None, false) if end.is_none() => {
+                return Err(NErr::index_error(format!(
+                    "Can't index infinite range from the end: {}",
+                    i
+                )))
             }
-            Sign::Minus => {
-                ((end - start - step + 1usize).max(BigInt::from(0)) / (-step)).to_usize()
+            _ => return Err(NErr::index_error(format!("Index out of bounds: {}", i))),
+        };
+        Ok(Obj::from(start + step * n))
+    }
+    fn reversed(&self) -> NRes<Seq> {
+        match self.big_len() {
+            None => Err(NErr::value_error(format!(
+                "Cannot reverse {} because it's infinite",
+                self
+            ))),
+            Some(n) if n.sign() == Sign::NoSign => Ok(Seq::List(Rc::new(Vec::new()))),
+            Some(n) => {
+                let Range(start, _, step) = self;
+                let last = start + step * (n - 1usize);
+                Ok(Seq::Stream(
+                    Range(last, Some(start - step), -step).into_stream(),
+                ))
             }
-            Sign::Plus => ((end - start + step - 1usize).max(BigInt::from(0)) / step).to_usize(),
         }
     }
 }
 
-// Order: lexicographic indexes
+// Range whose start, end, and step fit in i64, such that start can never overflow while
+// stepping toward end. Bounded only; infinite ranges stay as Range.
+#[derive(Debug, Clone)]
+pub struct SmallRange(pub i64, pub i64, pub i64);
+impl SmallRange {
+    fn from_range(r: &Range) -> Option<SmallRange> {
+        let Range(start, end, step) = r;
+        let (start, end, step) = (start.to_i64()?, end.as_ref()?.to_i64()?, step.to_i64()?);
+        if step == 0 {
+            return None;
+        }
+        end.checked_add(step)?;
+        Some(SmallRange(start, end, step))
+    }
+
+    fn empty(&self) -> bool {
+        let SmallRange(start, end, step) = *self;
+        if step < 0 {
+            start <= end
+        } else {
+            start >= end
+        }
+    }
+}
+impl Iterator for SmallRange {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        if self.empty() {
+            None
+        } else {
+            let ret = self.0;
+            self.0 += self.2;
+            Some(Ok(Obj::from(BigInt::from(ret))))
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        exact_size_hint(Stream::len(self))
+    }
+}
+impl Display for SmallRange {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{} til {} by {}", self.0, self.1, self.2)
+    }
+}
+impl Stream for SmallRange {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn cheap_prefix(&self, n: usize) -> Option<(Vec<Obj>, bool)> {
+        cloned_prefix(self, n)
+    }
+    fn advance_by(&mut self, n: usize) -> NRes<()> {
+        let target = self.0 as i128 + self.2 as i128 * n as i128;
+        self.0 = if self.2 < 0 {
+            target.max(self.1 as i128) as i64
+        } else {
+            target.min(self.1 as i128) as i64
+        };
+        Ok(())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+    fn len(&self) -> Option<usize> {
+        if self.empty() {
+            return Some(0);
+        }
+        let SmallRange(start, end, step) = *self;
+        let (dist, step) = ((end as i128 - start as i128).abs(), (step as i128).abs());
+        usize::try_from((dist + step - 1) / step).ok()
+    }
+    fn last(&self) -> NRes<Obj> {
+        if self.empty() {
+            return Err(NErr::value_error("empty stream".to_string()));
+        }
+        self.pythonic_index_isize(-1)
+    }
+    fn contains(&self, x: &Obj) -> NRes<bool> {
+        match x {
+            Obj::Num(n) => match n.to_integral().and_then(|n| n.to_i128()) {
+                Some(n) => {
+                    let d = n - self.0 as i128;
+                    Ok(d % self.2 as i128 == 0
+                        && d / (self.2 as i128) >= 0
+                        && d / (self.2 as i128) < self.len().unwrap_or(0) as i128)
+                }
+                None => Ok(false),
+            },
+            _ => Ok(false),
+        }
+    }
+    fn pythonic_index_isize(&self, i: isize) -> NRes<Obj> {
+        let len = self.len().unwrap_or(0) as i128;
+        let n = if i >= 0 { i as i128 } else { len + i as i128 };
+        if n < 0 || n >= len {
+            return Err(NErr::index_error(format!("Index out of bounds: {}", i)));
+        }
+        Ok(Obj::from(BigInt::from(self.0 as i128 + self.2 as i128 * n)))
+    }
+    fn reversed(&self) -> NRes<Seq> {
+        let SmallRange(start, end, step) = *self;
+        Range(BigInt::from(start), Some(BigInt::from(end)), BigInt::from(step)).reversed()
+    }
+}
+
+// n evenly spaced floats from start to stop inclusive, plus our position. Each point is computed
+// from its index rather than by repeated adding, so rounding error doesn't accumulate and both
+// endpoints come out exact.
+#[derive(Debug, Clone)]
+pub struct LinspaceStream(pub f64, pub f64, pub usize, pub usize);
+impl LinspaceStream {
+    fn point(&self, j: usize) -> f64 {
+        let LinspaceStream(start, stop, n, _) = *self;
+        if j == 0 {
+            start
+        } else if j + 1 == n {
+            stop
+        } else {
+            start + j as f64 * (stop - start) / (n - 1) as f64
+        }
+    }
+}
+impl Iterator for LinspaceStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        if self.3 >= self.2 {
+            return None;
+        }
+        let ret = self.point(self.3);
+        self.3 += 1;
+        Some(Ok(Obj::from(ret)))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        exact_size_hint(Some(self.2.saturating_sub(self.3)))
+    }
+}
+impl Display for LinspaceStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "linspace({}, {}, {} @ {})",
+            self.0, self.1, self.2, self.3
+        )
+    }
+}
+impl Stream for LinspaceStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+    fn len(&self) -> Option<usize> {
+        Some(self.2.saturating_sub(self.3))
+    }
+    fn last(&self) -> NRes<Obj> {
+        if self.3 >= self.2 {
+            return Err(NErr::value_error("empty stream".to_string()));
+        }
+        Ok(Obj::from(self.point(self.2 - 1)))
+    }
+    fn pythonic_index_isize(&self, i: isize) -> NRes<Obj> {
+        let len = self.2.saturating_sub(self.3) as isize;
+        let n = if i >= 0 { i } else { len + i };
+        if n < 0 || n >= len {
+            return Err(NErr::index_error(format!("Index out of bounds: {}", i)));
+        }
+        Ok(Obj::from(self.point(self.3 + n as usize)))
+    }
+}
+
+// Order: lexicographic indexes. We permute positions, not values, so equal elements are treated
+// as distinct: permutations([1, 1, 2]) yields all 6 arrangements, some of them equal, and len
+// counts them the same way. The last field is how many are left, None if that overflows.
 #[derive(Debug, Clone)]
-pub struct Permutations(pub Rc<Vec<Obj>>, pub Option<Rc<Vec<usize>>>);
+pub struct Permutations(
+    pub Rc<Vec<Obj>>,
+    pub Option<Rc<Vec<usize>>>,
+    pub Option<usize>,
+);
+impl Permutations {
+    pub fn new(v: Rc<Vec<Obj>>) -> Permutations {
+        let n = v.len();
+        Permutations(v, Some(Rc::new((0..n).collect())), factorial(n))
+    }
+}
 impl Iterator for Permutations {
     type Item = NRes<Obj>;
     fn next(&mut self) -> Option<NRes<Obj>> {
         let v = Rc::make_mut(self.1.as_mut()?);
+        self.2 = self.2.map(|k| k - 1);
         let ret = Obj::list(v.iter().map(|i| self.0[*i].clone()).collect());
 
         // 1 6 4 2 -> 2 1 4 6
         // last increase, and the largest index of something larger than it
         let mut up = None;
-        for i in 0..(v.len() - 1) {
+        for i in 0..v.len().saturating_sub(1) {
             if v[i] < v[i + 1] {
                 up = Some((i, i + 1));
             } else {
@@ -202,6 +744,9 @@ impl Iterator for Permutations {
         }
         Some(Ok(ret))
     }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        counted_size_hint(self.2)
+    }
 }
 impl Display for Permutations {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
@@ -222,36 +767,85 @@ impl Stream for Permutations {
     fn clone_box(&self) -> Box<dyn Stream> {
         Box::new(self.clone())
     }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
     fn len(&self) -> Option<usize> {
-        match &self.1 {
-            None => Some(0),
-            Some(v) => {
-                let mut cur = 1usize;
-                Some(
-                    (1..v.len())
-                        .map(|i| {
-                            // Each way we could replace v[len - 1 - i] with a later number that's larger
-                            // gives us cur.
-                            // i = 0, cur = undef
-                            // i = 1, cur = 1
-                            // i = 2, cur = 2
-                            // i = 3, cur = 6
-                            cur *= i;
-                            cur * (v.len() - i..v.len())
-                                .filter(|j| v[*j] > v[v.len() - 1 - i])
-                                .count()
-                        })
-                        .sum::<usize>()
-                        + 1usize,
-                )
+        self.2
+    }
+    fn pythonic_index_isize(&self, i: isize) -> NRes<Obj> {
+        let (v, len, rank) = match (&self.1, Stream::len(self)) {
+            (Some(v), Some(len)) => match permutation_rank(v) {
+                Some(rank) => (v, len, rank),
+                None => return walk_to_index(self, i),
+            },
+            (None, _) => return Err(NErr::index_error(format!("Index out of bounds: {}", i))),
+            (Some(_), None) => return walk_to_index(self, i),
+        };
+        let j = if i >= 0 { i as usize } else { len.wrapping_sub(i.unsigned_abs()) };
+        if j >= len {
+            return Err(NErr::index_error(format!("Index out of bounds: {}", i)));
+        }
+        let ix = permutation_unrank(v.len(), rank + j);
+        Ok(Obj::list(ix.iter().map(|k| self.0[*k].clone()).collect()))
+    }
+    fn reversed(&self) -> NRes<Seq> {
+        match Stream::len(self) {
+            Some(n) => Ok(Seq::Stream(Rc::new(ReversedCombinatorial(self.clone_box(), n)))),
+            None => {
+                let mut xs = self.force()?;
+                xs.reverse();
+                Ok(Seq::List(Rc::new(xs)))
             }
         }
     }
 }
 
-// Order: lexicographic indexes
+fn factorial(n: usize) -> Option<usize> {
+    (1..=n).try_fold(1usize, |acc, i| acc.checked_mul(i))
+}
+
+// How many permutations of the same positions come before v, lexicographically. None on overflow.
+fn permutation_rank(v: &[usize]) -> Option<usize> {
+    let mut ret = 0usize;
+    let mut place = 1usize;
+    for i in (0..v.len()).rev() {
+        // v[i] could have been any of the smaller positions after it, each followed by
+        // (len - 1 - i)! arrangements of the rest
+        let smaller = v[i + 1..].iter().filter(|x| **x < v[i]).count();
+        ret = ret.checked_add(smaller.checked_mul(place)?)?;
+        if i > 0 {
+            place = place.checked_mul(v.len() - i)?;
+        }
+    }
+    Some(ret)
+}
+
+// Inverse of permutation_rank, for permutations of 0..n; rank must be less than n!
+fn permutation_unrank(n: usize, mut rank: usize) -> Vec<usize> {
+    let mut left: Vec<usize> = (0..n).collect();
+    let mut ret = Vec::with_capacity(n);
+    for i in 0..n {
+        let place = factorial(n - 1 - i).expect("permutation_unrank: n! overflowed");
+        ret.push(left.remove(rank / place));
+        rank %= place;
+    }
+    ret
+}
+
+// Order: lexicographic indexes. The last field is how many are left, None if that overflows.
 #[derive(Debug, Clone)]
-pub struct Combinations(pub Rc<Vec<Obj>>, pub Option<Rc<Vec<usize>>>);
+pub struct Combinations(
+    pub Rc<Vec<Obj>>,
+    pub Option<Rc<Vec<usize>>>,
+    pub Option<usize>,
+);
+impl Combinations {
+    pub fn new(v: Rc<Vec<Obj>>, k: usize) -> Combinations {
+        let count = binomial(v.len(), k);
+        Combinations(v, Some(Rc::new((0..k).collect())), count)
+    }
+}
 impl Iterator for Combinations {
     type Item = NRes<Obj>;
     fn next(&mut self) -> Option<NRes<Obj>> {
@@ -259,6 +853,7 @@ impl Iterator for Combinations {
         if v.len() > self.0.len() {
             return None;
         }
+        self.2 = self.2.map(|k| k - 1);
         let ret = Obj::list(v.iter().map(|i| self.0[*i].clone()).collect());
 
         let mut last = self.0.len();
@@ -276,6 +871,9 @@ impl Iterator for Combinations {
         self.1 = None;
         Some(Ok(ret))
     }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        counted_size_hint(self.2)
+    }
 }
 impl Display for Combinations {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
@@ -296,28 +894,461 @@ impl Stream for Combinations {
     fn clone_box(&self) -> Box<dyn Stream> {
         Box::new(self.clone())
     }
-    // FIXME this math is hard
-    /*
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+    fn len(&self) -> Option<usize> {
+        self.2
+    }
+    fn pythonic_index_isize(&self, i: isize) -> NRes<Obj> {
+        let (v, len, rank) = match (&self.1, Stream::len(self)) {
+            (Some(v), Some(len)) => match combination_rank(self.0.len(), v) {
+                Some(rank) => (v, len, rank),
+                None => return walk_to_index(self, i),
+            },
+            (None, _) => return Err(NErr::index_error(format!("Index out of bounds: {}", i))),
+            (Some(_), None) => return walk_to_index(self, i),
+        };
+        let j = if i >= 0 { i as usize } else { len.wrapping_sub(i.unsigned_abs()) };
+        if j >= len {
+            return Err(NErr::index_error(format!("Index out of bounds: {}", i)));
+        }
+        let ix = combination_unrank(self.0.len(), v.len(), rank + j);
+        Ok(Obj::list(ix.iter().map(|k| self.0[*k].clone()).collect()))
+    }
+    fn reversed(&self) -> NRes<Seq> {
+        match Stream::len(self) {
+            Some(n) => Ok(Seq::Stream(Rc::new(ReversedCombinatorial(self.clone_box(), n)))),
+            None => {
+                let mut xs = self.force()?;
+                xs.reverse();
+                Ok(Seq::List(Rc::new(xs)))
+            }
+        }
+    }
+}
+
+// How many k-subsets of 0..n come before v (sorted, of length k), lexicographically. Every
+// binomial here is at most C(n, k), so if that fits, so does this.
+fn combination_rank(n: usize, v: &[usize]) -> Option<usize> {
+    let k = v.len();
+    let mut ret = 0usize;
+    let mut lo = 0;
+    for (i, &c) in v.iter().enumerate() {
+        // each j we skipped over at position i, followed by any choice of the rest above it
+        for j in lo..c {
+            ret += binomial(n - 1 - j, k - 1 - i)?;
+        }
+        lo = c + 1;
+    }
+    Some(ret)
+}
+
+// Inverse of combination_rank; rank must be less than C(n, k), which must fit
+fn combination_unrank(n: usize, k: usize, mut rank: usize) -> Vec<usize> {
+    let mut ret = Vec::with_capacity(k);
+    let mut j = 0;
+    for i in 0..k {
+        loop {
+            let skip = binomial(n - 1 - j, k - 1 - i).expect("combination_unrank: overflowed");
+            if rank < skip {
+                break;
+            }
+            rank -= skip;
+            j += 1;
+        }
+        ret.push(j);
+        j += 1;
+    }
+    ret
+}
+
+// Walks a sized stream back to front by indexing it, for streams that can jump to any index
+// without walking there. The usize is how many elements are left.
+#[derive(Debug)]
+pub struct ReversedCombinatorial(pub Box<dyn Stream>, pub usize);
+impl Clone for ReversedCombinatorial {
+    fn clone(&self) -> ReversedCombinatorial {
+        ReversedCombinatorial(self.0.clone_box(), self.1)
+    }
+}
+impl Iterator for ReversedCombinatorial {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        if self.1 == 0 {
+            return None;
+        }
+        self.1 -= 1;
+        Some(self.0.pythonic_index_isize(self.1 as isize))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        exact_size_hint(Some(self.1))
+    }
+}
+impl Display for ReversedCombinatorial {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "reversed({}) with {} left", self.0, self.1)
+    }
+}
+impl Stream for ReversedCombinatorial {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn advance_by(&mut self, n: usize) -> NRes<()> {
+        self.1 = self.1.saturating_sub(n);
+        Ok(())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+    fn len(&self) -> Option<usize> {
+        Some(self.1)
+    }
+    fn pythonic_index_isize(&self, i: isize) -> NRes<Obj> {
+        let j = if i >= 0 { i as usize } else { self.1.wrapping_sub(i.unsigned_abs()) };
+        if j >= self.1 {
+            return Err(NErr::index_error(format!("Index out of bounds: {}", i)));
+        }
+        self.0.pythonic_index_isize((self.1 - 1 - j) as isize)
+    }
+}
+
+// Permutations in the lexicographic order induced by a comparator, which returns a number
+// compared to 0 as in sort. Unlike Permutations, this steps through values with the usual
+// next-permutation successor, so it should start from the sorted arrangement, and elements the
+// comparator calls equal are interchangeable: each distinct arrangement comes out once. The bool
+// is whether we've yielded the current arrangement yet.
+pub struct ComparatorPermutations(pub NRes<(Rc<Vec<Obj>>, Func, REnv)>, pub bool);
+impl Clone for ComparatorPermutations {
+    fn clone(&self) -> ComparatorPermutations {
+        match &self.0 {
+            Err(e) => ComparatorPermutations(Err(e.clone()), self.1),
+            Ok((v, func, renv)) => {
+                ComparatorPermutations(Ok((Rc::clone(v), func.clone(), renv.clone())), self.1)
+            }
+        }
+    }
+}
+// directly debug-printing env can easily recurse infinitely
+impl Debug for ComparatorPermutations {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match &self.0 {
+            Err(NErr::Break(None)) => write!(fmt, "ComparatorPermutations(stopped)"),
+            Err(e) => write!(fmt, "ComparatorPermutations(ERROR: {:?})", e),
+            Ok((v, func, _)) => {
+                write!(fmt, "ComparatorPermutations({:?}, {:?}, {}, ...)", v, func, self.1)
+            }
+        }
+    }
+}
+fn compare_by(func: &Func, renv: &REnv, a: &Obj, b: &Obj) -> NRes<Ordering> {
+    ncmp(&func.run(renv, vec![a.clone(), b.clone()])?, &Obj::zero())
+}
+// Rearranges v into its successor; false if it was already the last arrangement.
+fn next_permutation_by(v: &mut [Obj], func: &Func, renv: &REnv) -> NRes<bool> {
+    // rightmost ascent, then the rightmost thing after it that's larger than it
+    let mut i = v.len();
+    loop {
+        if i < 2 {
+            return Ok(false);
+        }
+        i -= 1;
+        if compare_by(func, renv, &v[i - 1], &v[i])? == Ordering::Less {
+            break;
+        }
+    }
+    let mut j = v.len() - 1;
+    while compare_by(func, renv, &v[i - 1], &v[j])? != Ordering::Less {
+        j -= 1;
+    }
+    v.swap(i - 1, j);
+    v[i..].reverse();
+    Ok(true)
+}
+impl Iterator for ComparatorPermutations {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let (v, func, renv) = self.0.as_mut().ok()?;
+        if self.1 {
+            match next_permutation_by(Rc::make_mut(v).as_mut_slice(), func, renv) {
+                Ok(true) => {}
+                Ok(false) => {
+                    self.0 = Err(NErr::Break(None));
+                    return None;
+                }
+                Err(e) => return stop_on_func_err(&mut self.0, e),
+            }
+        }
+        self.1 = true;
+        Some(Ok(Obj::list(v.to_vec())))
+    }
+}
+impl Display for ComparatorPermutations {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Ok((v, func, _)) => write!(
+                formatter,
+                "permutations({} by {})",
+                CommaSeparated(v),
+                func
+            ),
+            Err(NErr::Break(None)) => write!(formatter, "permutations(done)"),
+            Err(e) => write!(formatter, "permutations(ERROR: {})", e),
+        }
+    }
+}
+impl Stream for ComparatorPermutations {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+}
+
+// Permutations that move every position, in the same order as Permutations. Like it, this is by
+// position: equal elements at different indexes don't count as fixed points. The usize is how
+// many are left, which starts at the subfactorial !n (None if that overflows).
+#[derive(Debug, Clone)]
+pub struct DerangementsStream(pub Permutations, pub Option<usize>);
+impl DerangementsStream {
+    pub fn new(v: Rc<Vec<Obj>>) -> DerangementsStream {
+        // !n = n * !(n - 1) + (-1)^n
+        let mut count = Some(1usize);
+        for n in 1..=v.len() {
+            count = count
+                .and_then(|c| c.checked_mul(n))
+                .map(|c| if n % 2 == 0 { c + 1 } else { c - 1 });
+        }
+        DerangementsStream(Permutations::new(v), count)
+    }
+}
+impl Iterator for DerangementsStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        loop {
+            let fixed = match &self.0 .1 {
+                Some(v) => v.iter().enumerate().any(|(i, x)| i == *x),
+                None => return None,
+            };
+            let ret = self.0.next()?;
+            if !fixed {
+                self.1 = self.1.map(|c| c - 1);
+                return Some(ret);
+            }
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        counted_size_hint(self.1)
+    }
+}
+impl Display for DerangementsStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 .1 {
+            Some(x) => write!(
+                formatter,
+                "derangements({} @ {})",
+                CommaSeparated(&self.0 .0),
+                CommaSeparated(x)
+            ),
+            None => write!(formatter, "derangements(done)"),
+        }
+    }
+}
+impl Stream for DerangementsStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+    fn len(&self) -> Option<usize> {
+        self.1
+    }
+}
+
+// Order: lexicographic indexes. Ordered selections of k distinct positions. The last field is how
+// many are left, None if that overflows.
+#[derive(Debug, Clone)]
+pub struct KPermutations(
+    pub Rc<Vec<Obj>>,
+    pub Option<Rc<Vec<usize>>>,
+    pub Option<usize>,
+);
+impl KPermutations {
+    pub fn new(v: Rc<Vec<Obj>>, k: usize) -> KPermutations {
+        let n = v.len();
+        if k > n {
+            return KPermutations(v, None, Some(0));
+        }
+        // n! / (n - k)!
+        let count = (n - k + 1..=n).try_fold(1usize, |acc, m| acc.checked_mul(m));
+        KPermutations(v, Some(Rc::new((0..k).collect())), count)
+    }
+}
+impl Iterator for KPermutations {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let v = Rc::make_mut(self.1.as_mut()?);
+        self.2 = self.2.map(|k| k - 1);
+        let ret = Obj::list(v.iter().map(|i| self.0[*i].clone()).collect());
+
+        let n = self.0.len();
+        for i in (0..v.len()).rev() {
+            // bump position i to the smallest larger index the prefix isn't using, then fill the
+            // rest with the smallest unused indices in order
+            let mut used = vec![false; n];
+            for x in &v[..i] {
+                used[*x] = true;
+            }
+            if let Some(x) = (v[i] + 1..n).find(|x| !used[*x]) {
+                v[i] = x;
+                used[x] = true;
+                let mut fill = (0..n).filter(|x| !used[*x]);
+                for slot in &mut v[i + 1..] {
+                    *slot = fill.next().expect("k-permutations ran out of indices");
+                }
+                return Some(Ok(ret));
+            }
+        }
+        self.1 = None;
+        Some(Ok(ret))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        counted_size_hint(self.2)
+    }
+}
+impl Display for KPermutations {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.1 {
+            Some(x) => {
+                write!(
+                    formatter,
+                    "permutations({} @ {})",
+                    CommaSeparated(&self.0),
+                    CommaSeparated(x)
+                )
+            }
+            None => write!(formatter, "permutations(done)"),
+        }
+    }
+}
+impl Stream for KPermutations {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
     fn len(&self) -> Option<usize> {
+        self.2
+    }
+}
+
+// None on overflow
+fn binomial(n: usize, k: usize) -> Option<usize> {
+    if k > n {
+        return Some(0);
+    }
+    let k = k.min(n - k);
+    let mut ret = 1usize;
+    for i in 0..k {
+        // always divides exactly: ret * (n - i) is (i + 1) * C(n, i + 1)
+        ret = ret.checked_mul(n - i)? / (i + 1);
+    }
+    Some(ret)
+}
+
+// Order: lexicographic indexes, which are nondecreasing. The last field is how many are left, None
+// if that overflows.
+#[derive(Debug, Clone)]
+pub struct CombinationsWithReplacement(
+    pub Rc<Vec<Obj>>,
+    pub Option<Rc<Vec<usize>>>,
+    pub Option<usize>,
+);
+impl CombinationsWithReplacement {
+    pub fn new(v: Rc<Vec<Obj>>, k: usize) -> CombinationsWithReplacement {
+        // with nothing to choose from, there's only the empty choice
+        if k == 0 {
+            return CombinationsWithReplacement(v, Some(Rc::new(Vec::new())), Some(1));
+        }
+        if v.is_empty() {
+            return CombinationsWithReplacement(v, None, Some(0));
+        }
+        let count = binomial(v.len() + k - 1, k);
+        CombinationsWithReplacement(v, Some(Rc::new(vec![0; k])), count)
+    }
+}
+impl Iterator for CombinationsWithReplacement {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let v = Rc::make_mut(self.1.as_mut()?);
+        self.2 = self.2.map(|k| k - 1);
+        let ret = Obj::list(v.iter().map(|i| self.0[*i].clone()).collect());
+
+        let n = self.0.len();
+        match (0..v.len()).rev().find(|i| v[*i] + 1 < n) {
+            Some(i) => {
+                let x = v[i] + 1;
+                for j in &mut v[i..] {
+                    *j = x;
+                }
+            }
+            None => self.1 = None,
+        }
+        Some(Ok(ret))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        counted_size_hint(self.2)
+    }
+}
+impl Display for CombinationsWithReplacement {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         match &self.1 {
-            None => Some(0),
-            Some(v) => {
-                Some((0..v.len()).rev().map(|i| {
-                    // ...
-                }).sum::<usize>() + 1usize)
+            Some(x) => {
+                write!(
+                    formatter,
+                    "combinations_with_replacement({} @ {})",
+                    CommaSeparated(&self.0),
+                    CommaSeparated(x)
+                )
             }
+            None => write!(formatter, "combinations_with_replacement(done)"),
         }
     }
-    */
+}
+impl Stream for CombinationsWithReplacement {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+    fn len(&self) -> Option<usize> {
+        self.2
+    }
 }
 
-// Order: big-endian binary
+// Order: big-endian binary. The last field is how many are left, None if that overflows.
 #[derive(Debug, Clone)]
-pub struct Subsequences(pub Rc<Vec<Obj>>, pub Option<Rc<Vec<bool>>>);
+pub struct Subsequences(
+    pub Rc<Vec<Obj>>,
+    pub Option<Rc<Vec<bool>>>,
+    pub Option<usize>,
+);
+impl Subsequences {
+    pub fn new(v: Rc<Vec<Obj>>) -> Subsequences {
+        let n = v.len();
+        let count = u32::try_from(n).ok().and_then(|n| 2usize.checked_pow(n));
+        Subsequences(v, Some(Rc::new(vec![false; n])), count)
+    }
+}
 impl Iterator for Subsequences {
     type Item = NRes<Obj>;
     fn next(&mut self) -> Option<NRes<Obj>> {
         let v = Rc::make_mut(self.1.as_mut()?);
+        self.2 = self.2.map(|k| k - 1);
         let ret = Obj::list(
             v.iter()
                 .zip(self.0.iter())
@@ -337,6 +1368,9 @@ impl Iterator for Subsequences {
         self.1 = None;
         Some(Ok(ret))
     }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        counted_size_hint(self.2)
+    }
 }
 impl Display for Subsequences {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
@@ -357,38 +1391,91 @@ impl Stream for Subsequences {
     fn clone_box(&self) -> Box<dyn Stream> {
         Box::new(self.clone())
     }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
     fn len(&self) -> Option<usize> {
-        match &self.1 {
-            None => Some(0),
-            Some(v) => {
-                let mut cur = 1usize;
-                Some(
-                    (0..v.len())
-                        .rev()
-                        .map(|i| {
-                            let s = if !v[i] {
-                                // If we keep everything before this and set this to true:
-                                cur
-                            } else {
-                                0
-                            };
-                            cur *= 2;
-                            s
-                        })
-                        .sum::<usize>()
-                        + 1usize,
-                )
-            }
+        self.2
+    }
+}
+
+// Order: binary-reflected Gray code, so consecutive subsequences differ by exactly one element.
+// Bit j of the code says whether element j is included; the usize is how many we've yielded.
+#[derive(Debug, Clone)]
+pub struct GrayCodeSubsequences(pub Rc<Vec<Obj>>, pub usize);
+impl GrayCodeSubsequences {
+    pub fn new(v: Rc<Vec<Obj>>) -> NRes<GrayCodeSubsequences> {
+        if v.len() >= usize::BITS as usize {
+            Err(NErr::value_error(format!(
+                "too many elements for gray code subsequences: {}",
+                v.len()
+            )))
+        } else {
+            Ok(GrayCodeSubsequences(v, 0))
+        }
+    }
+}
+impl Iterator for GrayCodeSubsequences {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        if self.1 >> self.0.len() != 0 {
+            return None;
         }
+        let code = self.1 ^ (self.1 >> 1);
+        self.1 += 1;
+        Some(Ok(Obj::list(
+            self.0
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| code >> j & 1 == 1)
+                .map(|(_, x)| x.clone())
+                .collect(),
+        )))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        exact_size_hint(Stream::len(self))
+    }
+}
+impl Display for GrayCodeSubsequences {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "gray_subsequences({} @ {})",
+            CommaSeparated(&self.0),
+            self.1
+        )
+    }
+}
+impl Stream for GrayCodeSubsequences {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+    fn len(&self) -> Option<usize> {
+        Some((1usize << self.0.len()).saturating_sub(self.1))
     }
 }
 
+// The last field is how many are left, None if that overflows.
 #[derive(Debug, Clone)]
-pub struct CartesianPower(pub Rc<Vec<Obj>>, pub Option<Rc<Vec<usize>>>);
+pub struct CartesianPower(
+    pub Rc<Vec<Obj>>,
+    pub Option<Rc<Vec<usize>>>,
+    pub Option<usize>,
+);
+impl CartesianPower {
+    pub fn new(v: Rc<Vec<Obj>>, k: usize) -> CartesianPower {
+        let count = u32::try_from(k).ok().and_then(|k| v.len().checked_pow(k));
+        CartesianPower(v, Some(Rc::new(vec![0; k])), count)
+    }
+}
 impl Iterator for CartesianPower {
     type Item = NRes<Obj>;
     fn next(&mut self) -> Option<NRes<Obj>> {
         let v = Rc::make_mut(self.1.as_mut()?);
+        self.2 = self.2.map(|k| k - 1);
         let ret = Obj::list(v.iter().map(|i| self.0[*i].clone()).collect());
 
         // let mut last = self.0.len();
@@ -403,6 +1490,9 @@ impl Iterator for CartesianPower {
         self.1 = None;
         Some(Ok(ret))
     }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        counted_size_hint(self.2)
+    }
 }
 impl Display for CartesianPower {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
@@ -423,43 +1513,624 @@ impl Stream for CartesianPower {
     fn clone_box(&self) -> Box<dyn Stream> {
         Box::new(self.clone())
     }
-    fn len(&self) -> Option<usize> {
-        match &self.1 {
-            None => Some(0),
-            Some(v) => {
-                let mut cur = 1usize;
-                Some(
-                    (0..v.len())
-                        .rev()
-                        .map(|i| {
-                            // If we keep everything before this and increase this:
-                            let s = (self.0.len() - 1 - v[i]) * cur;
-                            cur *= self.0.len();
-                            s
-                        })
-                        .sum::<usize>()
-                        + 1usize,
-                )
+    fn advance_by(&mut self, n: usize) -> NRes<()> {
+        if let Some(v) = self.1.as_mut() {
+            let m = self.0.len() as u128;
+            // add n to the digits, base len, carrying; carrying off the front means we're done
+            let mut carry = n as u128;
+            let v = Rc::make_mut(v);
+            for d in v.iter_mut().rev() {
+                if carry == 0 || m == 0 {
+                    break;
+                }
+                let t = *d as u128 + carry;
+                *d = (t % m) as usize;
+                carry = t / m;
+            }
+            self.2 = self.2.map(|k| k.saturating_sub(n));
+            if carry > 0 {
+                self.1 = None;
+                self.2 = Some(0);
             }
         }
+        Ok(())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+    fn len(&self) -> Option<usize> {
+        self.2
     }
 }
 
-// moderately illegal
-// we'll treat NErr::Break as graceful termination
-#[derive(Clone)]
-pub struct Iterate(pub NRes<(Obj, Func, REnv)>);
-// directly debug-printing env can easily recurse infinitely
-impl Debug for Iterate {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
-        match &self.0 {
-            Ok((obj, func, _)) => write!(fmt, "Iterate({:?}, {:?}, ...)", obj, func),
-            Err(NErr::Break(None)) => write!(fmt, "Iterate(stopped)"),
-            Err(e) => write!(fmt, "Iterate(ERROR: {:?})", e),
+// The lexicographically least De Bruijn sequence B(k, n), as symbols 0..k, by the FKM algorithm:
+// walk the Lyndon words over k symbols of length at most n in order, and concatenate the ones
+// whose length divides n. Fields: k, n, the next Lyndon word to consider (None once they've run
+// out), the word being emitted and our position in it, and how many symbols are left if that
+// fits in a usize.
+#[derive(Debug, Clone)]
+pub struct DeBruijn(
+    pub usize,
+    pub usize,
+    pub Option<Vec<usize>>,
+    pub Rc<Vec<usize>>,
+    pub usize,
+    pub Option<usize>,
+);
+impl DeBruijn {
+    pub fn new(k: usize, n: usize) -> NRes<DeBruijn> {
+        if n == 0 {
+            return Err(NErr::value_error("de_bruijn: n must be positive".to_string()));
+        }
+        let len = u32::try_from(n).ok().and_then(|n| k.checked_pow(n));
+        let first = if k == 0 { None } else { Some(vec![0]) };
+        Ok(DeBruijn(k, n, first, Rc::new(Vec::new()), 0, len))
+    }
+
+    fn advance_word(&mut self) {
+        let (k, n) = (self.0, self.1);
+        if let Some(w) = &mut self.2 {
+            let m = w.len();
+            while w.len() < n {
+                w.push(w[w.len() - m]);
+            }
+            while w.last() == Some(&(k - 1)) {
+                w.pop();
+            }
+            match w.last_mut() {
+                Some(x) => *x += 1,
+                None => self.2 = None,
+            }
         }
     }
 }
-impl Iterator for Iterate {
+impl Iterator for DeBruijn {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        while self.4 >= self.3.len() {
+            let w = self.2.as_ref()?;
+            if self.1.is_multiple_of(w.len()) {
+                self.3 = Rc::new(w.clone());
+                self.4 = 0;
+            }
+            self.advance_word();
+        }
+        let ret = self.3[self.4];
+        self.4 += 1;
+        self.5 = self.5.map(|n| n - 1);
+        Some(Ok(Obj::from(ret)))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        exact_size_hint(self.5)
+    }
+}
+impl Display for DeBruijn {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "de_bruijn({}, {})", self.0, self.1)
+    }
+}
+impl Stream for DeBruijn {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+    fn len(&self) -> Option<usize> {
+        self.5
+    }
+}
+
+// Order: lexicographic indexes, i.e. counting in mixed radix with the last sequence fastest. The
+// last field is how many are left, None if that overflows.
+#[derive(Debug, Clone)]
+pub struct CartesianProductStream(
+    pub Vec<Rc<Vec<Obj>>>,
+    pub Option<Vec<usize>>,
+    pub Option<usize>,
+);
+impl CartesianProductStream {
+    pub fn new(seqs: Vec<Rc<Vec<Obj>>>) -> CartesianProductStream {
+        if seqs.iter().any(|s| s.is_empty()) {
+            return CartesianProductStream(seqs, None, Some(0));
+        }
+        let iv = Some(vec![0; seqs.len()]);
+        let mut ret = CartesianProductStream(seqs, iv, None);
+        ret.2 = ret.total();
+        ret
+    }
+
+    fn total(&self) -> Option<usize> {
+        self.0
+            .iter()
+            .try_fold(1usize, |acc, s| acc.checked_mul(s.len()))
+    }
+
+    // how many tuples precede the current one
+    fn rank(&self, v: &[usize]) -> Option<usize> {
+        self.0
+            .iter()
+            .zip(v)
+            .try_fold(0usize, |acc, (s, i)| acc.checked_mul(s.len())?.checked_add(*i))
+    }
+
+    fn tuple_at(&self, mut rank: usize) -> Obj {
+        let mut ret = vec![Obj::Null; self.0.len()];
+        for (slot, s) in ret.iter_mut().zip(self.0.iter()).rev() {
+            *slot = s[rank % s.len()].clone();
+            rank /= s.len();
+        }
+        Obj::list(ret)
+    }
+}
+impl Iterator for CartesianProductStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let v = self.1.as_mut()?;
+        self.2 = self.2.map(|k| k - 1);
+        let ret = Obj::list(
+            v.iter()
+                .zip(self.0.iter())
+                .map(|(i, s)| s[*i].clone())
+                .collect(),
+        );
+
+        for i in (0..v.len()).rev() {
+            v[i] += 1;
+            if v[i] == self.0[i].len() {
+                v[i] = 0;
+            } else {
+                return Some(Ok(ret));
+            }
+        }
+        self.1 = None;
+        Some(Ok(ret))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        counted_size_hint(self.2)
+    }
+}
+impl Display for CartesianProductStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.1 {
+            Some(x) => write!(formatter, "CartesianProduct(@ {})", CommaSeparated(x)),
+            None => write!(formatter, "CartesianProduct(done)"),
+        }
+    }
+}
+impl Stream for CartesianProductStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn advance_by(&mut self, n: usize) -> NRes<()> {
+        if let Some(v) = self.1.as_mut() {
+            // add n in mixed radix, last digit fastest; carrying off the front means we're done
+            let mut carry = n as u128;
+            for (d, s) in v.iter_mut().zip(self.0.iter()).rev() {
+                if carry == 0 {
+                    break;
+                }
+                let t = *d as u128 + carry;
+                *d = (t % s.len() as u128) as usize;
+                carry = t / s.len() as u128;
+            }
+            self.2 = self.2.map(|k| k.saturating_sub(n));
+            if carry > 0 {
+                self.1 = None;
+                self.2 = Some(0);
+            }
+        }
+        Ok(())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+    fn len(&self) -> Option<usize> {
+        self.2
+    }
+    fn pythonic_index_isize(&self, i: isize) -> NRes<Obj> {
+        let oob = || NErr::index_error(format!("Index out of bounds: {}", i));
+        let v = self.1.as_ref().ok_or_else(oob)?;
+        let (rank, total) = match (self.rank(v), self.total()) {
+            (Some(r), Some(t)) => (r, t),
+            _ => return Err(NErr::index_error("cartesian product too big to index".to_string())),
+        };
+        let target = if i >= 0 {
+            rank.checked_add(i as usize).filter(|t| *t < total)
+        } else {
+            total.checked_sub(i.unsigned_abs()).filter(|t| *t >= rank)
+        };
+        Ok(self.tuple_at(target.ok_or_else(oob)?))
+    }
+}
+
+// Same order as CartesianProductStream, but the factors stay as streams that we index into, so a
+// factor like 0 til 1000000 never gets materialized. Every factor needs a known length, and this
+// is only as fast as their indexing. Fields are the factors, their lengths, the rank of the next
+// tuple, and the total number of tuples.
+#[derive(Debug, Clone)]
+pub struct LazyCartesianProduct(pub Vec<Rc<dyn Stream>>, pub Vec<usize>, pub usize, pub usize);
+impl LazyCartesianProduct {
+    pub fn new(factors: Vec<Rc<dyn Stream>>) -> NRes<LazyCartesianProduct> {
+        let lens = factors
+            .iter()
+            .map(|s| {
+                s.len().ok_or_else(|| {
+                    NErr::value_error(format!("can't take a lazy product with {}, length unknown", s))
+                })
+            })
+            .collect::<NRes<Vec<usize>>>()?;
+        let total = lens
+            .iter()
+            .try_fold(1usize, |acc, n| acc.checked_mul(*n))
+            .ok_or_else(|| NErr::value_error("lazy cartesian product too big".to_string()))?;
+        Ok(LazyCartesianProduct(factors, lens, 0, total))
+    }
+
+    // the rank, read as mixed-radix digits with the last factor fastest, picks one from each
+    fn tuple_at(&self, mut rank: usize) -> NRes<Obj> {
+        let mut ret = vec![Obj::Null; self.0.len()];
+        for ((slot, s), n) in ret.iter_mut().zip(self.0.iter()).zip(self.1.iter()).rev() {
+            *slot = s.pythonic_index_isize((rank % n) as isize)?;
+            rank /= n;
+        }
+        Ok(Obj::list(ret))
+    }
+}
+impl Iterator for LazyCartesianProduct {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        if self.2 >= self.3 {
+            return None;
+        }
+        self.2 += 1;
+        Some(self.tuple_at(self.2 - 1))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        exact_size_hint(Some(self.3 - self.2.min(self.3)))
+    }
+}
+impl Display for LazyCartesianProduct {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "lazy_cartesian_product(")?;
+        for (i, s) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(formatter, ", ")?;
+            }
+            write!(formatter, "{}", s)?;
+        }
+        write!(formatter, " @ {})", self.2)
+    }
+}
+impl Stream for LazyCartesianProduct {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn advance_by(&mut self, n: usize) -> NRes<()> {
+        self.2 = self.2.saturating_add(n).min(self.3);
+        Ok(())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+    fn len(&self) -> Option<usize> {
+        Some(self.3 - self.2.min(self.3))
+    }
+    fn pythonic_index_isize(&self, i: isize) -> NRes<Obj> {
+        let len = self.3 - self.2.min(self.3);
+        let j = if i >= 0 { i as usize } else { len.wrapping_sub(i.unsigned_abs()) };
+        if j >= len {
+            return Err(NErr::index_error(format!("Index out of bounds: {}", i)));
+        }
+        self.tuple_at(self.2 + j)
+    }
+}
+
+// Order: reverse lexicographic, each partition's parts nonincreasing: [4], [3, 1], [2, 2], ...
+// The second field counts how many are left, or is None if that doesn't fit in a usize.
+#[derive(Debug, Clone)]
+pub struct PartitionsStream(pub Option<Vec<usize>>, pub Option<usize>);
+impl PartitionsStream {
+    pub fn new(n: usize) -> PartitionsStream {
+        PartitionsStream(
+            Some(if n == 0 { Vec::new() } else { vec![n] }),
+            partition_count(n),
+        )
+    }
+}
+// p(n) by Euler's pentagonal number recurrence; None as soon as it overflows, which is past 400ish
+fn partition_count(n: usize) -> Option<usize> {
+    let mut p = vec![1usize];
+    for m in 1..=n {
+        let mut sum = 0i128;
+        for k in 1.. {
+            let g = k * (3 * k - 1) / 2;
+            if g > m {
+                break;
+            }
+            let term = p[m - g] as i128 + if g + k <= m { p[m - g - k] as i128 } else { 0 };
+            sum += if k % 2 == 1 { term } else { -term };
+        }
+        p.push(usize::try_from(sum).ok()?);
+    }
+    p.last().copied()
+}
+impl Iterator for PartitionsStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let v = self.0.as_mut()?;
+        let ret = Obj::list(v.iter().map(|x| Obj::from(*x)).collect());
+
+        // take the last part that's bigger than 1, decrement it, and greedily spread it and all the
+        // 1s after it into parts no bigger than it
+        match v.iter().rposition(|x| *x > 1) {
+            Some(k) => {
+                let mut rem = v[k] + (v.len() - k - 1);
+                let x = v[k] - 1;
+                v.truncate(k);
+                while rem >= x {
+                    v.push(x);
+                    rem -= x;
+                }
+                if rem > 0 {
+                    v.push(rem);
+                }
+            }
+            None => self.0 = None,
+        }
+        self.1 = self.1.map(|k| k - 1);
+        Some(Ok(ret))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        counted_size_hint(self.1)
+    }
+}
+impl Display for PartitionsStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Some(v) => write!(formatter, "partitions(@ {})", CommaSeparated(v)),
+            None => write!(formatter, "partitions(done)"),
+        }
+    }
+}
+impl Stream for PartitionsStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+    fn len(&self) -> Option<usize> {
+        self.1
+    }
+}
+
+// Partitions of a set into nonempty blocks, tracked as a restricted growth string: element i goes
+// in block a[i], where a[0] = 0 and each a[i] is at most one more than everything before it. Order:
+// lexicographic in that string, so everything in one block first and singletons last. The last
+// field counts how many are left, or is None if that doesn't fit in a usize.
+#[derive(Debug, Clone)]
+pub struct SetPartitions(pub Rc<Vec<Obj>>, pub Option<Rc<Vec<usize>>>, pub Option<usize>);
+impl SetPartitions {
+    pub fn new(v: Rc<Vec<Obj>>) -> SetPartitions {
+        let n = v.len();
+        SetPartitions(v, Some(Rc::new(vec![0; n])), bell_number(n))
+    }
+}
+// B(n) as the last entry of row n - 1 of the Bell triangle; None as soon as it overflows, which is
+// past 25
+fn bell_number(n: usize) -> Option<usize> {
+    if n == 0 {
+        return Some(1);
+    }
+    let mut row = vec![1usize];
+    for _ in 1..n {
+        let mut next = Vec::with_capacity(row.len() + 1);
+        let mut acc = *row.last()?;
+        next.push(acc);
+        for x in &row {
+            acc = acc.checked_add(*x)?;
+            next.push(acc);
+        }
+        row = next;
+    }
+    row.last().copied()
+}
+impl Iterator for SetPartitions {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let a = Rc::make_mut(self.1.as_mut()?);
+        let blocks = a.iter().max().map_or(0, |m| m + 1);
+        let mut ret = vec![Vec::new(); blocks];
+        for (b, x) in a.iter().zip(self.0.iter()) {
+            ret[*b].push(x.clone());
+        }
+        let ret = Obj::list(ret.into_iter().map(Obj::list).collect());
+
+        // bump the last entry that isn't already one more than everything before it, and reset
+        // everything after it
+        let mut prefix_max = Vec::with_capacity(a.len());
+        let mut m = 0;
+        for &x in a.iter() {
+            prefix_max.push(m);
+            m = m.max(x);
+        }
+        match (1..a.len()).rev().find(|&i| a[i] <= prefix_max[i]) {
+            Some(i) => {
+                a[i] += 1;
+                for x in &mut a[i + 1..] {
+                    *x = 0;
+                }
+            }
+            None => self.1 = None,
+        }
+        self.2 = self.2.map(|k| k - 1);
+        Some(Ok(ret))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        counted_size_hint(self.2)
+    }
+}
+impl Display for SetPartitions {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.1 {
+            Some(x) => {
+                write!(
+                    formatter,
+                    "set_partitions({} @ {})",
+                    CommaSeparated(&**self.0),
+                    CommaSeparated(&**x)
+                )
+            }
+            None => write!(formatter, "set_partitions(done)"),
+        }
+    }
+}
+impl Stream for SetPartitions {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+    fn len(&self) -> Option<usize> {
+        self.2
+    }
+}
+
+// Order: by number of parts, then reverse lexicographic: [3], [2, 1], [1, 2], [1, 1, 1]. Fields
+// are n, the current composition, whether we're restricted to its number of parts, and how many
+// are left (None if that overflows).
+#[derive(Debug, Clone)]
+pub struct CompositionsStream(
+    pub usize,
+    pub Option<Vec<usize>>,
+    pub bool,
+    pub Option<usize>,
+);
+impl CompositionsStream {
+    pub fn new(n: usize, k: Option<usize>) -> CompositionsStream {
+        let (start, count) = match k {
+            None if n == 0 => (Some(Vec::new()), Some(1)),
+            // 2^(n - 1), one for each subset of the n - 1 gaps to cut at
+            None => (
+                Some(vec![n]),
+                u32::try_from(n - 1)
+                    .ok()
+                    .and_then(|e| 2usize.checked_pow(e)),
+            ),
+            Some(k) => (
+                CompositionsStream::first_with_parts(n, k),
+                CompositionsStream::count(n, k),
+            ),
+        };
+        CompositionsStream(n, start, k.is_some(), count)
+    }
+
+    // the lexicographically largest composition of n into k parts: [n - k + 1, 1, ..., 1]
+    fn first_with_parts(n: usize, k: usize) -> Option<Vec<usize>> {
+        if k == 0 {
+            return if n == 0 { Some(Vec::new()) } else { None };
+        }
+        if k > n {
+            return None;
+        }
+        let mut v = vec![1; k];
+        v[0] = n - k + 1;
+        Some(v)
+    }
+
+    // compositions of m into exactly k parts
+    fn count(m: usize, k: usize) -> Option<usize> {
+        match (m, k) {
+            (0, 0) => Some(1),
+            (_, 0) | (0, _) => Some(0),
+            _ => binomial(m - 1, k - 1),
+        }
+    }
+}
+impl Iterator for CompositionsStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let v = self.1.as_mut()?;
+        self.3 = self.3.map(|k| k - 1);
+        let ret = Obj::list(v.iter().map(|x| Obj::from(*x)).collect());
+
+        // take one from the last part that can spare it (not counting the very last part), and
+        // make the suffix after it as big as possible up front
+        match v[..v.len().saturating_sub(1)].iter().rposition(|x| *x > 1) {
+            Some(i) => {
+                v[i] -= 1;
+                let rest = v[i + 1..].iter().sum::<usize>() + 1;
+                let parts = v.len() - i - 1;
+                for slot in &mut v[i + 2..] {
+                    *slot = 1;
+                }
+                v[i + 1] = rest - (parts - 1);
+            }
+            None => {
+                let k = v.len();
+                self.1 = if self.2 || k >= self.0 {
+                    None
+                } else {
+                    CompositionsStream::first_with_parts(self.0, k + 1)
+                };
+            }
+        }
+        Some(Ok(ret))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        counted_size_hint(self.3)
+    }
+}
+impl Display for CompositionsStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.1 {
+            Some(v) => write!(formatter, "compositions({} @ {})", self.0, CommaSeparated(v)),
+            None => write!(formatter, "compositions(done)"),
+        }
+    }
+}
+impl Stream for CompositionsStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+    fn len(&self) -> Option<usize> {
+        self.3
+    }
+}
+
+// How streams driven by a user function handle that function failing: a bare break ends the
+// stream right there, a break with a value yields that value as the last element, and any other
+// error is yielded once. In every case the stream is stopped afterwards, which its state records
+// as Err(NErr::Break(None)).
+pub fn stop_on_func_err<T>(state: &mut NRes<T>, e: NErr) -> Option<NRes<Obj>> {
+    *state = Err(NErr::Break(None));
+    match e {
+        NErr::Break(None) => None,
+        NErr::Break(Some(v)) => Some(Ok(v)),
+        e => Some(Err(e)),
+    }
+}
+
+// moderately illegal
+// we'll treat NErr::Break as graceful termination
+#[derive(Clone)]
+pub struct Iterate(pub NRes<(Obj, Func, REnv)>);
+// directly debug-printing env can easily recurse infinitely
+impl Debug for Iterate {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match &self.0 {
+            Ok((obj, func, _)) => write!(fmt, "Iterate({:?}, {:?}, ...)", obj, func),
+            Err(NErr::Break(None)) => write!(fmt, "Iterate(stopped)"),
+            Err(e) => write!(fmt, "Iterate(ERROR: {:?})", e),
+        }
+    }
+}
+impl Iterator for Iterate {
     type Item = NRes<Obj>;
     fn next(&mut self) -> Option<NRes<Obj>> {
         match &mut self.0 {
@@ -477,7 +2148,11 @@ impl Iterator for Iterate {
                 Some(Ok(ret))
             }
             Err(NErr::Break(None)) => None,
-            Err(e) => Some(Err(e.clone())),
+            // the function failed making this element, so that's what we report
+            Err(e) => {
+                let e = e.clone();
+                stop_on_func_err(&mut self.0, e)
+            }
         }
     }
 }
@@ -490,242 +2165,2743 @@ impl Display for Iterate {
         }
     }
 }
-impl Stream for Iterate {
+impl Stream for Iterate {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    // the function can stop us by breaking
+    fn is_infinite(&self) -> Option<bool> {
+        match &self.0 {
+            Ok(_) => None,
+            Err(_) => Some(false),
+        }
+    }
+    fn len(&self) -> Option<usize> {
+        None
+    }
+}
+
+// Iterate, but yielding only this many elements. We don't call the function after the last one,
+// so it never runs more than n - 1 times. It can still break early, so len has to walk.
+#[derive(Clone)]
+pub struct IterateN(pub NRes<(Obj, Func, REnv)>, pub BigInt);
+// directly debug-printing env can easily recurse infinitely
+impl Debug for IterateN {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match &self.0 {
+            Ok((obj, func, _)) => write!(fmt, "IterateN({:?}, {:?}, {:?}, ...)", obj, func, self.1),
+            Err(NErr::Break(None)) => write!(fmt, "IterateN(stopped)"),
+            Err(e) => write!(fmt, "IterateN(ERROR: {:?})", e),
+        }
+    }
+}
+impl Iterator for IterateN {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        if self.1.sign() != Sign::Plus {
+            return None;
+        }
+        self.1 -= 1;
+        match &mut self.0 {
+            Ok((obj, func, renv)) => {
+                if self.1.sign() != Sign::Plus {
+                    let ret = std::mem::take(obj);
+                    self.0 = Err(NErr::Break(None));
+                    return Some(Ok(ret));
+                }
+                let ret = obj.clone();
+                let cur = std::mem::take(obj);
+                match func.run(renv, vec![cur]) {
+                    Ok(nxt) => {
+                        *obj = nxt;
+                    }
+                    Err(e) => {
+                        self.0 = Err(e);
+                    }
+                }
+                Some(Ok(ret))
+            }
+            Err(NErr::Break(None)) => None,
+            // the function failed making this element, so that's what we report
+            Err(e) => {
+                let e = e.clone();
+                stop_on_func_err(&mut self.0, e)
+            }
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.1.to_usize())
+    }
+}
+impl Display for IterateN {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Ok((obj, func, _)) => write!(formatter, "IterateN({}, {}, {} more)", obj, func, self.1),
+            Err(NErr::Break(None)) => write!(formatter, "IterateN(stopped)"),
+            Err(e) => write!(formatter, "IterateN(ERROR: {})", e),
+        }
+    }
+}
+impl Stream for IterateN {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+}
+
+// maybe even more illegal? not sure
+// again we'll treat NErr::Break as graceful termination
+pub struct MappedStream(pub NRes<(Box<dyn Stream>, Func, REnv)>);
+impl Clone for MappedStream {
+    fn clone(&self) -> MappedStream {
+        match &self.0 {
+            Err(e) => MappedStream(Err(e.clone())),
+            Ok((inner, func, renv)) => {
+                MappedStream(Ok((inner.clone_box(), func.clone(), renv.clone())))
+            }
+        }
+    }
+}
+// directly debug-printing env can easily recurse infinitely
+impl Debug for MappedStream {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match &self.0 {
+            Err(NErr::Break(None)) => write!(fmt, "MappedStream(stopped)"),
+            Err(e) => write!(fmt, "MappedStream(ERROR: {:?})", e),
+            Ok((inner, func, _)) => write!(fmt, "MappedStream({:?}, {:?}, ...)", inner, func),
+        }
+    }
+}
+impl Iterator for MappedStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let (inner, func, renv) = self.0.as_mut().ok()?;
+        match inner.next() {
+            Some(Err(e)) => {
+                self.0 = Err(e.clone());
+                Some(Err(e))
+            }
+            Some(Ok(cur)) => match func.run(&renv, vec![cur]) {
+                Ok(nxt) => Some(Ok(nxt)),
+                Err(e) => stop_on_func_err(&mut self.0, e),
+            },
+            None => {
+                self.0 = Err(NErr::Break(None));
+                None
+            }
+        }
+    }
+    // the function can throw, so we can only say it won't be longer
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.0 {
+            Ok((inner, _, _)) => (0, inner.size_hint().1),
+            Err(_) => (0, Some(0)),
+        }
+    }
+}
+impl Display for MappedStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Ok((inner, func, _)) => write!(formatter, "MappedStream({}, {}, ...)", inner, func),
+            Err(e) => write!(formatter, "MappedStream(ERROR: {})", e),
+        }
+    }
+}
+impl Stream for MappedStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    // the function can stop us by breaking, but can't make a finite stream infinite
+    fn is_infinite(&self) -> Option<bool> {
+        match &self.0 {
+            Ok((inner, _, _)) => inner.is_infinite().filter(|x| !x),
+            Err(_) => Some(false),
+        }
+    }
+    // breaking can make us shorter than inner, so only its len being unknown is conclusive
+    fn len(&self) -> Option<usize> {
+        match &self.0 {
+            Ok((inner, _, _)) => {
+                inner.len()?;
+                let mut s = self.clone_box();
+                let mut ret = 0;
+                while s.next().is_some() {
+                    ret += 1;
+                }
+                Some(ret)
+            }
+            Err(_) => Some(0),
+        }
+    }
+}
+// Yields [key, [elements...]] for each maximal run of elements whose key under func is the same.
+// Errors from the key function stop the stream, like MappedStream.
+pub struct GroupByStream(
+    pub NRes<(Box<dyn Stream>, Func, REnv)>,
+    pub Option<(Obj, Vec<Obj>)>,
+);
+impl Clone for GroupByStream {
+    fn clone(&self) -> GroupByStream {
+        match &self.0 {
+            Err(e) => GroupByStream(Err(e.clone()), self.1.clone()),
+            Ok((inner, func, renv)) => GroupByStream(
+                Ok((inner.clone_box(), func.clone(), renv.clone())),
+                self.1.clone(),
+            ),
+        }
+    }
+}
+// directly debug-printing env can easily recurse infinitely
+impl Debug for GroupByStream {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match &self.0 {
+            Err(NErr::Break(None)) => write!(fmt, "GroupByStream(stopped)"),
+            Err(e) => write!(fmt, "GroupByStream(ERROR: {:?})", e),
+            Ok((inner, func, _)) => write!(fmt, "GroupByStream({:?}, {:?}, ...)", inner, func),
+        }
+    }
+}
+impl Iterator for GroupByStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let (inner, func, renv) = self.0.as_mut().ok()?;
+        loop {
+            match inner.next() {
+                Some(Err(e)) => {
+                    self.0 = Err(e.clone());
+                    return Some(Err(e));
+                }
+                Some(Ok(cur)) => match func.run(renv, vec![cur.clone()]) {
+                    Ok(key) => match &mut self.1 {
+                        Some((k, group)) if *k == key => group.push(cur),
+                        pending => {
+                            if let Some((k, group)) = pending.replace((key, vec![cur])) {
+                                return Some(Ok(Obj::list(vec![k, Obj::list(group)])));
+                            }
+                        }
+                    },
+                    // as if the source ended just before this element
+                    Err(NErr::Break(None)) => {
+                        self.0 = Err(NErr::Break(None));
+                        let (k, group) = self.1.take()?;
+                        return Some(Ok(Obj::list(vec![k, Obj::list(group)])));
+                    }
+                    Err(e) => return stop_on_func_err(&mut self.0, e),
+                },
+                None => {
+                    self.0 = Err(NErr::Break(None));
+                    let (k, group) = self.1.take()?;
+                    return Some(Ok(Obj::list(vec![k, Obj::list(group)])));
+                }
+            }
+        }
+    }
+}
+impl Display for GroupByStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Ok((inner, func, _)) => write!(formatter, "GroupByStream({}, {}, ...)", inner, func),
+            Err(e) => write!(formatter, "GroupByStream(ERROR: {})", e),
+        }
+    }
+}
+impl Stream for GroupByStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    // an infinite run hangs, so an infinite stream can't promise infinitely many groups
+    fn is_infinite(&self) -> Option<bool> {
+        match &self.0 {
+            Ok((inner, _, _)) => inner.is_infinite().filter(|x| !x),
+            Err(_) => Some(false),
+        }
+    }
+}
+
+// Yields lists of consecutive elements, starting a new list between prev and cur whenever
+// func(prev, cur) is truthy. The buffer holds the list in progress. Errors from func stop the
+// stream, like MappedStream.
+pub struct SplitWhenStream(pub NRes<(Box<dyn Stream>, Func, REnv)>, pub Option<Vec<Obj>>);
+impl Clone for SplitWhenStream {
+    fn clone(&self) -> SplitWhenStream {
+        match &self.0 {
+            Err(e) => SplitWhenStream(Err(e.clone()), self.1.clone()),
+            Ok((inner, func, renv)) => SplitWhenStream(
+                Ok((inner.clone_box(), func.clone(), renv.clone())),
+                self.1.clone(),
+            ),
+        }
+    }
+}
+// directly debug-printing env can easily recurse infinitely
+impl Debug for SplitWhenStream {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match &self.0 {
+            Err(NErr::Break(None)) => write!(fmt, "SplitWhenStream(stopped)"),
+            Err(e) => write!(fmt, "SplitWhenStream(ERROR: {:?})", e),
+            Ok((inner, func, _)) => write!(fmt, "SplitWhenStream({:?}, {:?}, ...)", inner, func),
+        }
+    }
+}
+impl Iterator for SplitWhenStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let (inner, func, renv) = self.0.as_mut().ok()?;
+        loop {
+            match inner.next() {
+                Some(Err(e)) => {
+                    self.0 = Err(e.clone());
+                    return Some(Err(e));
+                }
+                Some(Ok(cur)) => match &mut self.1 {
+                    Some(buf) => {
+                        let prev = buf.last().expect("split_when: empty buffer").clone();
+                        match func.run(renv, vec![prev, cur.clone()]) {
+                            Ok(split) if split.truthy() => {
+                                let done = std::mem::replace(buf, vec![cur]);
+                                return Some(Ok(Obj::list(done)));
+                            }
+                            Ok(_) => buf.push(cur),
+                            // as if the source ended just before this element
+                            Err(NErr::Break(None)) => {
+                                self.0 = Err(NErr::Break(None));
+                                return Some(Ok(Obj::list(self.1.take()?)));
+                            }
+                            Err(e) => return stop_on_func_err(&mut self.0, e),
+                        }
+                    }
+                    None => self.1 = Some(vec![cur]),
+                },
+                None => {
+                    self.0 = Err(NErr::Break(None));
+                    return Some(Ok(Obj::list(self.1.take()?)));
+                }
+            }
+        }
+    }
+}
+impl Display for SplitWhenStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Ok((inner, func, _)) => write!(formatter, "SplitWhenStream({}, {}, ...)", inner, func),
+            Err(e) => write!(formatter, "SplitWhenStream(ERROR: {})", e),
+        }
+    }
+}
+impl Stream for SplitWhenStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    // like GroupByStream, one endless list means no more lists
+    fn is_infinite(&self) -> Option<bool> {
+        match &self.0 {
+            Ok((inner, _, _)) => inner.is_infinite().filter(|x| !x),
+            Err(_) => Some(false),
+        }
+    }
+}
+
+pub struct StridedStream(pub NRes<(Box<dyn Stream>, usize, usize)>);
+// Shared with strided lists
+pub fn check_stride(stride: usize, offset: usize) -> NRes<()> {
+    if stride == 0 {
+        Err(NErr::value_error("stride must be positive".to_string()))
+    } else if offset >= stride {
+        Err(NErr::value_error(format!(
+            "stride offset must be less than stride {}, got {}",
+            stride, offset
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+impl StridedStream {
+    // keeps elements offset, offset + stride, offset + 2 * stride, ...
+    pub fn new(inner: Box<dyn Stream>, stride: usize, offset: usize) -> NRes<StridedStream> {
+        check_stride(stride, offset)?;
+        // the position counter starts "behind" so that it hits a multiple of stride exactly when
+        // we reach the offset
+        Ok(StridedStream(Ok((
+            inner,
+            stride,
+            (stride - offset) % stride,
+        ))))
+    }
+}
+impl Clone for StridedStream {
+    fn clone(&self) -> StridedStream {
+        match &self.0 {
+            Err(e) => StridedStream(Err(e.clone())),
+            Ok((inner, stride, pos)) => StridedStream(Ok((inner.clone_box(), *stride, *pos))),
+        }
+    }
+}
+// directly debug-printing env can easily recurse infinitely
+impl Debug for StridedStream {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match &self.0 {
+            Err(NErr::Break(None)) => write!(fmt, "StridedStream(stopped)"),
+            Err(e) => write!(fmt, "StridedStream(ERROR: {:?})", e),
+            Ok((inner, stride, size)) => write!(
+                fmt,
+                "StridedStream({:?}, {:?}, {:?},...)",
+                inner, stride, size
+            ),
+        }
+    }
+}
+impl Iterator for StridedStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let (inner, stride, size) = self.0.as_mut().ok()?;
+        loop {
+            match inner.next() {
+                Some(Err(e)) => {
+                    self.0 = Err(e.clone());
+                    return Some(Err(e));
+                }
+                Some(Ok(cur)) => {
+                    if *size % *stride == 0 {
+                        *size += 1;
+                        return Some(Ok(cur));
+                    }
+                    *size += 1;
+                }
+                None => {
+                    self.0 = Err(NErr::Break(None));
+                    return None;
+                }
+            }
+        }
+    }
+}
+impl Display for StridedStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Ok((inner, stride, pos)) => write!(
+                formatter,
+                "StridedStream({}, {}, {}, ...)",
+                inner, stride, pos
+            ),
+            Err(e) => write!(formatter, "StridedStream(ERROR: {})", e),
+        }
+    }
+}
+impl Stream for StridedStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        match &self.0 {
+            Ok((inner, _, _)) => inner.is_infinite(),
+            Err(_) => Some(false),
+        }
+    }
+    fn len(&self) -> Option<usize> {
+        match &self.0 {
+            Ok((inner, stride, pos)) => {
+                // the next element we keep is this many inner elements away
+                let skip = (stride - pos % stride) % stride;
+                Some(match inner.len()? {
+                    n if n <= skip => 0,
+                    n => (n - skip - 1) / stride + 1,
+                })
+            }
+            Err(_) => Some(0),
+        }
+    }
+}
+
+// TODO: remove ScannedStream and MappedStream with dyn Iterator i.e. type erased iterators
+// A None initial value means use the stream's first element, like scanl1.
+type ScanState = (Box<dyn Stream>, Option<Obj>, Func, REnv);
+pub struct ScannedStream(pub NRes<ScanState>, pub Option<Obj>);
+impl Clone for ScannedStream {
+    fn clone(&self) -> ScannedStream {
+        match &self.0 {
+            Err(e) => ScannedStream(Err(e.clone()), self.1.clone()),
+            Ok((inner, init, func, renv)) => ScannedStream(
+                Ok((inner.clone_box(), init.clone(), func.clone(), renv.clone())),
+                self.1.clone(),
+            ),
+        }
+    }
+}
+// directly debug-printing env can easily recurse infinitely
+impl Debug for ScannedStream {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match &self.0 {
+            Err(NErr::Break(None)) => write!(fmt, "ScannedStream(stopped)"),
+            Err(e) => write!(fmt, "ScannedStream(ERROR: {:?})", e),
+            Ok((inner, Some(init), func, _)) => write!(
+                fmt,
+                "ScannedStream({:?}, {:?}, {:?}, ...)",
+                inner, init, func
+            ),
+            Ok((inner, None, func, _)) => {
+                write!(fmt, "ScannedStream({:?}, {:?}, ...)", inner, func)
+            }
+        }
+    }
+}
+impl Iterator for ScannedStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let (inner, init, func, renv) = self.0.as_mut().ok()?;
+        if let Some(acc) = self.1.take() {
+            match inner.next() {
+                Some(Err(e)) => {
+                    self.0 = Err(e.clone());
+                    Some(Err(e))
+                }
+                Some(Ok(cur)) => match func.run(&renv, vec![acc, cur]) {
+                    Ok(nxt) => {
+                        self.1 = Some(nxt.clone());
+                        Some(Ok(nxt))
+                    }
+                    Err(e) => stop_on_func_err(&mut self.0, e),
+                },
+                None => {
+                    self.0 = Err(NErr::Break(None));
+                    None
+                }
+            }
+        } else {
+            let first = match init {
+                Some(init) => init.clone(),
+                None => match inner.next() {
+                    Some(Ok(x)) => x,
+                    Some(Err(e)) => {
+                        self.0 = Err(e.clone());
+                        return Some(Err(e));
+                    }
+                    None => {
+                        self.0 = Err(NErr::Break(None));
+                        return None;
+                    }
+                },
+            };
+            self.1 = Some(first.clone());
+            Some(Ok(first))
+        }
+    }
+}
+impl Display for ScannedStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Ok((inner, Some(init), func, _)) => write!(
+                formatter,
+                "ScannedStream({}, {}, {}, ...)",
+                inner, init, func
+            ),
+            Ok((inner, None, func, _)) => {
+                write!(formatter, "ScannedStream({}, {}, ...)", inner, func)
+            }
+            Err(e) => write!(formatter, "ScannedStream(ERROR: {})", e),
+        }
+    }
+}
+impl Stream for ScannedStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        match &self.0 {
+            Ok((inner, _, _, _)) => inner.is_infinite().filter(|x| !x),
+            Err(_) => Some(false),
+        }
+    }
+}
+
+// Pairs [i, x], with the index starting at .1 and going up by .2 each element
+#[derive(Debug)]
+pub struct EnumerateStream(pub Box<dyn Stream>, pub BigInt, pub BigInt);
+impl Clone for EnumerateStream {
+    fn clone(&self) -> EnumerateStream {
+        EnumerateStream(self.0.clone_box(), self.1.clone(), self.2.clone())
+    }
+}
+impl Iterator for EnumerateStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        match self.0.next()? {
+            Ok(x) => {
+                let i = self.1.clone();
+                self.1 += &self.2;
+                Some(Ok(Obj::list(vec![Obj::from(i), x])))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+impl Display for EnumerateStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "enumerate({}, {}, {})", self.0, self.1, self.2)
+    }
+}
+impl Stream for EnumerateStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn advance_by(&mut self, n: usize) -> NRes<()> {
+        Stream::advance_by(&mut *self.0, n)?;
+        self.1 += &self.2 * n;
+        Ok(())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        self.0.is_infinite()
+    }
+    fn len(&self) -> Option<usize> {
+        self.0.len()
+    }
+}
+
+// A finite, already-materialized sequence, so it can sit alongside other streams
+#[derive(Debug, Clone)]
+pub struct ListStream(pub Rc<Vec<Obj>>, pub usize);
+impl Iterator for ListStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let ret = self.0.get(self.1)?.clone();
+        self.1 += 1;
+        Some(Ok(ret))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        exact_size_hint(Stream::len(self))
+    }
+}
+impl Display for ListStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "ListStream({} @ {})", CommaSeparated(&self.0), self.1)
+    }
+}
+impl Stream for ListStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn cheap_prefix(&self, n: usize) -> Option<(Vec<Obj>, bool)> {
+        cloned_prefix(self, n)
+    }
+    fn advance_by(&mut self, n: usize) -> NRes<()> {
+        self.1 = self.1.saturating_add(n).min(self.0.len());
+        Ok(())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+    fn len(&self) -> Option<usize> {
+        Some(self.0.len().saturating_sub(self.1))
+    }
+    fn force(&self) -> NRes<Vec<Obj>> {
+        Ok(self.0.get(self.1..).unwrap_or(&[]).to_vec())
+    }
+    fn pythonic_index_isize(&self, i: isize) -> NRes<Obj> {
+        let rest = self.0.get(self.1..).unwrap_or(&[]);
+        Ok(rest[crate::core::pythonic_index_isize(rest, i)?].clone())
+    }
+    fn last(&self) -> NRes<Obj> {
+        match self.0.get(self.1..).and_then(|rest| rest.last()) {
+            Some(x) => Ok(x.clone()),
+            None => Err(NErr::value_error("empty stream".to_string())),
+        }
+    }
+}
+
+// Concatenates the sequences an outer stream yields, pulling each one only when we get to it.
+// Inner streams are walked as streams, so they aren't forced either; the Option is the one we're
+// in the middle of.
+#[derive(Debug)]
+pub struct FlattenStream(pub Box<dyn Stream>, pub Option<Box<dyn Stream>>);
+impl FlattenStream {
+    fn inner(obj: Obj) -> NRes<Box<dyn Stream>> {
+        match obj {
+            Obj::Seq(Seq::Stream(s)) => Ok(s.clone_box()),
+            Obj::Seq(Seq::List(v)) => Ok(Box::new(ListStream(v, 0))),
+            mut obj => {
+                let v = mut_obj_into_iter(&mut obj, "flatten (inner)")?.collect::<NRes<Vec<Obj>>>()?;
+                Ok(Box::new(ListStream(Rc::new(v), 0)))
+            }
+        }
+    }
+}
+impl Clone for FlattenStream {
+    fn clone(&self) -> FlattenStream {
+        FlattenStream(self.0.clone_box(), self.1.as_ref().map(|s| s.clone_box()))
+    }
+}
+impl Iterator for FlattenStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        loop {
+            if let Some(inner) = &mut self.1 {
+                match inner.next() {
+                    Some(x) => return Some(x),
+                    None => self.1 = None,
+                }
+            }
+            match self.0.next()?.and_then(FlattenStream::inner) {
+                Ok(inner) => self.1 = Some(inner),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+impl Display for FlattenStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.1 {
+            Some(inner) => write!(formatter, "FlattenStream({}, in {})", self.0, inner),
+            None => write!(formatter, "FlattenStream({})", self.0),
+        }
+    }
+}
+impl Stream for FlattenStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    // we can't know what later inner sequences will be like until we get to them
+    fn is_infinite(&self) -> Option<bool> {
+        let inner = match &self.1 {
+            Some(inner) => inner.is_infinite()?,
+            None => false,
+        };
+        if inner {
+            Some(true)
+        } else if cheap_len(&*self.0) == Some(0) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+    // only when the outer elements are already sitting there (a list, or a cache that's been
+    // forced) and each knows its own length; anything else would mean pulling from the outer
+    // stream, which could run user functions to make the inner sequences
+    fn len(&self) -> Option<usize> {
+        let outer = match self.0.cheap_prefix(cheap_len(&*self.0)?)? {
+            (xs, true) => xs,
+            (_, false) => return None,
+        };
+        let mut ret = match &self.1 {
+            Some(inner) => cheap_len(&**inner)?,
+            None => 0,
+        };
+        for e in outer {
+            ret = ret.checked_add(match e {
+                Obj::Seq(Seq::Stream(s)) => cheap_len(&*s)?,
+                Obj::Seq(s) => s.len()?,
+                _ => return None,
+            })?;
+        }
+        Some(ret)
+    }
+}
+
+// Yields everything from the front stream, then drops it and moves on to the next
+#[derive(Debug)]
+pub struct ChainStream(pub VecDeque<Box<dyn Stream>>);
+impl Clone for ChainStream {
+    fn clone(&self) -> ChainStream {
+        ChainStream(self.0.iter().map(|s| s.clone_box()).collect())
+    }
+}
+impl Iterator for ChainStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        loop {
+            match self.0.front_mut()?.next() {
+                Some(x) => return Some(x),
+                None => {
+                    self.0.pop_front();
+                }
+            }
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.iter().fold((0, Some(0)), |(lo, hi), s| {
+            let (slo, shi) = s.size_hint();
+            (lo.saturating_add(slo), hi.zip(shi).and_then(|(a, b)| a.checked_add(b)))
+        })
+    }
+}
+impl Display for ChainStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "ChainStream(")?;
+        for (i, s) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(formatter, ", ")?;
+            }
+            write!(formatter, "{}", s)?;
+        }
+        write!(formatter, ")")
+    }
+}
+impl Stream for ChainStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        let mut ret = Some(false);
+        for s in self.0.iter() {
+            match s.is_infinite() {
+                Some(true) => return Some(true),
+                Some(false) => {}
+                None => ret = None,
+            }
+        }
+        ret
+    }
+    fn len(&self) -> Option<usize> {
+        self.0
+            .iter()
+            .try_fold(0usize, |acc, s| acc.checked_add(s.len()?))
+    }
+    fn cheap_prefix(&self, n: usize) -> Option<(Vec<Obj>, bool)> {
+        let mut ret = Vec::new();
+        for s in self.0.iter() {
+            let (xs, all) = s.cheap_prefix(n - ret.len())?;
+            ret.extend(xs);
+            if !all {
+                return Some((ret, false));
+            }
+        }
+        Some((ret, true))
+    }
+    // chain(a, b) reversed is chain(reverse(b), reverse(a)), which stays lazy as long as the
+    // parts reverse lazily
+    fn reversed(&self) -> NRes<Seq> {
+        let mut parts = VecDeque::with_capacity(self.0.len());
+        for s in self.0.iter().rev() {
+            if s.is_infinite() == Some(true) {
+                return Err(NErr::value_error(format!(
+                    "Cannot reverse {} because it's infinite",
+                    self
+                )));
+            }
+            parts.push_back(match s.reversed()? {
+                Seq::Stream(r) => r.clone_box(),
+                Seq::List(v) => Box::new(ListStream(v, 0)) as Box<dyn Stream>,
+                mut r => Box::new(ListStream(
+                    Rc::new(mut_seq_into_iter(&mut r).collect::<NRes<Vec<Obj>>>()?),
+                    0,
+                )),
+            });
+        }
+        Ok(Seq::Stream(Rc::new(ChainStream(parts))))
+    }
+}
+
+// Round-robin: one element from each stream in turn, skipping (and dropping) ones that have ended.
+// The usize is whose turn it is.
+#[derive(Debug)]
+pub struct InterleaveStream(pub Vec<Option<Box<dyn Stream>>>, pub usize);
+impl Clone for InterleaveStream {
+    fn clone(&self) -> InterleaveStream {
+        InterleaveStream(
+            self.0
+                .iter()
+                .map(|s| s.as_ref().map(|s| s.clone_box()))
+                .collect(),
+            self.1,
+        )
+    }
+}
+impl Iterator for InterleaveStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let n = self.0.len();
+        for _ in 0..n {
+            let i = self.1;
+            self.1 = (self.1 + 1) % n;
+            if let Some(s) = &mut self.0[i] {
+                match s.next() {
+                    Some(x) => return Some(x),
+                    None => self.0[i] = None,
+                }
+            }
+        }
+        None
+    }
+}
+impl Display for InterleaveStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "InterleaveStream(")?;
+        for (i, s) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(formatter, ", ")?;
+            }
+            match s {
+                Some(s) => write!(formatter, "{}", s)?,
+                None => write!(formatter, "done")?,
+            }
+        }
+        write!(formatter, " @ {})", self.1)
+    }
+}
+impl Stream for InterleaveStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        let mut ret = Some(false);
+        for s in self.0.iter().flatten() {
+            match s.is_infinite() {
+                Some(true) => return Some(true),
+                Some(false) => {}
+                None => ret = None,
+            }
+        }
+        ret
+    }
+    fn len(&self) -> Option<usize> {
+        self.0.iter().try_fold(0usize, |acc, s| match s {
+            Some(s) => acc.checked_add(s.len()?),
+            None => Some(acc),
+        })
+    }
+}
+
+// Skips elements equal to the last one we emitted. Lazy, but asking for the next element of
+// something like repeat(1) after the first will hang, since there's always one more duplicate to
+// skip; it's only safe on infinite streams whose runs of duplicates are all finite.
+#[derive(Debug)]
+pub struct DedupStream(pub Box<dyn Stream>, pub Option<Obj>);
+impl Clone for DedupStream {
+    fn clone(&self) -> DedupStream {
+        DedupStream(self.0.clone_box(), self.1.clone())
+    }
+}
+impl Iterator for DedupStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        loop {
+            match self.0.next()? {
+                Ok(x) => {
+                    if self.1.as_ref() != Some(&x) {
+                        self.1 = Some(x.clone());
+                        return Some(Ok(x));
+                    }
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+impl Display for DedupStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.1 {
+            Some(last) => write!(formatter, "DedupStream({}, after {})", self.0, last),
+            None => write!(formatter, "DedupStream({})", self.0),
+        }
+    }
+}
+impl Stream for DedupStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    // an infinite stream might dedup down to finitely many, but then asking for more hangs anyway
+    fn is_infinite(&self) -> Option<bool> {
+        self.0.is_infinite().filter(|x| !x)
+    }
+}
+
+// Like DedupStream, but f(last, x) decides whether x is a duplicate of the last element we emitted,
+// and so skipped. The same caveat about infinite runs applies.
+pub struct DedupByStream(pub NRes<(Box<dyn Stream>, Func, REnv)>, pub Option<Obj>);
+impl Clone for DedupByStream {
+    fn clone(&self) -> DedupByStream {
+        match &self.0 {
+            Err(e) => DedupByStream(Err(e.clone()), self.1.clone()),
+            Ok((inner, func, renv)) => DedupByStream(
+                Ok((inner.clone_box(), func.clone(), renv.clone())),
+                self.1.clone(),
+            ),
+        }
+    }
+}
+// directly debug-printing env can easily recurse infinitely
+impl Debug for DedupByStream {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match &self.0 {
+            Err(NErr::Break(None)) => write!(fmt, "DedupByStream(stopped)"),
+            Err(e) => write!(fmt, "DedupByStream(ERROR: {:?})", e),
+            Ok((inner, func, _)) => {
+                write!(fmt, "DedupByStream({:?}, {:?}, {:?}, ...)", inner, func, self.1)
+            }
+        }
+    }
+}
+impl Iterator for DedupByStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        loop {
+            let (inner, func, renv) = self.0.as_mut().ok()?;
+            let x = match inner.next() {
+                Some(Ok(x)) => x,
+                Some(Err(e)) => {
+                    self.0 = Err(e.clone());
+                    return Some(Err(e));
+                }
+                None => {
+                    self.0 = Err(NErr::Break(None));
+                    return None;
+                }
+            };
+            let dup = match &self.1 {
+                None => false,
+                Some(last) => match func.run(renv, vec![last.clone(), x.clone()]) {
+                    Ok(r) => r.truthy(),
+                    Err(e) => return stop_on_func_err(&mut self.0, e),
+                },
+            };
+            if !dup {
+                self.1 = Some(x.clone());
+                return Some(Ok(x));
+            }
+        }
+    }
+}
+impl Display for DedupByStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.0, &self.1) {
+            (Ok((inner, func, _)), Some(last)) => {
+                write!(formatter, "DedupByStream({}, {}, after {})", inner, func, last)
+            }
+            (Ok((inner, func, _)), None) => write!(formatter, "DedupByStream({}, {})", inner, func),
+            (Err(e), _) => write!(formatter, "DedupByStream(ERROR: {})", e),
+        }
+    }
+}
+impl Stream for DedupByStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        match &self.0 {
+            Ok((inner, _, _)) => inner.is_infinite().filter(|x| !x),
+            Err(_) => Some(false),
+        }
+    }
+}
+
+// Yields [value, count] for each maximal run of equal elements. A run is only emitted once we see
+// the element after it (or the end), so like dedup, an infinite run hangs.
+#[derive(Debug)]
+pub struct GroupRunsStream(pub Box<dyn Stream>, pub Option<(Obj, usize)>);
+impl Clone for GroupRunsStream {
+    fn clone(&self) -> GroupRunsStream {
+        GroupRunsStream(self.0.clone_box(), self.1.clone())
+    }
+}
+impl Iterator for GroupRunsStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        loop {
+            match self.0.next() {
+                Some(Ok(x)) => match &mut self.1 {
+                    Some((v, count)) if *v == x => *count += 1,
+                    pending => {
+                        if let Some((v, count)) = pending.replace((x, 1)) {
+                            return Some(Ok(Obj::list(vec![v, Obj::from(count)])));
+                        }
+                    }
+                },
+                Some(Err(e)) => return Some(Err(e)),
+                None => {
+                    let (v, count) = self.1.take()?;
+                    return Some(Ok(Obj::list(vec![v, Obj::from(count)])));
+                }
+            }
+        }
+    }
+}
+impl Display for GroupRunsStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.1 {
+            Some((v, count)) => write!(formatter, "GroupRunsStream({}, {} x {})", self.0, v, count),
+            None => write!(formatter, "GroupRunsStream({})", self.0),
+        }
+    }
+}
+impl Stream for GroupRunsStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        self.0.is_infinite().filter(|x| !x)
+    }
+}
+
+// Yields each element the first time it appears. Like dedup, asking for another element after
+// the last distinct one will hang on infinite streams.
+#[derive(Debug)]
+pub struct UniqueStream(pub Box<dyn Stream>, pub HashSet<ObjKey>);
+impl Clone for UniqueStream {
+    fn clone(&self) -> UniqueStream {
+        UniqueStream(self.0.clone_box(), self.1.clone())
+    }
+}
+impl Iterator for UniqueStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        loop {
+            match self.0.next()? {
+                Ok(x) => match to_key(x.clone()) {
+                    Ok(k) => {
+                        if self.1.insert(k) {
+                            return Some(Ok(x));
+                        }
+                    }
+                    Err(e) => return Some(Err(e)),
+                },
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+impl Display for UniqueStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "UniqueStream({}, {} seen)", self.0, self.1.len())
+    }
+}
+impl Stream for UniqueStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        self.0.is_infinite().filter(|x| !x)
+    }
+}
+
+// Yields the best element so far at each step: the minimum if the bias is Less, the maximum if
+// it's Greater. Ties keep the earlier element, like min/max.
+#[derive(Debug)]
+pub struct RunningExtremumStream(pub Box<dyn Stream>, pub Option<Obj>, pub Ordering);
+impl Clone for RunningExtremumStream {
+    fn clone(&self) -> RunningExtremumStream {
+        RunningExtremumStream(self.0.clone_box(), self.1.clone(), self.2)
+    }
+}
+impl Iterator for RunningExtremumStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let x = match self.0.next()? {
+            Ok(x) => x,
+            Err(e) => return Some(Err(e)),
+        };
+        let better = match &self.1 {
+            None => true,
+            Some(best) => match ncmp(&x, best) {
+                Ok(ord) => ord == self.2,
+                Err(e) => return Some(Err(e)),
+            },
+        };
+        if better {
+            self.1 = Some(x);
+        }
+        self.1.clone().map(Ok)
+    }
+    // comparing can fail, so this is only an upper bound
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.0.size_hint().1)
+    }
+}
+impl Display for RunningExtremumStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self.2 {
+            Ordering::Greater => "RunningMaxStream",
+            _ => "RunningMinStream",
+        };
+        match &self.1 {
+            Some(best) => write!(formatter, "{}({}, best {})", name, self.0, best),
+            None => write!(formatter, "{}({})", name, self.0),
+        }
+    }
+}
+impl Stream for RunningExtremumStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        self.0.is_infinite()
+    }
+    fn len(&self) -> Option<usize> {
+        self.0.len()
+    }
+}
+
+// Running totals, starting from the first element rather than a leading zero. Int + int stays
+// exact and any float promotes, per NNum addition.
+#[derive(Debug)]
+pub struct PrefixSumsStream(pub Box<dyn Stream>, pub Option<NNum>);
+impl Clone for PrefixSumsStream {
+    fn clone(&self) -> PrefixSumsStream {
+        PrefixSumsStream(self.0.clone_box(), self.1.clone())
+    }
+}
+impl Iterator for PrefixSumsStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let x = match self.0.next()? {
+            Ok(Obj::Num(x)) => x,
+            Ok(x) => {
+                return Some(Err(NErr::type_error(format!(
+                    "not a number: {}",
+                    FmtObj::debug(&x)
+                ))))
+            }
+            Err(e) => return Some(Err(e)),
+        };
+        let total = match self.1.take() {
+            None => x,
+            Some(t) => &t + &x,
+        };
+        self.1 = Some(total.clone());
+        Some(Ok(Obj::Num(total)))
+    }
+    // a non-number is an error, so this is only an upper bound
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.0.size_hint().1)
+    }
+}
+impl Display for PrefixSumsStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.1 {
+            Some(total) => write!(formatter, "PrefixSumsStream({}, total {})", self.0, total),
+            None => write!(formatter, "PrefixSumsStream({})", self.0),
+        }
+    }
+}
+impl Stream for PrefixSumsStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        self.0.is_infinite()
+    }
+    fn len(&self) -> Option<usize> {
+        self.0.len()
+    }
+}
+
+// Maximum of each length-w window. The deque holds (index, value) pairs with strictly decreasing
+// values, so the front is always the current window's max and each element is pushed and popped
+// at most once. Elements that can't be compared are an error, after which the stream is stopped,
+// since the window no longer matches the input.
+type SlidingMaxState = (Box<dyn Stream>, VecDeque<(usize, Obj)>, usize);
+#[derive(Debug)]
+pub struct SlidingMaxStream(pub NRes<SlidingMaxState>, pub usize);
+impl SlidingMaxStream {
+    pub fn new(inner: Box<dyn Stream>, w: usize) -> NRes<SlidingMaxStream> {
+        if w == 0 {
+            Err(NErr::value_error("can't window 0".to_string()))
+        } else {
+            Ok(SlidingMaxStream(Ok((inner, VecDeque::new(), 0)), w))
+        }
+    }
+}
+impl Clone for SlidingMaxStream {
+    fn clone(&self) -> SlidingMaxStream {
+        match &self.0 {
+            Err(e) => SlidingMaxStream(Err(e.clone()), self.1),
+            Ok((inner, window, i)) => {
+                SlidingMaxStream(Ok((inner.clone_box(), window.clone(), *i)), self.1)
+            }
+        }
+    }
+}
+impl Iterator for SlidingMaxStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let w = self.1;
+        loop {
+            let (inner, window, i) = self.0.as_mut().ok()?;
+            let x = match inner.next()? {
+                Ok(x) => x,
+                Err(e) => return Some(Err(e)),
+            };
+            let j = *i;
+            *i += 1;
+            while let Some((_, back)) = window.back() {
+                match ncmp(back, &x) {
+                    Ok(Ordering::Greater) => break,
+                    Ok(_) => {
+                        window.pop_back();
+                    }
+                    Err(e) => return stop_on_func_err(&mut self.0, e),
+                }
+            }
+            window.push_back((j, x));
+            if window.front().is_some_and(|(k, _)| k + w <= j) {
+                window.pop_front();
+            }
+            if j + 1 >= w {
+                return window.front().map(|(_, m)| Ok(m.clone()));
+            }
+        }
+    }
+}
+impl Display for SlidingMaxStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Ok((inner, _, _)) => write!(formatter, "SlidingMaxStream({}, {})", inner, self.1),
+            Err(NErr::Break(None)) => write!(formatter, "SlidingMaxStream(stopped)"),
+            Err(e) => write!(formatter, "SlidingMaxStream(ERROR: {})", e),
+        }
+    }
+}
+impl Stream for SlidingMaxStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        match &self.0 {
+            Ok((inner, _, _)) => inner.is_infinite(),
+            Err(_) => Some(false),
+        }
+    }
+    fn len(&self) -> Option<usize> {
+        match &self.0 {
+            Ok((inner, _, consumed)) => {
+                // outputs start once w elements have been consumed in total
+                let total = consumed + inner.len()?;
+                Some(total.saturating_sub(self.1 - 1) - consumed.saturating_sub(self.1 - 1))
+            }
+            Err(_) => Some(0),
+        }
+    }
+}
+
+// f applied to each length-w window. The deque is a ring buffer of the current window, so the
+// input isn't materialized, though f still gets each window as a list; it can break like in
+// lazy_map.
+pub struct WindowReduceStream(pub NRes<(Box<dyn Stream>, Func, REnv)>, pub usize, pub VecDeque<Obj>);
+impl WindowReduceStream {
+    pub fn new(inner: Box<dyn Stream>, w: usize, f: Func, renv: REnv) -> NRes<WindowReduceStream> {
+        if w == 0 {
+            Err(NErr::value_error("can't window 0".to_string()))
+        } else {
+            Ok(WindowReduceStream(Ok((inner, f, renv)), w, VecDeque::with_capacity(w)))
+        }
+    }
+}
+impl Clone for WindowReduceStream {
+    fn clone(&self) -> WindowReduceStream {
+        match &self.0 {
+            Err(e) => WindowReduceStream(Err(e.clone()), self.1, self.2.clone()),
+            Ok((inner, func, renv)) => WindowReduceStream(
+                Ok((inner.clone_box(), func.clone(), renv.clone())),
+                self.1,
+                self.2.clone(),
+            ),
+        }
+    }
+}
+// directly debug-printing env can easily recurse infinitely
+impl Debug for WindowReduceStream {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match &self.0 {
+            Err(NErr::Break(None)) => write!(fmt, "WindowReduceStream(stopped)"),
+            Err(e) => write!(fmt, "WindowReduceStream(ERROR: {:?})", e),
+            Ok((inner, func, _)) => write!(
+                fmt,
+                "WindowReduceStream({:?}, {:?}, {}, {:?}, ...)",
+                inner, func, self.1, self.2
+            ),
+        }
+    }
+}
+impl Iterator for WindowReduceStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let (inner, func, renv) = self.0.as_mut().ok()?;
+        if self.2.len() == self.1 {
+            self.2.pop_front();
+        }
+        while self.2.len() < self.1 {
+            match inner.next() {
+                Some(Ok(x)) => self.2.push_back(x),
+                Some(Err(e)) => {
+                    self.0 = Err(e.clone());
+                    return Some(Err(e));
+                }
+                None => {
+                    self.0 = Err(NErr::Break(None));
+                    return None;
+                }
+            }
+        }
+        match func.run(renv, vec![Obj::list(self.2.iter().cloned().collect())]) {
+            Ok(r) => Some(Ok(r)),
+            Err(e) => stop_on_func_err(&mut self.0, e),
+        }
+    }
+}
+impl Display for WindowReduceStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Ok((inner, func, _)) => {
+                write!(formatter, "WindowReduceStream({}, {}, {}, ...)", inner, func, self.1)
+            }
+            Err(e) => write!(formatter, "WindowReduceStream(ERROR: {})", e),
+        }
+    }
+}
+impl Stream for WindowReduceStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    // the function can stop us by breaking, but can't make a finite stream infinite
+    fn is_infinite(&self) -> Option<bool> {
+        match &self.0 {
+            Ok((inner, _, _)) => inner.is_infinite().filter(|x| !x),
+            Err(_) => Some(false),
+        }
+    }
+    fn len(&self) -> Option<usize> {
+        match &self.0 {
+            // a full buffer has already been reported, and the next window drops its front
+            Ok((inner, _, _)) => Some(
+                (inner.len()? + self.2.len()).saturating_sub(self.1 - 1)
+                    - usize::from(self.2.len() == self.1),
+            ),
+            Err(_) => Some(0),
+        }
+    }
+}
+
+// Merges two streams that are each assumed to be sorted by total_cmp, so elements of mixed types
+// merge in the order sort would put them. Holds at most one pulled element from each side; on ties
+// the left one comes first.
+#[derive(Debug)]
+pub struct MergeSortedStream(
+    pub Box<dyn Stream>,
+    pub Box<dyn Stream>,
+    pub Option<Obj>,
+    pub Option<Obj>,
+);
+impl Clone for MergeSortedStream {
+    fn clone(&self) -> MergeSortedStream {
+        MergeSortedStream(
+            self.0.clone_box(),
+            self.1.clone_box(),
+            self.2.clone(),
+            self.3.clone(),
+        )
+    }
+}
+impl Iterator for MergeSortedStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        if self.2.is_none() {
+            match self.0.next() {
+                Some(Ok(x)) => self.2 = Some(x),
+                Some(Err(e)) => return Some(Err(e)),
+                None => {}
+            }
+        }
+        if self.3.is_none() {
+            match self.1.next() {
+                Some(Ok(x)) => self.3 = Some(x),
+                Some(Err(e)) => return Some(Err(e)),
+                None => {}
+            }
+        }
+        let take_left = match (&self.2, &self.3) {
+            (Some(a), Some(b)) => total_cmp(a, b) != Ordering::Greater,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => return None,
+        };
+        if take_left {
+            self.2.take().map(Ok)
+        } else {
+            self.3.take().map(Ok)
+        }
+    }
+}
+impl Display for MergeSortedStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "MergeSortedStream({}, {})", self.0, self.1)
+    }
+}
+impl Stream for MergeSortedStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        match (self.0.is_infinite(), self.1.is_infinite()) {
+            (Some(true), _) | (_, Some(true)) => Some(true),
+            (Some(false), Some(false)) => Some(false),
+            _ => None,
+        }
+    }
+    fn len(&self) -> Option<usize> {
+        let held = self.2.is_some() as usize + self.3.is_some() as usize;
+        self.0.len()?.checked_add(self.1.len()?)?.checked_add(held)
+    }
+}
+
+// Consecutive lists of strings whose total size is at most n, where a string's size is its
+// number of chars (not bytes) and a bytes element's is its number of bytes. Greedy: a chunk ends
+// just before the element that would push it over. An element bigger than n alone gets a chunk
+// to itself. The Option holds an element pulled but not yet placed.
+#[derive(Debug)]
+pub struct ChunkBySizeStream(pub Box<dyn Stream>, pub usize, pub Option<Obj>);
+impl Clone for ChunkBySizeStream {
+    fn clone(&self) -> ChunkBySizeStream {
+        ChunkBySizeStream(self.0.clone_box(), self.1, self.2.clone())
+    }
+}
+fn chunk_size(x: &Obj) -> NRes<usize> {
+    match x {
+        Obj::Seq(Seq::String(s)) => Ok(s.chars().count()),
+        Obj::Seq(Seq::Bytes(b)) => Ok(b.len()),
+        x => Err(NErr::type_error(format!(
+            "chunk_by_size: not a string or bytes: {}",
+            FmtObj::debug(x)
+        ))),
+    }
+}
+impl Iterator for ChunkBySizeStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let mut chunk = Vec::new();
+        let mut total = 0usize;
+        loop {
+            let x = match self.2.take() {
+                Some(x) => x,
+                None => match self.0.next() {
+                    Some(Ok(x)) => x,
+                    Some(Err(e)) => return Some(Err(e)),
+                    None => break,
+                },
+            };
+            let size = match chunk_size(&x) {
+                Ok(size) => size,
+                Err(e) => return Some(Err(e)),
+            };
+            if !chunk.is_empty() && total.saturating_add(size) > self.1 {
+                self.2 = Some(x);
+                break;
+            }
+            total = total.saturating_add(size);
+            chunk.push(x);
+        }
+        if chunk.is_empty() {
+            None
+        } else {
+            Some(Ok(Obj::list(chunk)))
+        }
+    }
+}
+impl Display for ChunkBySizeStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "chunk_by_size({}, {})", self.1, self.0)
+    }
+}
+impl Stream for ChunkBySizeStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        self.0.is_infinite()
+    }
+}
+
+// Intersection (bool true) or difference (false) of two streams that are each assumed to be sorted
+// by total_cmp. Counts multiplicities like a multiset: each element of the right side cancels at
+// most one equal element of the left. Holds at most one pulled element from the right.
+#[derive(Debug)]
+pub struct SortedSetOpStream(pub Box<dyn Stream>, pub Box<dyn Stream>, pub Option<Obj>, pub bool);
+impl Clone for SortedSetOpStream {
+    fn clone(&self) -> SortedSetOpStream {
+        SortedSetOpStream(self.0.clone_box(), self.1.clone_box(), self.2.clone(), self.3)
+    }
+}
+impl Iterator for SortedSetOpStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        'left: loop {
+            let x = match self.0.next()? {
+                Ok(x) => x,
+                Err(e) => return Some(Err(e)),
+            };
+            loop {
+                if self.2.is_none() {
+                    match self.1.next() {
+                        Some(Ok(y)) => self.2 = Some(y),
+                        Some(Err(e)) => return Some(Err(e)),
+                        // nothing left to intersect with, or to take away
+                        None => return if self.3 { None } else { Some(Ok(x)) },
+                    }
+                }
+                match self.2.as_ref().map(|y| total_cmp(y, &x)) {
+                    Some(Ordering::Less) => self.2 = None,
+                    Some(Ordering::Equal) => {
+                        self.2 = None;
+                        if self.3 {
+                            return Some(Ok(x));
+                        }
+                        continue 'left;
+                    }
+                    _ => {
+                        if self.3 {
+                            continue 'left;
+                        }
+                        return Some(Ok(x));
+                    }
+                }
+            }
+        }
+    }
+}
+impl Display for SortedSetOpStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let name = if self.3 { "intersect_sorted" } else { "diff_sorted" };
+        write!(formatter, "{}({}, {})", name, self.0, self.1)
+    }
+}
+impl Stream for SortedSetOpStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        match (self.0.is_infinite(), self.1.is_infinite(), self.3) {
+            (Some(false), _, _) | (_, Some(false), true) => Some(false),
+            // only finitely many can be taken away
+            (Some(true), Some(false), false) => Some(true),
+            _ => None,
+        }
+    }
+}
+
+// Everything but the last n elements, holding the n most recent in a ring buffer and only letting
+// one go once n newer ones have arrived.
+#[derive(Debug)]
+pub struct DropLastStream(pub Box<dyn Stream>, pub usize, pub VecDeque<Obj>);
+impl Clone for DropLastStream {
+    fn clone(&self) -> DropLastStream {
+        DropLastStream(self.0.clone_box(), self.1, self.2.clone())
+    }
+}
+impl Iterator for DropLastStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        loop {
+            match self.0.next()? {
+                Ok(x) => {
+                    self.2.push_back(x);
+                    if self.2.len() > self.1 {
+                        return self.2.pop_front().map(Ok);
+                    }
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+impl Display for DropLastStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "DropLastStream({}, {})", self.0, self.1)
+    }
+}
+impl Stream for DropLastStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        self.0.is_infinite()
+    }
+    fn len(&self) -> Option<usize> {
+        Some((self.0.len()? + self.2.len()).saturating_sub(self.1))
+    }
+}
+
+// One element of lookahead: peek pulls the next element into the buffer without consuming it.
+#[derive(Debug)]
+pub struct PeekableStream(pub Box<dyn Stream>, pub Option<NRes<Obj>>);
+impl PeekableStream {
+    pub fn new(inner: Box<dyn Stream>) -> PeekableStream {
+        PeekableStream(inner, None)
+    }
+
+    pub fn peek(&mut self) -> Option<&NRes<Obj>> {
+        if self.1.is_none() {
+            self.1 = self.0.next();
+        }
+        self.1.as_ref()
+    }
+}
+impl Clone for PeekableStream {
+    fn clone(&self) -> PeekableStream {
+        PeekableStream(self.0.clone_box(), self.1.clone())
+    }
+}
+impl Iterator for PeekableStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        self.1.take().or_else(|| self.0.next())
+    }
+}
+impl Display for PeekableStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.1 {
+            Some(Ok(x)) => write!(formatter, "PeekableStream({}, peeked {})", self.0, x),
+            Some(Err(e)) => write!(formatter, "PeekableStream({}, peeked ERROR: {})", self.0, e),
+            None => write!(formatter, "PeekableStream({})", self.0),
+        }
+    }
+}
+impl Stream for PeekableStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        self.0.is_infinite()
+    }
+    fn len(&self) -> Option<usize> {
+        Some(self.0.len()? + self.1.is_some() as usize)
+    }
+}
+
+// The bool is whether we just emitted a value, so a separator comes next if there are more
+// values, which we find out by peeking.
+#[derive(Debug, Clone)]
+pub struct IntersperseStream(pub PeekableStream, pub Obj, pub bool);
+impl IntersperseStream {
+    pub fn new(inner: Box<dyn Stream>, sep: Obj) -> IntersperseStream {
+        IntersperseStream(PeekableStream::new(inner), sep, false)
+    }
+}
+impl Iterator for IntersperseStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        if self.2 {
+            match self.0.peek()? {
+                Ok(_) => {
+                    self.2 = false;
+                    Some(Ok(self.1.clone()))
+                }
+                // surface errors right away rather than after a separator
+                Err(_) => self.0.next(),
+            }
+        } else {
+            self.2 = true;
+            self.0.next()
+        }
+    }
+}
+impl Display for IntersperseStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "IntersperseStream({}, {}, {})", self.0, self.1, self.2)
+    }
+}
+impl Stream for IntersperseStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        self.0.is_infinite()
+    }
+    fn len(&self) -> Option<usize> {
+        let n = self.0.len()?;
+        if self.2 {
+            Some(2 * n)
+        } else {
+            Some((2 * n).saturating_sub(1))
+        }
+    }
+}
+
+// Indices of the elements where f is truthy, counting from the BigInt, which is the index of the
+// next element we'll pull.
+pub struct IndicesWhereStream(pub NRes<(Box<dyn Stream>, Func, REnv)>, pub BigInt);
+impl Clone for IndicesWhereStream {
+    fn clone(&self) -> IndicesWhereStream {
+        match &self.0 {
+            Err(e) => IndicesWhereStream(Err(e.clone()), self.1.clone()),
+            Ok((inner, func, renv)) => IndicesWhereStream(
+                Ok((inner.clone_box(), func.clone(), renv.clone())),
+                self.1.clone(),
+            ),
+        }
+    }
+}
+// directly debug-printing env can easily recurse infinitely
+impl Debug for IndicesWhereStream {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match &self.0 {
+            Err(NErr::Break(None)) => write!(fmt, "IndicesWhereStream(stopped)"),
+            Err(e) => write!(fmt, "IndicesWhereStream(ERROR: {:?})", e),
+            Ok((inner, func, _)) => {
+                write!(fmt, "IndicesWhereStream({:?}, {:?}, {}, ...)", inner, func, self.1)
+            }
+        }
+    }
+}
+impl Iterator for IndicesWhereStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        loop {
+            let (inner, func, renv) = self.0.as_mut().ok()?;
+            let x = match inner.next() {
+                Some(Ok(x)) => x,
+                Some(Err(e)) => {
+                    self.0 = Err(e.clone());
+                    return Some(Err(e));
+                }
+                None => {
+                    self.0 = Err(NErr::Break(None));
+                    return None;
+                }
+            };
+            let i = self.1.clone();
+            self.1 += 1;
+            match func.run(renv, vec![x]) {
+                Ok(r) if r.truthy() => return Some(Ok(Obj::from(i))),
+                Ok(_) => {}
+                Err(e) => return stop_on_func_err(&mut self.0, e),
+            }
+        }
+    }
+}
+impl Display for IndicesWhereStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Ok((inner, func, _)) => {
+                write!(formatter, "IndicesWhereStream({}, {}, {}, ...)", inner, func, self.1)
+            }
+            Err(e) => write!(formatter, "IndicesWhereStream(ERROR: {})", e),
+        }
+    }
+}
+impl Stream for IndicesWhereStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    // we can't tell how many will match, but there can't be more than there are elements
+    fn is_infinite(&self) -> Option<bool> {
+        match &self.0 {
+            Ok((inner, _, _)) => inner.is_infinite().filter(|x| !x),
+            Err(_) => Some(false),
+        }
+    }
+}
+
+// Like IntersperseStream, but each separator is f(prev, next). The Option is the value we just
+// emitted, if a separator might come next.
+pub struct IntersperseWithStream(pub NRes<(PeekableStream, Func, REnv)>, pub Option<Obj>);
+impl Clone for IntersperseWithStream {
+    fn clone(&self) -> IntersperseWithStream {
+        match &self.0 {
+            Err(e) => IntersperseWithStream(Err(e.clone()), self.1.clone()),
+            Ok((inner, func, renv)) => IntersperseWithStream(
+                Ok((inner.clone(), func.clone(), renv.clone())),
+                self.1.clone(),
+            ),
+        }
+    }
+}
+// directly debug-printing env can easily recurse infinitely
+impl Debug for IntersperseWithStream {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match &self.0 {
+            Err(NErr::Break(None)) => write!(fmt, "IntersperseWithStream(stopped)"),
+            Err(e) => write!(fmt, "IntersperseWithStream(ERROR: {:?})", e),
+            Ok((inner, func, _)) => write!(
+                fmt,
+                "IntersperseWithStream({:?}, {:?}, {:?}, ...)",
+                inner, func, self.1
+            ),
+        }
+    }
+}
+impl Iterator for IntersperseWithStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let (inner, func, renv) = self.0.as_mut().ok()?;
+        match self.1.take() {
+            Some(prev) => match inner.peek()? {
+                Ok(nxt) => {
+                    let nxt = nxt.clone();
+                    match func.run(renv, vec![prev, nxt]) {
+                        Ok(sep) => Some(Ok(sep)),
+                        Err(e) => stop_on_func_err(&mut self.0, e),
+                    }
+                }
+                // surface errors right away rather than after a separator
+                Err(_) => inner.next(),
+            },
+            None => {
+                let x = inner.next()?;
+                if let Ok(x) = &x {
+                    self.1 = Some(x.clone());
+                }
+                Some(x)
+            }
+        }
+    }
+}
+impl Display for IntersperseWithStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Ok((inner, func, _)) => {
+                write!(formatter, "IntersperseWithStream({}, {}, ...)", inner, func)
+            }
+            Err(e) => write!(formatter, "IntersperseWithStream(ERROR: {})", e),
+        }
+    }
+}
+impl Stream for IntersperseWithStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    // the function can stop us by breaking, but can't make a finite stream infinite
+    fn is_infinite(&self) -> Option<bool> {
+        match &self.0 {
+            Ok((inner, _, _)) => inner.is_infinite().filter(|x| !x),
+            Err(_) => Some(false),
+        }
+    }
+    fn len(&self) -> Option<usize> {
+        match &self.0 {
+            Ok((inner, _, _)) => {
+                let n = inner.len()?;
+                if self.1.is_some() {
+                    Some(2 * n)
+                } else {
+                    Some((2 * n).saturating_sub(1))
+                }
+            }
+            Err(_) => Some(0),
+        }
+    }
+}
+
+// Yields [prev, cur] for each consecutive pair. The Option is the previous element, None before
+// we've pulled the first one.
+#[derive(Debug)]
+pub struct PairwiseStream(pub Box<dyn Stream>, pub Option<Obj>);
+impl Clone for PairwiseStream {
+    fn clone(&self) -> PairwiseStream {
+        PairwiseStream(self.0.clone_box(), self.1.clone())
+    }
+}
+impl Iterator for PairwiseStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        if self.1.is_none() {
+            match self.0.next()? {
+                Ok(x) => self.1 = Some(x),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        match self.0.next()? {
+            Ok(cur) => {
+                let prev = self.1.replace(cur.clone())?;
+                Some(Ok(Obj::list(vec![prev, cur])))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+impl Display for PairwiseStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.1 {
+            Some(prev) => write!(formatter, "PairwiseStream({}, after {})", self.0, prev),
+            None => write!(formatter, "PairwiseStream({})", self.0),
+        }
+    }
+}
+impl Stream for PairwiseStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        self.0.is_infinite()
+    }
+    fn len(&self) -> Option<usize> {
+        let n = self.0.len()?;
+        match self.1 {
+            Some(_) => Some(n),
+            None => Some(n.saturating_sub(1)),
+        }
+    }
+}
+
+// The first n elements, for any nonnegative n, even ones that don't fit in an isize
+#[derive(Debug)]
+pub struct TakeStream(pub Box<dyn Stream>, pub BigInt);
+impl TakeStream {
+    pub fn new(inner: Box<dyn Stream>, n: BigInt) -> NRes<TakeStream> {
+        if n.sign() == Sign::Minus {
+            Err(NErr::value_error(format!("can't take a negative number of elements: {}", n)))
+        } else {
+            Ok(TakeStream(inner, n))
+        }
+    }
+}
+impl Clone for TakeStream {
+    fn clone(&self) -> TakeStream {
+        TakeStream(self.0.clone_box(), self.1.clone())
+    }
+}
+impl Iterator for TakeStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        if self.1.sign() == Sign::NoSign {
+            return None;
+        }
+        self.1 -= 1;
+        self.0.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.1.to_usize();
+        match (cheap_len(&*self.0), n) {
+            (Some(a), Some(n)) => exact_size_hint(Some(a.min(n))),
+            (Some(a), None) => exact_size_hint(Some(a)),
+            (None, Some(n)) if self.0.is_infinite() == Some(true) || len_overflows(&*self.0) => {
+                exact_size_hint(Some(n))
+            }
+            (None, n) => (0, n),
+        }
+    }
+}
+impl Display for TakeStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "TakeStream({}, {})", self.0, self.1)
+    }
+}
+impl Stream for TakeStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn advance_by(&mut self, n: usize) -> NRes<()> {
+        let n = BigInt::from(n).min(self.1.clone());
+        self.1 -= &n;
+        Stream::advance_by(&mut *self.0, n.to_usize().expect("took at most a usize"))
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+    // Taking n elements should never cost more than n elements, so if size_hint doesn't know,
+    // walk a clone of the inner stream only that far.
+    fn len(&self) -> Option<usize> {
+        if let Some(n) = cheap_len(self) {
+            return Some(n);
+        }
+        let n = match self.1.to_usize() {
+            Some(n) => n,
+            None => return self.0.len(),
+        };
+        let mut s = self.0.clone_box();
+        let mut ret = 0;
+        while ret < n && s.next().is_some() {
+            ret += 1;
+        }
+        Some(ret)
+    }
+}
+
+// Discards the first n elements the first time it's asked for one, then passes the rest through
+#[derive(Debug)]
+pub struct SkipStream(pub Box<dyn Stream>, pub BigInt);
+impl SkipStream {
+    pub fn new(inner: Box<dyn Stream>, n: BigInt) -> NRes<SkipStream> {
+        if n.sign() == Sign::Minus {
+            Err(NErr::value_error(format!("can't drop a negative number of elements: {}", n)))
+        } else {
+            Ok(SkipStream(inner, n))
+        }
+    }
+}
+impl Clone for SkipStream {
+    fn clone(&self) -> SkipStream {
+        SkipStream(self.0.clone_box(), self.1.clone())
+    }
+}
+impl Iterator for SkipStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        if self.1.sign() == Sign::Plus {
+            if let Err(e) = Stream::advance_by(self, 0) {
+                return Some(Err(e));
+            }
+        }
+        self.0.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lo, hi) = self.0.size_hint();
+        let k = self.1.to_usize().unwrap_or(usize::MAX);
+        (lo.saturating_sub(k), hi.map(|hi| hi.saturating_sub(k)))
+    }
+}
+impl Display for SkipStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "SkipStream({}, {})", self.0, self.1)
+    }
+}
+impl Stream for SkipStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn advance_by(&mut self, n: usize) -> NRes<()> {
+        // the skip we still owe, then n more, only paid off as the inner stream gets through it. A
+        // stream that knows its length can jump without failing partway; anything else goes one
+        // element at a time, so an error leaves exactly the rest of the skip owed.
+        self.1 += n;
+        if cheap_len(&*self.0).is_some() {
+            while self.1.sign() == Sign::Plus {
+                let step = self.1.to_usize().unwrap_or(usize::MAX);
+                Stream::advance_by(&mut *self.0, step)?;
+                self.1 -= step;
+            }
+            return Ok(());
+        }
+        while self.1.sign() == Sign::Plus {
+            match self.0.next() {
+                Some(Err(e)) => {
+                    self.1 -= 1;
+                    return Err(e);
+                }
+                Some(Ok(_)) => self.1 -= 1,
+                None => self.1 = BigInt::from(0),
+            }
+        }
+        Ok(())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        self.0.is_infinite()
+    }
+    // Like TakeStream, only walk when size_hint doesn't know; then there's no way around walking
+    // all of it.
+    fn len(&self) -> Option<usize> {
+        if let Some(n) = cheap_len(self) {
+            return Some(n);
+        }
+        let n = self.0.len()?;
+        Some(match self.1.to_usize() {
+            Some(k) => n.saturating_sub(k),
+            None => 0,
+        })
+    }
+}
+
+// f(i), f(i + 1), ..., up to an optional exclusive bound. Random access just calls f directly.
+#[derive(Clone)]
+pub struct TabulateStream(pub NRes<(Func, REnv)>, pub BigInt, pub Option<BigInt>);
+// directly debug-printing env can easily recurse infinitely
+impl Debug for TabulateStream {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match &self.0 {
+            Err(NErr::Break(None)) => write!(fmt, "TabulateStream(stopped)"),
+            Err(e) => write!(fmt, "TabulateStream(ERROR: {:?})", e),
+            Ok((func, _)) => write!(fmt, "TabulateStream({:?}, {:?}, {:?}, ...)", func, self.1, self.2),
+        }
+    }
+}
+impl Iterator for TabulateStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let (func, renv) = self.0.as_ref().ok()?;
+        if let Some(end) = &self.2 {
+            if &self.1 >= end {
+                return None;
+            }
+        }
+        match func.run(renv, vec![Obj::from(self.1.clone())]) {
+            Ok(x) => {
+                self.1 += 1;
+                Some(Ok(x))
+            }
+            Err(e) => stop_on_func_err(&mut self.0, e),
+        }
+    }
+}
+impl Display for TabulateStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Ok((func, _)) => match &self.2 {
+                Some(end) => write!(formatter, "TabulateStream({}, {} til {})", func, self.1, end),
+                None => write!(formatter, "TabulateStream({}, from {})", func, self.1),
+            },
+            Err(NErr::Break(None)) => write!(formatter, "TabulateStream(stopped)"),
+            Err(e) => write!(formatter, "TabulateStream(ERROR: {})", e),
+        }
+    }
+}
+impl Stream for TabulateStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    // the function can stop us by breaking
+    fn is_infinite(&self) -> Option<bool> {
+        match (&self.0, &self.2) {
+            (Ok(_), None) => None,
+            _ => Some(false),
+        }
+    }
+    fn len(&self) -> Option<usize> {
+        if self.0.is_err() {
+            return Some(0);
+        }
+        let end = self.2.as_ref()?;
+        (end - &self.1).max(BigInt::from(0)).to_usize()
+    }
+    fn pythonic_index_isize(&self, i: isize) -> NRes<Obj> {
+        let (func, renv) = match &self.0 {
+            Ok(x) => x,
+            Err(_) => return Err(NErr::index_error(format!("Index out of bounds: {}", i))),
+        };
+        let index = match (i >= 0, &self.2) {
+            (true, _) => &self.1 + i,
+            (false, Some(end)) => end + i,
+            (false, None) => {
+                return Err(NErr::index_error(format!(
+                    "Can't index infinite tabulate from the end: {}",
+                    i
+                )))
+            }
+        };
+        if index < self.1 || self.2.as_ref().is_some_and(|end| &index >= end) {
+            return Err(NErr::index_error(format!("Index out of bounds: {}", i)));
+        }
+        func.run(renv, vec![Obj::from(index)])
+    }
+}
+
+// func(state) gives [output, next state] to continue, or null (or breaks) to stop. Like Iterate,
+// but what we yield doesn't have to be what we carry.
+#[derive(Clone)]
+pub struct UnfoldStream(pub NRes<(Obj, Func, REnv)>);
+// directly debug-printing env can easily recurse infinitely
+impl Debug for UnfoldStream {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match &self.0 {
+            Err(NErr::Break(None)) => write!(fmt, "UnfoldStream(stopped)"),
+            Err(e) => write!(fmt, "UnfoldStream(ERROR: {:?})", e),
+            Ok((state, func, _)) => write!(fmt, "UnfoldStream({:?}, {:?}, ...)", state, func),
+        }
+    }
+}
+impl Iterator for UnfoldStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let (state, func, renv) = self.0.as_mut().ok()?;
+        match func.run(renv, vec![state.clone()]) {
+            Ok(Obj::Null) => {
+                self.0 = Err(NErr::Break(None));
+                None
+            }
+            Ok(Obj::Seq(Seq::List(v))) if v.len() == 2 => {
+                *state = v[1].clone();
+                Some(Ok(v[0].clone()))
+            }
+            Ok(x) => {
+                let e = NErr::type_error(format!(
+                    "unfold: function must return [output, next state] or null, got {}",
+                    FmtObj::debug(&x)
+                ));
+                stop_on_func_err(&mut self.0, e)
+            }
+            Err(e) => stop_on_func_err(&mut self.0, e),
+        }
+    }
+}
+impl Display for UnfoldStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Ok((state, func, _)) => write!(formatter, "UnfoldStream({}, {}, ...)", state, func),
+            Err(NErr::Break(None)) => write!(formatter, "UnfoldStream(stopped)"),
+            Err(e) => write!(formatter, "UnfoldStream(ERROR: {})", e),
+        }
+    }
+}
+impl Stream for UnfoldStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        match &self.0 {
+            Ok(_) => None,
+            Err(_) => Some(false),
+        }
+    }
+    fn len(&self) -> Option<usize> {
+        match &self.0 {
+            Ok(_) => None,
+            Err(_) => Some(0),
+        }
+    }
+}
+
+// Incremental sieve: maps each upcoming composite to the primes that will cross it off, so memory
+// grows with the number of primes seen rather than how far we've gone.
+#[derive(Debug, Clone)]
+pub struct PrimesStream(pub u64, pub HashMap<u64, Vec<u64>>);
+impl PrimesStream {
+    pub fn new() -> PrimesStream {
+        PrimesStream(2, HashMap::new())
+    }
+}
+impl Iterator for PrimesStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        loop {
+            let n = self.0;
+            self.0 += 1;
+            match self.1.remove(&n) {
+                Some(ps) => {
+                    // a multiple past u64::MAX is never reached, so it needn't be tracked
+                    for p in ps {
+                        if let Some(m) = n.checked_add(p) {
+                            self.1.entry(m).or_default().push(p);
+                        }
+                    }
+                }
+                None => {
+                    if let Some(m) = n.checked_mul(n) {
+                        self.1.insert(m, vec![n]);
+                    }
+                    return Some(Ok(Obj::from(BigInt::from(n))));
+                }
+            }
+        }
+    }
+}
+impl Display for PrimesStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "primes(from {})", self.0)
+    }
+}
+impl Stream for PrimesStream {
     fn clone_box(&self) -> Box<dyn Stream> {
         Box::new(self.clone())
     }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(true)
+    }
     fn len(&self) -> Option<usize> {
         None
     }
+    fn force(&self) -> NRes<Vec<Obj>> {
+        Err(NErr::value_error(
+            "Cannot force primes because it's infinite".to_string(),
+        ))
+    }
 }
 
-// maybe even more illegal? not sure
-// again we'll treat NErr::Break as graceful termination
-pub struct MappedStream(pub NRes<(Box<dyn Stream>, Func, REnv)>);
-impl Clone for MappedStream {
-    fn clone(&self) -> MappedStream {
-        match &self.0 {
-            Err(e) => MappedStream(Err(e.clone())),
-            Ok((inner, func, renv)) => {
-                MappedStream(Ok((inner.clone_box(), func.clone(), renv.clone())))
-            }
+// The 3n+1 trajectory, ending after it reaches 1. Nobody knows that it always does, so we don't
+// claim to be finite.
+#[derive(Debug, Clone)]
+pub struct CollatzStream(pub Option<BigInt>);
+impl CollatzStream {
+    pub fn new(n: BigInt) -> NRes<CollatzStream> {
+        if n.sign() == Sign::Plus {
+            Ok(CollatzStream(Some(n)))
+        } else {
+            Err(NErr::value_error(format!("collatz needs a positive start, got {}", n)))
         }
     }
 }
-// directly debug-printing env can easily recurse infinitely
-impl Debug for MappedStream {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+impl Iterator for CollatzStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let n = self.0.take()?;
+        if n != BigInt::from(1) {
+            self.0 = Some(if n.bit(0) { &n * 3 + 1 } else { &n >> 1 });
+        }
+        Some(Ok(Obj::from(n)))
+    }
+}
+impl Display for CollatzStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         match &self.0 {
-            Err(NErr::Break(None)) => write!(fmt, "MappedStream(stopped)"),
-            Err(e) => write!(fmt, "MappedStream(ERROR: {:?})", e),
-            Ok((inner, func, _)) => write!(fmt, "MappedStream({:?}, {:?}, ...)", inner, func),
+            Some(n) => write!(formatter, "collatz({})", n),
+            None => write!(formatter, "collatz(done)"),
         }
     }
 }
-impl Iterator for MappedStream {
+impl Stream for CollatzStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        match self.0 {
+            Some(_) => None,
+            None => Some(false),
+        }
+    }
+}
+
+// Prime factorization as [prime, exponent] pairs in increasing order. Trial division only goes as
+// far as the next factor, and once the divisor passes the square root of what's left, what's left
+// is prime. Fields are the unfactored part and the next divisor to try.
+#[derive(Debug, Clone)]
+pub struct FactorsStream(pub BigInt, pub BigInt);
+impl FactorsStream {
+    pub fn new(n: BigInt) -> NRes<FactorsStream> {
+        if n.sign() == Sign::Plus {
+            Ok(FactorsStream(n, BigInt::from(2)))
+        } else {
+            Err(NErr::value_error(format!("can only factorize positive integers, got {}", n)))
+        }
+    }
+}
+impl Iterator for FactorsStream {
     type Item = NRes<Obj>;
     fn next(&mut self) -> Option<NRes<Obj>> {
-        let (inner, func, renv) = self.0.as_mut().ok()?;
-        match inner.next() {
-            Some(Err(e)) => {
-                self.0 = Err(e.clone());
-                Some(Err(e))
+        let one = BigInt::from(1);
+        loop {
+            if self.0 <= one {
+                return None;
             }
-            Some(Ok(cur)) => match func.run(&renv, vec![cur]) {
-                Ok(nxt) => Some(Ok(nxt)),
-                Err(e) => {
-                    self.0 = Err(e.clone());
-                    Some(Err(e))
-                }
-            },
-            None => {
-                self.0 = Err(NErr::Break(None));
-                None
+            if &self.1 * &self.1 > self.0 {
+                let p = std::mem::replace(&mut self.0, one);
+                return Some(Ok(Obj::list(vec![Obj::from(p), Obj::from(1usize)])));
+            }
+            // 2, 3, then only numbers that are 1 or 5 mod 6
+            let f = self.1.clone();
+            self.1 = if f <= BigInt::from(3) {
+                &f * 2 - 1
+            } else if (&f % 6u8) == BigInt::from(5) {
+                &f + 2
+            } else {
+                &f + 4
+            };
+            let mut e = 0usize;
+            while (&self.0 % &f).sign() == Sign::NoSign {
+                self.0 /= &f;
+                e += 1;
+            }
+            if e > 0 {
+                return Some(Ok(Obj::list(vec![Obj::from(f), Obj::from(e)])));
             }
         }
     }
 }
-impl Display for MappedStream {
+impl Display for FactorsStream {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        match &self.0 {
-            Ok((inner, func, _)) => write!(formatter, "MappedStream({}, {}, ...)", inner, func),
-            Err(e) => write!(formatter, "MappedStream(ERROR: {})", e),
+        if self.0 == BigInt::from(1) {
+            write!(formatter, "factorize(done)")
+        } else {
+            write!(formatter, "factorize({})", self.0)
         }
     }
 }
-impl Stream for MappedStream {
+impl Stream for FactorsStream {
     fn clone_box(&self) -> Box<dyn Stream> {
         Box::new(self.clone())
     }
-    /*
-    fn len(&self) -> Option<usize> {
-        match &self.0 {
-            Ok((inner, _, _)) => inner.len(),
-            Err(_) => Some(0),
-        }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
     }
-    */
 }
-pub struct StridedStream(pub NRes<(Box<dyn Stream>, usize, usize)>);
-impl Clone for StridedStream {
-    fn clone(&self) -> StridedStream {
-        match &self.0 {
-            Err(e) => StridedStream(Err(e.clone())),
-            Ok((inner, stride, pos)) => {
-                StridedStream(Ok((inner.clone_box(), stride.clone(), pos.clone())))
-            }
+
+// Digits of a non-negative integer in some base, least significant first unless we're given the
+// place value of the leading digit, in which case we peel off most significant first. Fields are
+// what's left of the number, the base, that place value, and how many digits are left; zero has
+// the one digit 0.
+#[derive(Debug, Clone)]
+pub struct DigitsStream(pub BigInt, pub BigInt, pub Option<BigInt>, pub usize);
+impl DigitsStream {
+    pub fn new(n: BigInt, base: BigInt, msb_first: bool) -> NRes<DigitsStream> {
+        if n.sign() == Sign::Minus {
+            return Err(NErr::value_error(format!("can't take digits of negative {}", n)));
         }
-    }
-}
-// directly debug-printing env can easily recurse infinitely
-impl Debug for StridedStream {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
-        match &self.0 {
-            Err(NErr::Break(None)) => write!(fmt, "StridedStream(stopped)"),
-            Err(e) => write!(fmt, "StridedStream(ERROR: {:?})", e),
-            Ok((inner, stride, size)) => write!(fmt, "StridedStream({:?}, {:?}, {:?},...)", inner, stride, size),
+        if base < BigInt::from(2) {
+            return Err(NErr::value_error(format!("digit base must be at least 2, got {}", base)));
         }
+        let mut count = 1;
+        let mut place = BigInt::from(1);
+        while &place * &base <= n {
+            place *= &base;
+            count += 1;
+        }
+        Ok(DigitsStream(n, base, if msb_first { Some(place) } else { None }, count))
     }
 }
-impl Iterator for StridedStream {
+impl Iterator for DigitsStream {
     type Item = NRes<Obj>;
     fn next(&mut self) -> Option<NRes<Obj>> {
-        let (inner, stride, size) = self.0.as_mut().ok()?;
-        loop {
-            match inner.next() {
-                Some(Err(e)) => {
-                    self.0 = Err(e.clone());
-                    return Some(Err(e))
-                }
-                Some(Ok(cur)) => {
-                    if *size % *stride == 0 {
-                        *size += 1;
-                        return Some(Ok(cur));
-                    }
-                    *size += 1;
-                },
-                None => {
-                    self.0 = Err(NErr::Break(None));
-                    return None;
-                }
-            }
+        if self.3 == 0 {
+            return None;
         }
+        self.3 -= 1;
+        let d = match &mut self.2 {
+            None => {
+                let d = &self.0 % &self.1;
+                self.0 /= &self.1;
+                d
+            }
+            Some(place) => {
+                let d = &self.0 / &*place;
+                self.0 %= &*place;
+                *place /= &self.1;
+                d
+            }
+        };
+        Some(Ok(Obj::from(d)))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        exact_size_hint(Some(self.3))
     }
 }
-impl Display for StridedStream {
+impl Display for DigitsStream {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        match &self.0 {
-            Ok((inner, stride, pos)) => write!(formatter, "StridedStream({}, {}, {}, ...)", inner, stride, pos),
-            Err(e) => write!(formatter, "StridedStream(ERROR: {})", e),
-        }
+        write!(formatter, "digits({}, {})", self.0, self.1)
     }
 }
-impl Stream for StridedStream {
+impl Stream for DigitsStream {
     fn clone_box(&self) -> Box<dyn Stream> {
         Box::new(self.clone())
     }
-    /*
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
     fn len(&self) -> Option<usize> {
-        match &self.0 {
-            Ok((inner, _, _)) => inner.len(),
-            Err(_) => Some(0),
-        }
+        Some(self.3)
     }
-    */
 }
 
-// TODO: remove ScannedStream and MappedStream with dyn Iterator i.e. type erased iterators
-pub struct ScannedStream(pub NRes<(Box<dyn Stream>, Obj, Func, REnv)>, pub Option<Obj>);
-impl Clone for ScannedStream {
-    fn clone(&self) -> ScannedStream {
-        match &self.0 {
-            Err(e) => ScannedStream(Err(e.clone()), None),
-            Ok((inner, init, func, renv)) => {
-                ScannedStream(Ok((inner.clone_box(), init.clone(), func.clone(), renv.clone())), self.1.clone())
-            }
+// Row n of Pascal's triangle. Each coefficient comes from the previous one, since
+// C(n, k + 1) = C(n, k) * (n - k) / (k + 1) exactly. Fields are n, the next k, and C(n, k).
+#[derive(Debug, Clone)]
+pub struct BinomialRowStream(pub usize, pub usize, pub BigInt);
+impl BinomialRowStream {
+    pub fn new(n: usize) -> BinomialRowStream {
+        BinomialRowStream(n, 0, BigInt::from(1))
+    }
+}
+impl Iterator for BinomialRowStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let BinomialRowStream(n, k, c) = self;
+        if *k > *n {
+            return None;
         }
+        let ret = c.clone();
+        *c = &*c * (*n - *k) / (*k + 1);
+        *k += 1;
+        Some(Ok(Obj::from(ret)))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        exact_size_hint(Some(self.0 + 1 - self.1))
     }
 }
-// directly debug-printing env can easily recurse infinitely
-impl Debug for ScannedStream {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
-        match &self.0 {
-            Err(NErr::Break(None)) => write!(fmt, "ScannedStream(stopped)"),
-            Err(e) => write!(fmt, "ScannedStream(ERROR: {:?})", e),
-            Ok((inner, init, func, _)) => write!(fmt, "ScannedStream({:?}, {:?}, {:?}, ...)", inner, init, func),
+impl Display for BinomialRowStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "binomial_row({}) from {}", self.0, self.1)
+    }
+}
+impl Stream for BinomialRowStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn advance_by(&mut self, n: usize) -> NRes<()> {
+        let BinomialRowStream(row, k, c) = self;
+        *k = k.saturating_add(n).min(*row + 1);
+        if *k <= *row {
+            // C(row, k) from scratch, choosing the smaller side
+            let j = (*k).min(*row - *k);
+            *c = BigInt::from(1);
+            for m in 0..j {
+                *c = &*c * (*row - m) / (m + 1);
+            }
         }
+        Ok(())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(false)
+    }
+    fn len(&self) -> Option<usize> {
+        Some(self.0 + 1 - self.1)
+    }
+    fn pythonic_index_isize(&self, i: isize) -> NRes<Obj> {
+        let len = self.0 + 1 - self.1;
+        let j = if i >= 0 { i as usize } else { len.wrapping_sub(i.unsigned_abs()) };
+        if j >= len {
+            return Err(NErr::index_error(format!("Index out of bounds: {}", i)));
+        }
+        // straight from the top, choosing whichever of k and n - k is smaller
+        let (n, k) = (self.0, self.1 + j);
+        let k = k.min(n - k);
+        let mut ret = BigInt::from(1);
+        for m in 0..k {
+            ret = ret * (n - m) / (m + 1);
+        }
+        Ok(Obj::from(ret))
     }
 }
-impl Iterator for ScannedStream {
-    type Item = NRes<Obj>;
-    fn next(&mut self) -> Option<NRes<Obj>> {
-        let (inner, init, func, renv) = self.0.as_mut().ok()?;
-        
-        if let Some(acc) = self.1.take() {
-            match inner.next() {
-                Some(Err(e)) => {
-                    self.0 = Err(e.clone());
-                    Some(Err(e))
+
+type StreamCache = (NRes<Option<Box<dyn Stream>>>, Vec<Obj>);
+
+// Clones share the inner stream and everything it's produced so far, so each element is only
+// computed once no matter how many times the stream gets iterated. The inner stream is
+// Err(NErr::Break(None)) once exhausted, and Ok(None) while it's been taken out to compute the
+// next element, since that can run user code that iterates this same cache. Our own position is
+// None once we've stopped.
+#[derive(Debug, Clone)]
+pub struct CachedStream(pub Rc<RefCell<StreamCache>>, pub Option<usize>);
+impl CachedStream {
+    pub fn new(inner: Box<dyn Stream>) -> CachedStream {
+        CachedStream(Rc::new(RefCell::new((Ok(Some(inner)), Vec::new()))), Some(0))
+    }
+
+    // pull from the inner stream until index i is cached, without holding the borrow while the
+    // inner stream runs
+    fn fill(&self, i: usize) -> Option<NRes<Obj>> {
+        loop {
+            let mut inner = {
+                let mut cache = match try_borrow_mut_nres(&self.0, "CachedStream", "fill") {
+                    Ok(c) => c,
+                    Err(e) => return Some(Err(e)),
+                };
+                if let Some(x) = cache.1.get(i) {
+                    return Some(Ok(x.clone()));
                 }
-                Some(Ok(cur)) => match func.run(&renv, vec![acc, cur]) {
-                    Ok(nxt) => {
-                        self.1 = Some(nxt.clone());
-                        Some(Ok(nxt))
+                match &mut cache.0 {
+                    Ok(s) => match s.take() {
+                        Some(s) => s,
+                        None => {
+                            return Some(Err(NErr::value_error(format!(
+                                "cached stream needs its element {} to compute itself",
+                                i
+                            ))))
+                        }
                     },
-                    Err(e) => {
-                        self.0 = Err(e.clone());
-                        Some(Err(e))
-                    }
-                },
+                    Err(NErr::Break(None)) => return None,
+                    Err(e) => return Some(Err(e.clone())),
+                }
+            };
+            let next = inner.next();
+            let mut cache = match try_borrow_mut_nres(&self.0, "CachedStream", "fill") {
+                Ok(c) => c,
+                Err(e) => return Some(Err(e)),
+            };
+            match next {
+                Some(Ok(x)) => {
+                    cache.0 = Ok(Some(inner));
+                    cache.1.push(x);
+                }
+                Some(Err(e)) => {
+                    cache.0 = Err(e.clone());
+                    return Some(Err(e));
+                }
                 None => {
-                    self.0 = Err(NErr::Break(None));
-                    None
+                    cache.0 = Err(NErr::Break(None));
+                    return None;
                 }
             }
-        } else {
-            self.1 = Some(init.clone());
-            Some(Ok(init.clone()))
         }
     }
 }
-impl Display for ScannedStream {
+impl Iterator for CachedStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        let pos = self.1?;
+        let ret = self.fill(pos);
+        self.1 = match ret {
+            Some(Ok(_)) => Some(pos + 1),
+            _ => None,
+        };
+        ret
+    }
+}
+impl Display for CachedStream {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        match &self.0 {
-            Ok((inner, init, func, _)) => write!(formatter, "ScannedStream({}, {}, {}, ...)", inner, init, func),
-            Err(e) => write!(formatter, "ScannedStream(ERROR: {})", e),
+        match self.1 {
+            Some(pos) => match self.0.try_borrow() {
+                Ok(cache) => write!(
+                    formatter,
+                    "CachedStream(at {}, {} cached)",
+                    pos,
+                    cache.1.len()
+                ),
+                Err(_) => write!(formatter, "CachedStream(at {})", pos),
+            },
+            None => write!(formatter, "CachedStream(stopped)"),
         }
     }
 }
-impl Stream for ScannedStream {
+impl Stream for CachedStream {
     fn clone_box(&self) -> Box<dyn Stream> {
         Box::new(self.clone())
     }
-    /*
+    fn is_infinite(&self) -> Option<bool> {
+        if self.1.is_none() {
+            return Some(false);
+        }
+        match &self.0.try_borrow().ok()?.0 {
+            Ok(inner) => inner.as_ref()?.is_infinite(),
+            Err(_) => Some(false),
+        }
+    }
     fn len(&self) -> Option<usize> {
-        match &self.0 {
-            Ok((inner, _, _)) => inner.len(),
-            Err(_) => Some(0),
+        let pos = match self.1 {
+            Some(pos) => pos,
+            None => return Some(0),
+        };
+        let cache = self.0.try_borrow().ok()?;
+        let rest = match &cache.0 {
+            Ok(s) => s.as_ref()?.len()?,
+            Err(_) => 0,
+        };
+        Some((cache.1.len() + rest).saturating_sub(pos))
+    }
+    // just what's already been computed
+    fn cheap_prefix(&self, n: usize) -> Option<(Vec<Obj>, bool)> {
+        let pos = match self.1 {
+            Some(pos) => pos,
+            None => return Some((Vec::new(), true)),
+        };
+        let cache = self.0.try_borrow().ok()?;
+        let cached = cache.1.get(pos..).unwrap_or(&[]);
+        let done = matches!(cache.0, Err(NErr::Break(None)));
+        Some((cached.iter().take(n).cloned().collect(), done && cached.len() <= n))
+    }
+    fn pythonic_index_isize(&self, i: isize) -> NRes<Obj> {
+        if i < 0 {
+            return match self.force() {
+                Ok(v) => Ok(v[pythonic_index_isize(&v, i)?].clone()),
+                Err(e) => Err(e),
+            };
+        }
+        match self.1 {
+            Some(pos) => match self.fill(pos + i as usize) {
+                Some(res) => res,
+                None => Err(NErr::index_error(format!("Index out of bounds: {}", i))),
+            },
+            None => Err(NErr::index_error(format!("Index out of bounds: {}", i))),
         }
     }
-    */
 }
 
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[derive(Debug, Clone)]
 struct TotalOrderWrapper(Obj);
 
+impl PartialEq for TotalOrderWrapper {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
 impl Eq for TotalOrderWrapper {}
 
+impl PartialOrd for TotalOrderWrapper {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Ord for TotalOrderWrapper {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        match self.partial_cmp(other) {
-            Some(o) => o,
-            None => std::cmp::Ordering::Equal
-        }
-        // match (self.0, other.0) {
-        //     (Obj::Num(a), Obj::Num(b)) => a.cmp(b),
-        //     (Obj::Seq(a), Obj::Seq(b)) => a.cmp(b),
-        //     _ => std::cmp::Ordering::Equal,
-        // }
+    fn cmp(&self, other: &Self) -> Ordering {
+        total_cmp(&self.0, &other.0)
     }
 }
 
@@ -744,13 +4920,16 @@ impl Iterator for HeapStream {
     type Item = NRes<Obj>;
     fn next(&mut self) -> Option<NRes<Obj>> {
         let (heap, func, renv) = self.0.as_mut().ok()?;
-        // This does not match LazyStream. Should error out Stream state for future next calls???
-        let ret = func.run(renv, vec![heap.pop()?.0]).ok()?;
+        let ret = match func.run(renv, vec![heap.pop()?.0]) {
+            Ok(ret) => ret,
+            Err(e) => return stop_on_func_err(&mut self.0, e),
+        };
 
         if let Obj::Seq(Seq::List(v)) = ret.clone() {
              heap.extend(v.iter().map(|o| TotalOrderWrapper(o.clone())))
         } else {
-            return Some(Err(NErr::type_error(format!("HeapStream func must return lists. Got {:?}", ret))));
+            let e = NErr::type_error(format!("HeapStream func must return lists. Got {:?}", ret));
+            return stop_on_func_err(&mut self.0, e);
         }
         Some(Ok(ret))
     }
@@ -764,4 +4943,85 @@ impl Stream for HeapStream {
     fn clone_box(&self) -> Box<dyn Stream> {
         Box::new(self.clone())
     }
-}
\ No newline at end of file
+}
+// splitmix64: tiny, fast, and fine statistically for scripting. Not for anything cryptographic.
+#[derive(Debug, Clone)]
+pub struct SplitMix64(pub u64);
+impl SplitMix64 {
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // uniform in [0, 1), from the top 53 bits
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // uniform in [0, n), rejecting the low values that would bias the modulus
+    pub fn below(&mut self, n: u64) -> u64 {
+        let threshold = n.wrapping_neg() % n;
+        loop {
+            let x = self.next_u64();
+            if x >= threshold {
+                return x % n;
+            }
+        }
+    }
+}
+
+// Yields floats in [0, 1), or ints in [lo, hi) if given a range. Clones copy the generator state,
+// so a clone replays exactly what the original would yield from that point on; they never share.
+#[derive(Debug, Clone)]
+pub struct RandomStream(pub SplitMix64, pub Option<(BigInt, u64)>);
+impl RandomStream {
+    pub fn new(seed: u64, range: Option<(BigInt, BigInt)>) -> NRes<RandomStream> {
+        let range = match range {
+            None => None,
+            Some((lo, hi)) => match (&hi - &lo).to_u64() {
+                Some(span) if span > 0 => Some((lo, span)),
+                _ => {
+                    return Err(NErr::value_error(format!(
+                        "random range must be nonempty and fit in 64 bits: {} to {}",
+                        lo, hi
+                    )))
+                }
+            },
+        };
+        Ok(RandomStream(SplitMix64(seed), range))
+    }
+}
+impl Iterator for RandomStream {
+    type Item = NRes<Obj>;
+    fn next(&mut self) -> Option<NRes<Obj>> {
+        Some(Ok(match &self.1 {
+            None => Obj::from(self.0.next_f64()),
+            Some((lo, span)) => Obj::from(lo + self.0.below(*span)),
+        }))
+    }
+}
+impl Display for RandomStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.1 {
+            None => write!(formatter, "random_stream(state {})", self.0 .0),
+            Some((lo, span)) => write!(
+                formatter,
+                "random_stream(state {}, {}, {})",
+                self.0 .0,
+                lo,
+                lo + span
+            ),
+        }
+    }
+}
+impl Stream for RandomStream {
+    fn clone_box(&self) -> Box<dyn Stream> {
+        Box::new(self.clone())
+    }
+    fn is_infinite(&self) -> Option<bool> {
+        Some(true)
+    }
+}
@@ -513,3 +513,2791 @@ fn switch() {
     assert_eq!(simple_eval("struct Foo(bar, baz); switch (Foo(3, 4)) case a, b -> 0 case Foo(0, _) -> 0 case Foo(a, b) -> a + b"), i(7));
     assert_eq!(simple_eval("struct Foo(bar, baz); switch (Foo(3, 4)) case a, b -> 0 case Foo(3, b) -> b case Foo(a, b) -> a + b"), i(4));
 }
+
+#[test]
+fn cycle_empty() {
+    assert_eq!(
+        simple_eval("cycle([1, 2]) take 5 join ''"),
+        Obj::from("12121")
+    );
+    assert_eq!(
+        simple_eval("try cycle([]) catch e -> e"),
+        Obj::from("cycle(list): value error: cannot cycle an empty sequence")
+    );
+}
+
+#[test]
+fn strides() {
+    assert_eq!(
+        simple_eval("(0 til 10) stride 3 join ','"),
+        Obj::from("0,3,6,9")
+    );
+    assert_eq!(
+        simple_eval("stride(0 til 10, 3, 1) join ','"),
+        Obj::from("1,4,7")
+    );
+    assert_eq!(
+        simple_eval("stride([0, 1, 2, 3, 4], 2, 1) join ','"),
+        Obj::from("1,3")
+    );
+    assert_eq!(
+        simple_eval("try (0 til 10) stride 0 catch e -> e"),
+        Obj::from("stride: value error: stride must be positive")
+    );
+    // the rest of a partially consumed strided stream keeps its phase
+    assert_eq!(
+        simple_eval("_, rest := uncons((0 til 10) stride 3); rest join ','"),
+        Obj::from("3,6,9")
+    );
+    assert_eq!(
+        simple_eval(
+            "_, rest := uncons(stride(0 til 10, 3, 2)); [rest join ',', rest join ','] join ';'"
+        ),
+        Obj::from("5,8;5,8")
+    );
+}
+
+#[test]
+fn repeat_slices() {
+    for (slice, expected) in [
+        ("[2:5]", "777"),
+        ("[5:2]", ""),
+        ("[-3:-1]", "77"),
+        ("[-1:-3]", ""),
+        ("[:3]", "777"),
+        ("[-3:2]", ""),
+        ("[2:-1] take 4", "7777"),
+        ("[2:] take 4", "7777"),
+        ("[:] take 2", "77"),
+    ] {
+        assert_eq!(
+            simple_eval(&format!("repeat(7){} join ''", slice)),
+            Obj::from(expected),
+            "{}",
+            slice
+        );
+    }
+    assert_eq!(
+        simple_eval("try repeat(7)[-3:] catch e -> e"),
+        Obj::from("value error: cannot slice repeat from -3 to the end, it's infinite")
+    );
+}
+
+#[test]
+fn zero_step_ranges() {
+    assert_eq!(
+        simple_eval("try 0 til 5 by 0 catch e -> e"),
+        Obj::from("til: value error: range step cannot be zero")
+    );
+    assert_eq!(
+        simple_eval("try 0 to 5 by 0 catch e -> e"),
+        Obj::from("to: value error: range step cannot be zero")
+    );
+    assert_eq!(simple_eval("len(5 til 0 by (-2))"), i(3));
+    assert_eq!(simple_eval("len(5 til 1 by (-2))"), i(2));
+    assert_eq!(simple_eval("len(0 til 5 by 2)"), i(3));
+}
+
+#[test]
+fn permutations_with_duplicates() {
+    assert_eq!(
+        simple_eval("permutations([1, 1, 2]) map (join '') join ','"),
+        Obj::from("112,121,112,121,211,211")
+    );
+    for src in ["[1, 1, 2]", "[2, 1, 1, 3]", "[3, 3, 3]", "[1, 2, 2, 1]"] {
+        assert_eq!(
+            simple_eval(&format!(
+                "p := permutations({}); _, r := uncons(uncons(p)[1]); [len(p) == len(list(p)), len(r) == len(list(r))]",
+                src
+            )),
+            simple_eval("[true, true]"),
+            "{}",
+            src
+        );
+    }
+}
+
+#[test]
+fn big_and_small_ranges() {
+    assert_eq!(simple_eval("sum(0 til 1000)"), i(499500));
+    assert_eq!(
+        simple_eval("10 til 0 by (-3) join ','"),
+        Obj::from("10,7,4,1")
+    );
+    assert_eq!(simple_eval("len(10 to 0 by (-3))"), i(4));
+    // bounds past i64 fall back to bigints
+    assert_eq!(
+        simple_eval("9223372036854775806 til 9223372036854775809 join ','"),
+        Obj::from("9223372036854775806,9223372036854775807,9223372036854775808")
+    );
+    assert_eq!(
+        simple_eval("len(9223372036854775800 til 9223372036854775807 by 5)"),
+        i(2)
+    );
+}
+
+#[test]
+fn cached_streams() {
+    assert_eq!(
+        simple_eval("c := 0; s := cache((0 til 5) lazy_map \\x -> (c += 1; x * x)); [s join ',', s join ',', c]"),
+        simple_eval("['0,1,4,9,16', '0,1,4,9,16', 5]")
+    );
+    assert_eq!(
+        simple_eval("c := 0; s := cache(iota(0) lazy_map \\x -> (c += 1; x * x)); [s[3], s take 5 join ',', s[2], c]"),
+        simple_eval("[9, '0,1,4,9,16', 4, 5]")
+    );
+    assert_eq!(simple_eval("len(cache(0 til 5))"), i(5));
+    // the mapper can read what's already cached while the next element is being computed
+    assert_eq!(
+        simple_eval("s := null; s = cache(iota(0) lazy_map \\x -> len(s take x)); s take 3"),
+        simple_eval("[0, 1, 2]")
+    );
+    assert_eq!(
+        simple_eval("s := null; s = cache(iota(0) lazy_map \\x -> len(s take (x + 1))); try s take 3 catch e -> e"),
+        Obj::from("take: take: value error: cached stream needs its element 0 to compute itself")
+    );
+}
+
+#[test]
+fn chains() {
+    assert_eq!(
+        simple_eval("chain(0 til 3, repeat(9)) take 5"),
+        simple_eval("[0, 1, 2, 9, 9]")
+    );
+    assert_eq!(
+        simple_eval("chain([1, 2], 'ab', 3 til 5) join ','"),
+        Obj::from("1,2,a,b,3,4")
+    );
+    assert_eq!(simple_eval("len(chain(0 til 3, [], [5, 6]))"), i(5));
+    assert_eq!(simple_eval("chain() join ','"), Obj::from(""));
+}
+
+#[test]
+fn interleaves() {
+    assert_eq!(
+        simple_eval("interleave([1, 2, 3], [10, 20]) join ','"),
+        Obj::from("1,10,2,20,3")
+    );
+    assert_eq!(
+        simple_eval("interleave([1, 2], repeat(0), 'a') take 8 join ','"),
+        Obj::from("1,0,a,2,0,0,0,0")
+    );
+    assert_eq!(simple_eval("len(interleave([1, 2, 3], [], 0 til 4))"), i(7));
+    assert_eq!(simple_eval("interleave() join ','"), Obj::from(""));
+}
+
+#[test]
+fn dedups() {
+    assert_eq!(
+        simple_eval("dedup([1, 1, 2, 2, 2, 3, 1])"),
+        simple_eval("[1, 2, 3, 1]")
+    );
+    assert_eq!(simple_eval("dedup('aabccca') join ''"), Obj::from("abca"));
+    assert_eq!(
+        simple_eval("dedup(iota(0) lazy_map \\x -> x // 3) take 4"),
+        simple_eval("[0, 1, 2, 3]")
+    );
+    assert_eq!(simple_eval("dedup(repeat(1)) take 1"), simple_eval("[1]"));
+}
+
+#[test]
+fn unique_streams() {
+    assert_eq!(
+        simple_eval("unique([3, 1, 3, 2, 1])"),
+        simple_eval("[3, 1, 2]")
+    );
+    assert_eq!(
+        simple_eval("unique(cycle([3, 1, 3, 2, 1])) take 3"),
+        simple_eval("[3, 1, 2]")
+    );
+    assert_eq!(
+        simple_eval("unique(iota(0) lazy_map \\x -> x % 4) take 4 join ','"),
+        Obj::from("0,1,2,3")
+    );
+    assert_eq!(
+        simple_eval("try unique(iota(0) lazy_map \\x -> \\y -> y) take 2 catch e -> e"),
+        Obj::from("take: type error: Using a function as a dictionary key isn't supported")
+    );
+}
+
+#[test]
+fn intersperses() {
+    assert_eq!(
+        simple_eval("intersperse(0, [1, 2, 3])"),
+        simple_eval("[1, 0, 2, 0, 3]")
+    );
+    assert_eq!(simple_eval("intersperse(0, [])"), simple_eval("[]"));
+    assert_eq!(simple_eval("intersperse(0, [1])"), simple_eval("[1]"));
+    assert_eq!(
+        simple_eval("intersperse(',', 1 to 3) join ''"),
+        Obj::from("1,2,3")
+    );
+    assert_eq!(
+        simple_eval("intersperse(0, iota(1)) take 4"),
+        simple_eval("[1, 0, 2, 0]")
+    );
+    assert_eq!(
+        simple_eval(
+            "s := intersperse(0, 1 to 3); _, r := uncons(s); [len(s), len(r), len(uncons(r)[1])]"
+        ),
+        simple_eval("[5, 4, 3]")
+    );
+}
+
+#[test]
+fn pairwise_pairs() {
+    assert_eq!(
+        simple_eval("pairwise([1, 2, 3, 4])"),
+        simple_eval("[[1, 2], [2, 3], [3, 4]]")
+    );
+    assert_eq!(simple_eval("pairwise([1])"), simple_eval("[]"));
+    assert_eq!(simple_eval("[1, 4, 9] pairwise -"), simple_eval("[-3, -5]"));
+    assert_eq!(
+        simple_eval("pairwise(iota(1) lazy_map \\x -> x * x) lazy_map (\\[a, b] -> b - a) take 4"),
+        simple_eval("[3, 5, 7, 9]")
+    );
+    assert_eq!(simple_eval("len(pairwise(0 til 5))"), i(4));
+    assert_eq!(simple_eval("len(pairwise(0 til 0))"), i(0));
+}
+
+#[test]
+fn scan_rights() {
+    assert_eq!(
+        simple_eval("scanr(\\a, x -> a + x, 0, [1, 2, 3])"),
+        simple_eval("[6, 5, 3, 0]")
+    );
+    assert_eq!(
+        simple_eval("scanr(\\a, x -> x $ a, '', 1 to 3) join ','"),
+        Obj::from("123,23,3,")
+    );
+    assert_eq!(
+        simple_eval("scanr(\\a, x -> a + x, 0, [])"),
+        simple_eval("[0]")
+    );
+    assert_eq!(
+        simple_eval("try scanr(\\a, x -> a + x, 0, repeat(1)) catch e -> e"),
+        Obj::from("value error: scanr: infinite, will not terminate")
+    );
+}
+
+#[test]
+fn take_n() {
+    assert_eq!(
+        simple_eval("take(4, iota(0)) join ','"),
+        Obj::from("0,1,2,3")
+    );
+    assert_eq!(simple_eval("take(5, [1, 2]) join ','"), Obj::from("1,2"));
+    assert_eq!(
+        simple_eval("take(3, take(100000000000000000000000, iota(0))) join ','"),
+        Obj::from("0,1,2")
+    );
+    assert_eq!(simple_eval("len(take(4, 0 til 10))"), i(4));
+    assert_eq!(simple_eval("len(take(40, 0 til 10))"), i(10));
+    assert_eq!(
+        simple_eval("try take(-1, iota(0)) catch e -> e"),
+        Obj::from("take: value error: can't take a negative number of elements: -1")
+    );
+    // the inner stream can stop before n
+    assert_eq!(
+        simple_eval("len(list(take(10, iterate(0, \\x -> (x < 2 or break; x + 1)))))"),
+        i(3)
+    );
+    // a mapped stream's length isn't known without running the function, so count that far
+    assert_eq!(simple_eval("len(take(5, (0 til 3) lazy_map (+1)))"), i(3));
+    assert_eq!(simple_eval("len(take(3, iota(0) lazy_map (+1)))"), i(3));
+    assert_eq!(simple_eval("len(take(3, iterate(0, (+1))))"), i(3));
+    // taking doesn't hide an error from the function
+    assert_eq!(
+        simple_eval("try list(take(5, iterate(1, \\x -> x + 1) lazy_map \\x -> 10 // (x - 3))) catch e -> 'error'"),
+        Obj::from("error")
+    );
+    assert_eq!(simple_eval("len(take(3, iota(0)))"), i(3));
+    // the inner stream is only walked as far as n
+    assert_eq!(simple_eval("len(take(3, dedup(0 til (10^30))))"), i(3));
+    assert_eq!(simple_eval("len(take(3, dedup(1 to 10)))"), i(3));
+    assert_eq!(simple_eval("len(take(3, partitions(1000)))"), i(3));
+    // the other argument order is still eager slicing
+    assert_eq!(simple_eval("iota(0) take 2 is list"), i(1));
+}
+
+#[test]
+fn drop_n() {
+    assert_eq!(
+        simple_eval("drop(3, 0 til 10) join ','"),
+        Obj::from("3,4,5,6,7,8,9")
+    );
+    assert_eq!(simple_eval("drop(30, 0 til 10) join ','"), Obj::from(""));
+    assert_eq!(simple_eval("len(drop(3, 0 til 10))"), i(7));
+    assert_eq!(simple_eval("len(drop(30, 0 til 10))"), i(0));
+    assert_eq!(
+        simple_eval("take(3, drop(5, iota(0))) join ','"),
+        Obj::from("5,6,7")
+    );
+    assert_eq!(
+        simple_eval("try drop(-1, iota(0)) catch e -> e"),
+        Obj::from("drop: value error: can't drop a negative number of elements: -1")
+    );
+    // the length comes from the inner stream's size hint when it has one, without walking
+    assert_eq!(
+        simple_eval("remaining(drop(3, set_partitions(1 to 5)))"),
+        i(49)
+    );
+    assert_eq!(simple_eval("remaining(drop(3, dedup(1 to 10)))"), Obj::Null);
+    assert_eq!(simple_eval("len(drop(3, dedup(1 to 10)))"), i(7));
+    // the other argument order is still eager slicing
+    assert_eq!(simple_eval("list((0 til 5) drop 3)"), simple_eval("[3, 4]"));
+}
+
+#[test]
+fn tabulates() {
+    assert_eq!(
+        simple_eval("tabulate(\\i -> i * i) take 4"),
+        simple_eval("[0, 1, 4, 9]")
+    );
+    assert_eq!(
+        simple_eval("tabulate(\\i -> i * i, 4) join ','"),
+        Obj::from("0,1,4,9")
+    );
+    assert_eq!(simple_eval("len(tabulate(\\i -> i, 7))"), i(7));
+    assert_eq!(simple_eval("tabulate(\\i -> i * i)[1000]"), i(1000000));
+    assert_eq!(simple_eval("tabulate(\\i -> i * i, 5)[-1]"), i(16));
+    assert_eq!(
+        simple_eval("c := 0; t := tabulate(\\i -> (c += 1; i)); t[10]; t[20]; c"),
+        i(2)
+    );
+}
+
+#[test]
+fn unfolds() {
+    assert_eq!(
+        simple_eval("unfold(\\[a, b] -> [a, [b, a + b]], [0, 1]) take 8"),
+        simple_eval("[0, 1, 1, 2, 3, 5, 8, 13]")
+    );
+    assert_eq!(
+        simple_eval("unfold(\\n -> [null, [1, 0], [4, 1], [9, 2]][n], 3) join ','"),
+        Obj::from("9,4,1")
+    );
+    assert_eq!(
+        simple_eval("try unfold(\\n -> n, 3) take 1 catch e -> e"),
+        Obj::from(
+            "take: type error: unfold: function must return [output, next state] or null, got 3"
+        )
+    );
+}
+
+#[test]
+fn primes_stream() {
+    assert_eq!(
+        simple_eval("primes take 5"),
+        simple_eval("[2, 3, 5, 7, 11]")
+    );
+    assert_eq!(simple_eval("primes[:5]"), simple_eval("[2, 3, 5, 7, 11]"));
+    assert_eq!(simple_eval("primes[3:6]"), simple_eval("[7, 11, 13]"));
+    assert_eq!(simple_eval("primes[999]"), i(7919));
+    assert_eq!(
+        simple_eval("try primes[-1] catch e -> e"),
+        Obj::from("value error: Cannot force primes because it's infinite")
+    );
+}
+
+#[test]
+fn combinations_with_replacement_stream() {
+    assert_eq!(
+        simple_eval("list(combinations_with_replacement([0, 1], 2))"),
+        simple_eval("[[0, 0], [0, 1], [1, 1]]")
+    );
+    assert_eq!(
+        simple_eval("combinations_with_replacement('abc', 2) map (join '') join ','"),
+        Obj::from("aa,ab,ac,bb,bc,cc")
+    );
+    assert_eq!(
+        simple_eval("list(combinations_with_replacement([], 2))"),
+        simple_eval("[]")
+    );
+    assert_eq!(
+        simple_eval("list(combinations_with_replacement([], 0))"),
+        simple_eval("[[]]")
+    );
+    assert_eq!(
+        simple_eval("len(combinations_with_replacement(1 to 5, 3))"),
+        i(35)
+    );
+    assert_eq!(
+        simple_eval("c := combinations_with_replacement(1 to 4, 3); r := c; for (_ <- 1 to 7) (_, r = uncons(r)); [len(r), len(list(r))]"),
+        simple_eval("[13, 13]")
+    );
+}
+
+#[test]
+fn k_permutations() {
+    assert_eq!(
+        simple_eval("list(permutations([1, 2, 3], 2))"),
+        simple_eval("[[1, 2], [1, 3], [2, 1], [2, 3], [3, 1], [3, 2]]")
+    );
+    assert_eq!(
+        simple_eval("list(permutations([1, 2], 0))"),
+        simple_eval("[[]]")
+    );
+    assert_eq!(
+        simple_eval("list(permutations([1, 2], 3))"),
+        simple_eval("[]")
+    );
+    assert_eq!(
+        simple_eval("list(permutations([1, 2, 3], 3)) == list(permutations([1, 2, 3]))"),
+        i(1)
+    );
+    assert_eq!(simple_eval("len(permutations(1 to 6, 3))"), i(120));
+    assert_eq!(
+        simple_eval("r := permutations(1 to 5, 3); for (_ <- 1 to 17) (_, r = uncons(r)); [len(r), len(list(r))]"),
+        simple_eval("[43, 43]")
+    );
+}
+
+#[test]
+fn cartesian_product_stream() {
+    assert_eq!(
+        simple_eval("list(cartesian_product([1, 2], ['a', 'b']))"),
+        simple_eval("[[1, 'a'], [1, 'b'], [2, 'a'], [2, 'b']]")
+    );
+    assert_eq!(
+        simple_eval("cartesian_product(1 to 2, 'abc') map (join '') join ','"),
+        Obj::from("1a,1b,1c,2a,2b,2c")
+    );
+    assert_eq!(
+        simple_eval("list(cartesian_product([1], []))"),
+        simple_eval("[]")
+    );
+    assert_eq!(
+        simple_eval("list(cartesian_product())"),
+        simple_eval("[[]]")
+    );
+    assert_eq!(
+        simple_eval("len(cartesian_product(1 to 3, 1 to 4, 1 to 5))"),
+        i(60)
+    );
+    assert_eq!(
+        simple_eval("cartesian_product(1 to 3, 1 to 4, 1 to 5)[37]"),
+        simple_eval("[2, 4, 3]")
+    );
+    assert_eq!(
+        simple_eval("_, r := uncons(cartesian_product(1 to 3, 1 to 4)); [len(r), r[0], r[-1]]"),
+        simple_eval("[11, [1, 2], [3, 4]]")
+    );
+}
+
+#[test]
+fn integer_partitions() {
+    assert_eq!(
+        simple_eval("list(partitions(4))"),
+        simple_eval("[[4], [3, 1], [2, 2], [2, 1, 1], [1, 1, 1, 1]]")
+    );
+    assert_eq!(simple_eval("len(partitions(4))"), i(5));
+    assert_eq!(simple_eval("list(partitions(0))"), simple_eval("[[]]"));
+    assert_eq!(simple_eval("len(partitions(30))"), i(5604));
+    assert_eq!(simple_eval("len(list(partitions(12)))"), i(77));
+    assert_eq!(
+        simple_eval(
+            "r := partitions(12); for (_ <- 1 to 30) (_, r = uncons(r)); [len(r), len(list(r))]"
+        ),
+        simple_eval("[47, 47]")
+    );
+    assert_eq!(
+        simple_eval("len(partitions(416)) == 17873792969689876004"),
+        i(1)
+    );
+    assert_eq!(
+        simple_eval("list(take(2, partitions(100000)))"),
+        simple_eval("[[100000], [99999, 1]]")
+    );
+}
+
+#[test]
+fn integer_compositions() {
+    assert_eq!(
+        simple_eval("list(compositions(3))"),
+        simple_eval("[[3], [2, 1], [1, 2], [1, 1, 1]]")
+    );
+    assert_eq!(
+        simple_eval("list(compositions(4, 2))"),
+        simple_eval("[[3, 1], [2, 2], [1, 3]]")
+    );
+    assert_eq!(simple_eval("list(compositions(0))"), simple_eval("[[]]"));
+    assert_eq!(simple_eval("list(compositions(2, 3))"), simple_eval("[]"));
+    assert_eq!(simple_eval("len(compositions(10))"), i(512));
+    assert_eq!(simple_eval("len(compositions(10, 4))"), i(84));
+    assert_eq!(simple_eval("len(list(compositions(10)))"), i(512));
+    assert_eq!(simple_eval("len(list(compositions(10, 4)))"), i(84));
+    assert_eq!(
+        simple_eval(
+            "r := compositions(8); for (_ <- 1 to 50) (_, r = uncons(r)); [len(r), len(list(r))]"
+        ),
+        simple_eval("[78, 78]")
+    );
+    assert_eq!(
+        simple_eval("r := compositions(9, 4); for (_ <- 1 to 20) (_, r = uncons(r)); [len(r), len(list(r))]"),
+        simple_eval("[36, 36]")
+    );
+}
+
+#[test]
+fn gray_code_subsequences() {
+    assert_eq!(
+        simple_eval("list(gray_subsequences(1 to 3))"),
+        simple_eval("[[], [1], [1, 2], [2], [2, 3], [1, 2, 3], [1, 3], [3]]")
+    );
+    assert_eq!(
+        simple_eval("pairwise(list(gray_subsequences(1 to 4))) map (\\[a, b] -> len((set(a) -- set(b)) || (set(b) -- set(a)))) == (1 .* 15)"),
+        i(1)
+    );
+    assert_eq!(
+        simple_eval("len(unique(list(gray_subsequences(1 to 4))))"),
+        i(16)
+    );
+    assert_eq!(simple_eval("len(gray_subsequences(1 to 4))"), i(16));
+    assert_eq!(simple_eval("len(list(gray_subsequences([])))"), i(1));
+}
+
+#[test]
+fn derangements_stream() {
+    assert_eq!(
+        simple_eval("list(derangements([1, 2, 3]))"),
+        simple_eval("[[2, 3, 1], [3, 1, 2]]")
+    );
+    assert_eq!(simple_eval("len(derangements([1, 2, 3]))"), i(2));
+    assert_eq!(simple_eval("len(derangements(1 to 6))"), i(265));
+    assert_eq!(simple_eval("len(list(derangements(1 to 6)))"), i(265));
+    assert_eq!(simple_eval("list(derangements([]))"), simple_eval("[[]]"));
+    assert_eq!(simple_eval("list(derangements([1]))"), simple_eval("[]"));
+    assert_eq!(
+        simple_eval("_, r := uncons(derangements(1 to 4)); [len(r), len(list(r))]"),
+        simple_eval("[8, 8]")
+    );
+}
+
+#[test]
+fn stream_nth() {
+    // fast paths
+    assert_eq!(simple_eval("(0 til 10000000000)[123456789]"), i(123456789));
+    assert_eq!(simple_eval("(0 til 10 by 3)[-1]"), i(9));
+    assert_eq!(simple_eval("(10 til 0 by (-4))[2]"), i(2));
+    assert_eq!(simple_eval("iota(5)[10]"), i(15));
+    assert_eq!(
+        simple_eval("(1 to 100000000000000000000)[-2]"),
+        simple_eval("99999999999999999999")
+    );
+    assert_eq!(simple_eval("cycle([1, 2, 3])[100]"), i(2));
+    assert_eq!(
+        simple_eval("try (0 til 10)[10] catch e -> e"),
+        Obj::from("index error: Index out of bounds: 10")
+    );
+    assert_eq!(
+        simple_eval("try (0 til 10)[-11] catch e -> e"),
+        Obj::from("index error: Index out of bounds: -11")
+    );
+    assert_eq!(
+        simple_eval("try iota(0)[-1] catch e -> e"),
+        Obj::from("index error: Can't index infinite range from the end: -1")
+    );
+    // stepping through
+    assert_eq!(simple_eval("((0 til 10) lazy_map \\x -> x * x)[4]"), i(16));
+    assert_eq!(simple_eval("((0 til 10) lazy_map \\x -> x * x)[-1]"), i(81));
+    assert_eq!(
+        simple_eval("try ((0 til 10) lazy_map \\x -> x * x)[10] catch e -> e"),
+        Obj::from("index error: Index out of bounds: 10")
+    );
+}
+
+#[test]
+fn stream_is_infinite() {
+    let t = i(1);
+    let f = i(0);
+    let unknown = simple_eval("null");
+    for (code, expected) in [
+        ("repeat(1)", &t),
+        ("cycle([1, 2])", &t),
+        ("iota(0)", &t),
+        ("primes", &t),
+        ("0 til 10", &f),
+        ("0 til 10000000000000000000000", &f),
+        ("[1, 2, 3]", &f),
+        ("permutations([1, 2, 3])", &f),
+        ("permutations([1, 2, 3], 2)", &f),
+        ("derangements([1, 2, 3])", &f),
+        ("combinations([1, 2, 3], 2)", &f),
+        ("combinations_with_replacement([1, 2, 3], 2)", &f),
+        ("subsequences([1, 2, 3])", &f),
+        ("gray_subsequences([1, 2, 3])", &f),
+        ("[1, 2] ^^ 3", &f),
+        ("cartesian_product([1, 2], [3])", &f),
+        ("partitions(5)", &f),
+        ("compositions(5)", &f),
+        ("iterate(0, \\x -> x + 1)", &unknown),
+        ("unfold(\\x -> [x, x], 0)", &unknown),
+        ("tabulate(\\x -> x)", &unknown),
+        ("tabulate(\\x -> x, 5)", &f),
+        ("iota(0) lazy_map \\x -> x", &unknown),
+        ("(0 til 5) lazy_map \\x -> x", &f),
+        ("iota(0) stride 2", &t),
+        ("chain([1], repeat(2))", &t),
+        ("chain([1], 0 til 3)", &f),
+        ("interleave([1], iota(0))", &t),
+        ("dedup(iota(0))", &unknown),
+        ("unique(0 til 5)", &f),
+        ("intersperse(0, iota(0))", &t),
+        ("pairwise(iota(0))", &t),
+        ("take(5, iota(0))", &f),
+        ("drop(5, iota(0))", &t),
+        ("cache(iota(0))", &t),
+    ] {
+        assert_eq!(
+            &simple_eval(&format!("is_infinite({})", code)),
+            expected,
+            "{}",
+            code
+        );
+    }
+    assert_eq!(
+        simple_eval("try list(primes) catch e -> e"),
+        Obj::from("value error: Cannot force primes because it's infinite")
+    );
+    assert_eq!(
+        simple_eval("try reverse(iota(0) stride 2) catch e -> e"),
+        Obj::from("reverse(stream): value error: Cannot reverse StridedStream(0 til ... by 1, 2, 0, ...) because it's infinite")
+    );
+}
+
+#[test]
+fn force_sized_streams() {
+    assert_eq!(simple_eval("len(list(0 til 100000))"), i(100000));
+    assert_eq!(
+        simple_eval("list(10 til 0 by (-3))"),
+        simple_eval("[10, 7, 4, 1]")
+    );
+    assert_eq!(simple_eval("len(list(permutations(1 to 5)))"), i(120));
+    assert_eq!(
+        simple_eval("_, r := uncons(1 to 5); list(r)"),
+        simple_eval("[2, 3, 4, 5]")
+    );
+}
+
+#[test]
+fn running_extrema() {
+    assert_eq!(
+        simple_eval("running_min([3, 1, 4, 1, 5])"),
+        simple_eval("[3, 1, 1, 1, 1]")
+    );
+    assert_eq!(
+        simple_eval("running_max([3, 1, 4, 1, 5])"),
+        simple_eval("[3, 3, 4, 4, 5]")
+    );
+    assert_eq!(
+        simple_eval("list(take(4, running_max(cycle([2, 7, 1]))))"),
+        simple_eval("[2, 7, 7, 7]")
+    );
+    assert_eq!(simple_eval("len(running_min(1 to 10))"), i(10));
+    assert_eq!(
+        simple_eval("try running_min([1, \"a\"]) catch e -> e"),
+        Obj::from("running_min(list): type error: Can't compare \"a\" and 1")
+    );
+}
+
+#[test]
+fn sliding_max() {
+    assert_eq!(
+        simple_eval("[1, 3, 2, 5, 4, 1] sliding_max 3"),
+        simple_eval("[3, 5, 5, 5]")
+    );
+    assert_eq!(simple_eval("[1, 2] sliding_max 3"), simple_eval("[]"));
+    assert_eq!(simple_eval("len((1 to 10) sliding_max 4)"), i(7));
+    assert_eq!(
+        simple_eval(
+            "try list(sliding_max((0 til 4) lazy_map (\\x -> [1, 'a', 3, 2][x]), 2)) catch e -> e"
+        ),
+        Obj::from("type error: Can't compare 1 and \"a\"")
+    );
+    let mut seed: u64 = 12345;
+    for w in 1..6 {
+        let xs: Vec<String> = (0..30)
+            .map(|_| {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                ((seed >> 33) % 10).to_string()
+            })
+            .collect();
+        let xs = format!("[{}]", xs.join(", "));
+        assert_eq!(
+            simple_eval(&format!("{} sliding_max {}", xs, w)),
+            simple_eval(&format!("{} window {} map max", xs, w)),
+            "{} {}",
+            xs,
+            w
+        );
+    }
+}
+
+#[test]
+fn merge_sorted() {
+    assert_eq!(
+        simple_eval("list([1, 3, 5] merge_sorted [2, 4, 6])"),
+        simple_eval("[1, 2, 3, 4, 5, 6]")
+    );
+    assert_eq!(
+        simple_eval("list([1, 1, 9] merge_sorted (0 til 3))"),
+        simple_eval("[0, 1, 1, 1, 2, 9]")
+    );
+    assert_eq!(simple_eval("len([1, 3] merge_sorted (0 til 3))"), i(5));
+    assert_eq!(
+        simple_eval("len((0 til (2 ^ 63)) merge_sorted (0 til (2 ^ 63)))"),
+        Obj::from(f64::INFINITY)
+    );
+    assert_eq!(
+        simple_eval("list(take(7, primes merge_sorted [4, 6, 8]))"),
+        simple_eval("[2, 3, 4, 5, 6, 7, 8]")
+    );
+    assert_eq!(
+        simple_eval("is_infinite(primes merge_sorted [4])"),
+        Obj::from(true)
+    );
+    assert_eq!(
+        simple_eval("list([1, \"a\", 3] merge_sorted [2])"),
+        simple_eval("[1, 2, \"a\", 3]")
+    );
+    assert_eq!(
+        simple_eval("list([1, 3, \"a\"] merge_sorted [2, \"b\"])"),
+        simple_eval("[1, 2, 3, \"a\", \"b\"]")
+    );
+}
+
+#[test]
+fn random_stream() {
+    assert_eq!(
+        simple_eval("list(take(5, random_stream(42)))"),
+        simple_eval("list(take(5, random_stream(42)))")
+    );
+    assert_eq!(
+        simple_eval("list(take(5, random_stream(1))) != list(take(5, random_stream(2)))"),
+        i(1)
+    );
+    assert_eq!(
+        simple_eval("s := random_range(7, 1, 7); _, r := uncons(s); list(take(10, r)) == list(take(10, drop(1, s)))"),
+        Obj::from(true)
+    );
+    assert_eq!(
+        simple_eval("all(list(take(100, random_range(7, 1, 7))) map \\x -> 1 <= x < 7)"),
+        Obj::from(true)
+    );
+    assert_eq!(
+        simple_eval("all(list(take(100, random_stream(-3))) map \\x -> 0 <= x < 1)"),
+        Obj::from(true)
+    );
+    assert_eq!(
+        simple_eval("is_infinite(random_stream(0))"),
+        Obj::from(true)
+    );
+    assert_eq!(
+        simple_eval("list(take(5, random_range(42, -5, 5)))"),
+        simple_eval("list(take(5, random_range(42, -5, 5)))")
+    );
+}
+
+#[test]
+fn reservoir_sample() {
+    assert_eq!(
+        simple_eval("reservoir_sample(3, 1 to 100, 5)"),
+        simple_eval("reservoir_sample(3, 1 to 100, 5)")
+    );
+    assert_eq!(
+        simple_eval("sort(reservoir_sample(5, 1 to 5, 9))"),
+        simple_eval("[1, 2, 3, 4, 5]")
+    );
+    assert_eq!(
+        simple_eval("reservoir_sample(5, [1, 2], 0, true)"),
+        simple_eval("[1, 2]")
+    );
+    assert_eq!(
+        simple_eval("try reservoir_sample(5, [1, 2], 0) catch e -> e"),
+        Obj::from("reservoir_sample: value error: wanted 5 elements but only got 2")
+    );
+    assert_eq!(
+        simple_eval("try reservoir_sample(1, primes, 0) catch e -> e"),
+        Obj::from(
+            "reservoir_sample: value error: can't sample from primes(from 2) because it's infinite"
+        )
+    );
+    // each of 10 elements should land in a 2-sample about 20% of the time; over 2000 seeds that's
+    // 400 each, and 300..500 is many standard deviations of slack
+    let counts = simple_eval(
+        "c := 0 til 10 map \\_ -> 0; for (s <- 0 til 2000; x <- reservoir_sample(2, 0 til 10, s)) c[x] += 1; c",
+    );
+    assert_eq!(
+        simple_eval(&format!("all({} map \\n -> 300 < n < 500)", counts)),
+        Obj::from(true),
+        "{}",
+        counts
+    );
+}
+
+#[test]
+fn stream_equality() {
+    assert_eq!(simple_eval("(0 til 3) == [0, 1, 2]"), i(1));
+    assert_eq!(simple_eval("[0, 1, 2] == (0 til 3)"), i(1));
+    assert_eq!(simple_eval("(0 til 3) == [0, 1]"), i(0));
+    assert_eq!(simple_eval("(0 til 3) == [0, 1, 3]"), i(0));
+    assert_eq!(simple_eval("(0 til 3) == (0 to 2)"), i(1));
+    assert_eq!(simple_eval("(0 til 3) != (0 til 4)"), i(1));
+    assert_eq!(simple_eval("repeat(1) == repeat(1)"), i(0));
+    assert_eq!(simple_eval("iota(0) == iota(0)"), i(0));
+    assert_eq!(simple_eval("iterate(0, \\x -> x + 1) == [0]"), i(0));
+    // each element is computed once, and errors aren't swallowed
+    assert_eq!(
+        simple_eval("c := 0; r := ((1 to 3) lazy_map \\x -> (c += 1; x)) == [1, 2, 3]; [r, c]"),
+        simple_eval("[1, 3]")
+    );
+    assert_eq!(
+        simple_eval(
+            "try ((1 to 5) lazy_map \\x -> 10 // (x - 3)) == [-5, -10, 0, 0, 0] catch e -> 'error'"
+        ),
+        Obj::from("error")
+    );
+    // streams nested in lists and dict values compare the same way
+    assert_eq!(simple_eval("[0 til 3] == [[0, 1, 2]]"), i(1));
+    assert_eq!(simple_eval("[[1], 0 til 3] != [[1], [0, 1]]"), i(1));
+    assert_eq!(simple_eval("{1: 0 til 2} == {1: [0, 1]}"), i(1));
+    assert_eq!(simple_eval("{1: 0 til 2} == {2: [0, 1]}"), i(0));
+}
+
+#[test]
+fn stream_display() {
+    // short enough to fit, so it shows what it is rather than looking like a list
+    assert_eq!(simple_eval("str(1 til 4)"), Obj::from("1 til 4 by 1"));
+    assert_eq!(
+        simple_eval("str(1 til 12)"),
+        Obj::from("[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, ..., (11 elements)]")
+    );
+    assert_eq!(
+        simple_eval("str(iota(1))"),
+        Obj::from("[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, ...]")
+    );
+    assert_eq!(
+        simple_eval("str(0 til 100)"),
+        Obj::from("[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, ..., (100 elements)]")
+    );
+    assert_eq!(
+        simple_eval("set_stream_preview(3); [str(iota(1)), F\"{iota(1)}\"]"),
+        simple_eval("['[1, 2, 3, ...]', '[1, 2, 3, ...]']")
+    );
+    assert_eq!(
+        simple_eval("set_stream_preview(0); str(1 to 3)"),
+        Obj::from("1 til 4 by 1")
+    );
+    // the setting belongs to one interpreter
+    assert_eq!(
+        simple_eval("str(1 to 30)"),
+        Obj::from("[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, ..., (30 elements)]")
+    );
+    // anything that would have to run a function or do real work shows its structure instead
+    assert_eq!(
+        simple_eval("str(repeat(0) lazy_map \\x -> x + 1)"),
+        Obj::from("MappedStream(repeat(0), Closure, ...)")
+    );
+    assert_eq!(
+        simple_eval("str(dedup(repeat(1)))"),
+        Obj::from("DedupStream(repeat(1))")
+    );
+    assert_eq!(
+        simple_eval("c := 0; s := iota(0) lazy_map \\x -> (c += 1; x); str(s); c"),
+        i(0)
+    );
+    // caches show what they've computed so far
+    assert_eq!(
+        simple_eval("s := cache(iota(0)); s take 3; str(s)"),
+        Obj::from("[0, 1, 2, ...]")
+    );
+    assert_eq!(
+        simple_eval("s := cache(1 to 3); list(s); str(s)"),
+        Obj::from("CachedStream(at 0, 3 cached)")
+    );
+}
+
+#[test]
+fn step_by() {
+    assert_eq!(
+        simple_eval("list(step_by(0 til 10, 3, 1))"),
+        simple_eval("[1, 4, 7]")
+    );
+    assert_eq!(
+        simple_eval("step_by([0, 1, 2, 3, 4, 5], 2, 1)"),
+        simple_eval("[1, 3, 5]")
+    );
+    assert_eq!(
+        simple_eval("list((0 til 10) step_by 4)"),
+        simple_eval("[0, 4, 8]")
+    );
+    for (stride, offset, expected) in [(3, 0, 4), (3, 1, 3), (3, 2, 3), (5, 4, 2), (11, 3, 1)] {
+        assert_eq!(
+            simple_eval(&format!("len(step_by(0 til 10, {}, {}))", stride, offset)),
+            i(expected)
+        );
+    }
+    assert_eq!(
+        simple_eval("_, rest := uncons(step_by(0 til 10, 3, 1)); len(rest)"),
+        i(2)
+    );
+    assert_eq!(
+        simple_eval("try step_by(0 til 10, 3, 3) catch e -> e"),
+        Obj::from("stride: value error: stride offset must be less than stride 3, got 3")
+    );
+    assert_eq!(
+        simple_eval("try step_by([1, 2], 2, 5) catch e -> e"),
+        Obj::from("stride: value error: stride offset must be less than stride 2, got 5")
+    );
+    // lists and streams agree on what's invalid
+    for seq in ["[1, 2, 3]", "1 to 3"] {
+        assert_eq!(
+            simple_eval(&format!("try step_by({}, 0) catch e -> e", seq)),
+            Obj::from("stride: value error: stride must be positive")
+        );
+    }
+    for seq in ["[1, 2, 3, 4]", "1 to 4"] {
+        assert_eq!(
+            simple_eval(&format!("try step_by({}, -2) catch e -> e", seq)),
+            Obj::from("stride: value error: stride must be positive")
+        );
+    }
+}
+
+#[test]
+fn collatz() {
+    assert_eq!(
+        simple_eval("list(collatz(6))"),
+        simple_eval("[6, 3, 10, 5, 16, 8, 4, 2, 1]")
+    );
+    assert_eq!(simple_eval("list(collatz(1))"), simple_eval("[1]"));
+    assert_eq!(simple_eval("len(collatz(27))"), i(112));
+    assert_eq!(simple_eval("max(collatz(27))"), i(9232));
+    assert_eq!(
+        simple_eval("try collatz(0) catch e -> e"),
+        Obj::from("collatz(int): value error: collatz needs a positive start, got 0")
+    );
+}
+
+#[test]
+fn linspace() {
+    assert_eq!(
+        simple_eval("list(linspace(0, 1, 5))"),
+        simple_eval("[0, 0.25, 0.5, 0.75, 1]")
+    );
+    assert_eq!(simple_eval("linspace(0.1, 0.7, 7)[-1]"), Obj::from(0.7));
+    assert_eq!(simple_eval("linspace(0.1, 0.7, 7)[0]"), Obj::from(0.1));
+    assert_eq!(simple_eval("linspace(0, 10, 11)[3]"), Obj::from(3.0));
+    assert_eq!(simple_eval("len(linspace(0, 1, 100))"), i(100));
+    assert_eq!(simple_eval("list(linspace(2, 3, 1))"), simple_eval("[2]"));
+    assert_eq!(simple_eval("list(linspace(2, 3, 0))"), simple_eval("[]"));
+    assert_eq!(
+        simple_eval("_, r := uncons(linspace(0, 1, 3)); [len(r), r[0], r[-1]]"),
+        simple_eval("[2, 0.5, 1]")
+    );
+}
+
+#[test]
+fn force_infinite_fails_fast() {
+    for code in ["list(iota(1))", "sort(iota(1))", "reverse(primes)"] {
+        assert!(
+            simple_eval(&format!("try {} catch e -> e", code))
+                .to_string()
+                .contains("because it's infinite"),
+            "{}",
+            code
+        );
+    }
+    for (code, purpose) in [
+        ("vector(iota(1))", "vector"),
+        ("bytes(repeat(1))", "bytes"),
+        ("dict(repeat([1, 2]))", "dict"),
+    ] {
+        assert_eq!(
+            simple_eval(&format!("try {} catch e -> e", code)),
+            Obj::from(format!(
+                "value error: {} conversion: infinite, will not terminate",
+                purpose
+            ))
+        );
+    }
+    assert_eq!(simple_eval("vector(1 to 3)"), simple_eval("V(1, 2, 3)"));
+}
+
+#[test]
+fn take_and_drop_last() {
+    assert_eq!(
+        simple_eval("drop_last(2, [1, 2, 3, 4, 5])"),
+        simple_eval("[1, 2, 3]")
+    );
+    assert_eq!(
+        simple_eval("take_last(2, [1, 2, 3, 4, 5])"),
+        simple_eval("[4, 5]")
+    );
+    assert_eq!(
+        simple_eval("list((1 to 5) drop_last 2)"),
+        simple_eval("[1, 2, 3]")
+    );
+    assert_eq!(simple_eval("(1 to 5) take_last 2"), simple_eval("[4, 5]"));
+    assert_eq!(simple_eval("take_last(0, [1, 2])"), simple_eval("[]"));
+    assert_eq!(simple_eval("take_last(5, [1, 2])"), simple_eval("[1, 2]"));
+    assert_eq!(
+        simple_eval("take_last(10^18, [1, 2])"),
+        simple_eval("[1, 2]")
+    );
+    assert_eq!(simple_eval("drop_last(5, [1, 2])"), simple_eval("[]"));
+    assert_eq!(simple_eval("len((1 to 10) drop_last 3)"), i(7));
+    assert_eq!(
+        simple_eval("_, r := uncons((1 to 10) drop_last 3); len(r)"),
+        i(6)
+    );
+    assert_eq!(
+        simple_eval("try drop_last(1, iota(0)) catch e -> e"),
+        Obj::from("drop_last: value error: 0 til ... by 1 is infinite, will not terminate")
+    );
+    assert_eq!(
+        simple_eval("try take_last(1, repeat(0)) catch e -> e"),
+        Obj::from("take_last: value error: repeat(0) is infinite, will not terminate")
+    );
+}
+
+#[test]
+fn run_length() {
+    assert_eq!(
+        simple_eval("run_length([1, 1, 2, 3, 3, 3])"),
+        simple_eval("[[1, 2], [2, 1], [3, 3]]")
+    );
+    assert_eq!(simple_eval("run_length([])"), simple_eval("[]"));
+    assert_eq!(
+        simple_eval("run_length('aab') map (\\[c, n] -> c .* n join '') join ''"),
+        Obj::from("aab")
+    );
+    assert_eq!(
+        simple_eval("list(take(3, run_length(cycle([0, 0, 1]))))"),
+        simple_eval("[[0, 2], [1, 1], [0, 2]]")
+    );
+}
+
+#[test]
+fn group_by() {
+    assert_eq!(
+        simple_eval("group_by(\\x -> x % 2, [1, 3, 2, 4, 5])"),
+        simple_eval("[[1, [1, 3]], [0, [2, 4]], [1, [5]]]")
+    );
+    assert_eq!(
+        simple_eval("(1 to 7) group_by (\\x -> x // 3) map first"),
+        simple_eval("[0, 1, 2]")
+    );
+    assert_eq!(
+        simple_eval("list(take(2, group_by(\\x -> x // 2, iota(0))))"),
+        simple_eval("[[0, [0, 1]], [1, [2, 3]]]")
+    );
+    assert_eq!(
+        simple_eval("try group_by(\\x -> x + 'a', [1]) catch e -> 0"),
+        i(0)
+    );
+}
+
+#[test]
+fn stream_contains() {
+    assert_eq!(simple_eval("iota(1) contains 7"), i(1));
+    assert_eq!(simple_eval("0 in iota(1)"), i(0));
+    assert_eq!(simple_eval("10 ∈ (0 til 100 by 5)"), i(1));
+    assert_eq!(simple_eval("12 ∈ (0 til 100 by 5)"), i(0));
+    assert_eq!(simple_eval("100 ∈ (0 til 100 by 5)"), i(0));
+    assert_eq!(simple_eval("(-4) ∈ (10 til (-10) by (-2))"), i(1));
+    assert_eq!(simple_eval("(10 ^ 30) ∈ iota(0)"), i(1));
+    assert_eq!(simple_eval("(-(10 ^ 30)) ∈ iota(0)"), i(0));
+    assert_eq!(simple_eval("2.0 ∈ (0 til 5)"), i(1));
+    assert_eq!(simple_eval("2.5 ∈ iota(0)"), i(0));
+    assert_eq!(simple_eval("(10 / 2) ∈ iota(0)"), i(1));
+    assert_eq!(simple_eval("(5 / 2) ∈ iota(0)"), i(0));
+    assert_eq!(simple_eval("(-3.0) ∈ iota(0)"), i(0));
+    assert_eq!(simple_eval("contains(7, 1 til 100)"), i(1));
+    assert_eq!(simple_eval("contains(8, iota(1) lazy_map (*2))"), i(1));
+    assert_eq!(simple_eval("contains(7, (1 to 5) lazy_map (*2))"), i(0));
+    assert_eq!(simple_eval("'a' ∈ iota(0)"), i(0));
+    assert_eq!(simple_eval("49 ∈ (iota(0) lazy_map \\x -> x * x)"), i(1));
+    assert_eq!(simple_eval("5 ∈ take(2, [1, 2])"), i(0));
+    assert_eq!(
+        simple_eval("(iota(0) lazy_map \\x -> x * x) locate 49"),
+        i(7)
+    );
+    assert_eq!(simple_eval("primes locate (\\p -> p > 100)"), i(25));
+    assert_eq!(simple_eval("((1 to 5) lazy_map (*2)) locate? 7"), Obj::Null);
+}
+
+#[test]
+fn fold_streams() {
+    assert_eq!(simple_eval("fold(\\a, x -> a + x, 0, 0 til 100)"), i(4950));
+    assert_eq!(simple_eval("fold(\\a, x -> a + x, 0 til 100)"), i(4950));
+    assert_eq!(simple_eval("reduce(\\a, x -> a * x, 1 to 5)"), i(120));
+    assert_eq!(simple_eval("(1 to 5) reduce *"), i(120));
+    assert_eq!(
+        simple_eval("fold(\\a, x -> (x < 5 or break a; a + x), 0, iota(1))"),
+        i(10)
+    );
+    assert_eq!(
+        simple_eval("fold(\\a, x -> (x < 5 or break; a + x), 0, iota(1))"),
+        Obj::Null
+    );
+    assert_eq!(
+        simple_eval("fold(\\a, x -> (x < 5 or break 99; a + x), 0, iota(1))"),
+        i(99)
+    );
+    assert_eq!(
+        simple_eval("try reduce(+, 0 til 0) catch e -> e"),
+        Obj::from("empty error: fold: empty seq")
+    );
+}
+
+#[test]
+fn stream_aggregates() {
+    assert_eq!(
+        simple_eval("sum(0 til 1000000)"),
+        simple_eval("999999 * 1000000 // 2")
+    );
+    assert_eq!(simple_eval("product(1 to 10)"), i(3628800));
+    assert_eq!(simple_eval("sum((1 to 4) lazy_map \\x -> x * x)"), i(30));
+    assert_eq!(simple_eval("sum(0 til 0)"), i(0));
+    assert_eq!(simple_eval("product(0 til 0)"), i(1));
+    assert_eq!(
+        simple_eval("maximum((0 til 10) lazy_map \\x -> x % 7)"),
+        i(6)
+    );
+    assert_eq!(simple_eval("minimum(10 til 0 by (-3))"), i(1));
+    assert_eq!(
+        simple_eval("try minimum(0 til 0) catch e -> e"),
+        Obj::from("empty error: min: empty")
+    );
+    assert_eq!(
+        simple_eval("try sum(iota(0)) catch e -> e"),
+        Obj::from("value error: sum: infinite, will not terminate")
+    );
+    assert_eq!(
+        simple_eval("try max(repeat(1)) catch e -> e"),
+        Obj::from("value error: max: infinite, will not terminate")
+    );
+    // these can still stop partway through an infinite stream
+    assert_eq!(simple_eval("any(iota(0), \\x -> x > 5)"), i(1));
+}
+
+#[test]
+fn intersperse_lookahead() {
+    // after a separator, the next value is sitting in the peek buffer
+    assert_eq!(
+        simple_eval("s := intersperse(0, 1 to 3); _, r := uncons(s); _, r = uncons(r); [r[0], len(r), list(r), list(r)]"),
+        simple_eval("[2, 3, [2, 0, 3], [2, 0, 3]]")
+    );
+    assert_eq!(
+        simple_eval(
+            "try list(intersperse(0, ([1, 'a'] lazy_map \\x -> x + 1))) catch e -> 'error'"
+        ),
+        Obj::from("error")
+    );
+    assert_eq!(
+        simple_eval("intersperse(0, iota(1) lazy_map \\x -> x * x) take 5"),
+        simple_eval("[1, 0, 4, 0, 9]")
+    );
+}
+
+#[test]
+fn iterate_n() {
+    assert_eq!(
+        simple_eval("list(iterate(\\x -> x * 2, 1, 4))"),
+        simple_eval("[1, 2, 4, 8]")
+    );
+    assert_eq!(
+        simple_eval("list(iterate(1, \\x -> x * 2, 4))"),
+        simple_eval("[1, 2, 4, 8]")
+    );
+    assert_eq!(
+        simple_eval("iterate(1, \\x -> x * 2) take 3"),
+        simple_eval("[1, 2, 4]")
+    );
+    assert_eq!(simple_eval("len(iterate(0, \\x -> x + 1, 10))"), i(10));
+    assert_eq!(
+        simple_eval("list(iterate(0, \\x -> x + 1, 0))"),
+        simple_eval("[]")
+    );
+    // the function doesn't run past the last element
+    assert_eq!(
+        simple_eval("c := 0; list(iterate(0, \\x -> (c += 1; x + 1), 3)); c"),
+        i(2)
+    );
+    assert_eq!(
+        simple_eval("list(iterate(1, \\x -> (x < 4 or break; x + 1), 10))"),
+        simple_eval("[1, 2, 3, 4]")
+    );
+    assert_eq!(
+        simple_eval("is_infinite(iterate(0, \\x -> x, 3))"),
+        Obj::from(false)
+    );
+}
+
+#[test]
+fn stream_last() {
+    assert_eq!(
+        simple_eval("last(0 til (10 ^ 30))"),
+        simple_eval("(10 ^ 30) - 1")
+    );
+    assert_eq!(simple_eval("last(10 til 0 by (-3))"), i(1));
+    assert_eq!(simple_eval("last((1 to 5) lazy_map \\x -> x * x)"), i(25));
+    assert_eq!(simple_eval("last(linspace(0, 1, 7))"), Obj::from(1.0));
+    assert_eq!(simple_eval("last(iterate(1, \\x -> x * 2, 5))"), i(16));
+    assert_eq!(
+        simple_eval("try last(0 til 0) catch e -> e"),
+        Obj::from("last(stream): value error: empty stream")
+    );
+    assert_eq!(
+        simple_eval("try last((0 til 0) lazy_map \\x -> x) catch e -> e"),
+        Obj::from("last(stream): value error: empty stream")
+    );
+    assert_eq!(
+        simple_eval("try last(cycle([1, 2])) catch e -> e"),
+        Obj::from("last(stream): value error: Cannot take the last element of cycle(1, 2) because it's infinite")
+    );
+}
+
+#[test]
+fn rotate() {
+    assert_eq!(
+        simple_eval("rotate([1, 2, 3, 4, 5], 2)"),
+        simple_eval("[3, 4, 5, 1, 2]")
+    );
+    assert_eq!(
+        simple_eval("rotate([1, 2, 3, 4, 5], -1)"),
+        simple_eval("[5, 1, 2, 3, 4]")
+    );
+    assert_eq!(
+        simple_eval("rotate([1, 2, 3], 7)"),
+        simple_eval("[2, 3, 1]")
+    );
+    assert_eq!(simple_eval("rotate([], 3)"), simple_eval("[]"));
+    assert_eq!(
+        simple_eval("list((1 to 5) rotate 2)"),
+        simple_eval("[3, 4, 5, 1, 2]")
+    );
+    assert_eq!(simple_eval("len((0 til 100) rotate 30)"), i(100));
+    assert_eq!(
+        simple_eval("list(rotate((1 to 4) lazy_map \\x -> x * 10, 1) lazy_map \\x -> x + 1)"),
+        simple_eval("[21, 31, 41, 11]")
+    );
+    assert_eq!(
+        simple_eval("try rotate(iota(0), 1) catch e -> e"),
+        Obj::from("rotate: value error: 0 til ... by 1 is infinite, will not terminate")
+    );
+}
+
+#[test]
+fn split_at_and_partition() {
+    assert_eq!(
+        simple_eval("split_at(2, 0 til 5)"),
+        simple_eval("[[0, 1], [2, 3, 4]]")
+    );
+    assert_eq!(
+        simple_eval("[1, 2, 3] split_at 1"),
+        simple_eval("[[1], [2, 3]]")
+    );
+    assert_eq!(
+        simple_eval("split_at(-1, \"abc\")"),
+        simple_eval("[\"ab\", \"c\"]")
+    );
+    assert_eq!(
+        simple_eval("split_at(9, 0 til 5)"),
+        simple_eval("[[0, 1, 2, 3, 4], []]")
+    );
+    assert_eq!(
+        simple_eval("a, b := split_at(3, iota(0)); [a, b take 2]"),
+        simple_eval("[[0, 1, 2], [3, 4]]")
+    );
+    assert_eq!(
+        simple_eval("partition(odd, 1 to 6)"),
+        simple_eval("[[1, 3, 5], [2, 4, 6]]")
+    );
+    assert_eq!(
+        simple_eval("[1, 2, 3, 4] partition (>= 3)"),
+        simple_eval("[[3, 4], [1, 2]]")
+    );
+    assert_eq!(
+        simple_eval("try partition(odd, iota(0)) catch e -> e"),
+        Obj::from("partition: value error: partition: infinite, will not terminate")
+    );
+}
+
+#[test]
+fn count_elements() {
+    assert_eq!(
+        simple_eval("count_elements((1 to 100) filter (\\x -> x % 7 == 0))"),
+        i(14)
+    );
+    assert_eq!(
+        simple_eval("count_elements((1 to 10) filter (> 100))"),
+        i(0)
+    );
+    assert_eq!(simple_eval("count_elements(collatz(27))"), i(112));
+    assert_eq!(simple_eval("count_elements(run_length(1 to 5))"), i(5));
+    assert_eq!(
+        simple_eval("count_elements(0 til (10 ^ 30))"),
+        simple_eval("10 ^ 30")
+    );
+    assert_eq!(simple_eval("count_elements([1, 2, 3])"), i(3));
+    assert_eq!(simple_eval("count_elements(\"héllo\")"), i(5));
+    assert_eq!(
+        simple_eval(
+            "try count_elements((1 to 5) lazy_map \\x -> 10 // (x - 3)) catch e -> 'error'"
+        ),
+        Obj::from("error")
+    );
+    assert_eq!(
+        simple_eval("try count_elements(iota(0)) catch e -> e"),
+        Obj::from("count_elements(stream): value error: Cannot count the elements of 0 til ... by 1 because it's infinite")
+    );
+    assert_eq!(
+        simple_eval("try count(repeat(true)) catch e -> e"),
+        Obj::from("value error: count: infinite, will not terminate")
+    );
+}
+
+#[test]
+fn shuffle() {
+    assert_eq!(
+        simple_eval("list(shuffle(1 to 20, 7)) == list(shuffle(1 to 20, 7))"),
+        Obj::from(true)
+    );
+    assert_eq!(
+        simple_eval("list(shuffle(1 to 20, 7)) == list(shuffle(1 to 20, 8))"),
+        Obj::from(false)
+    );
+    assert_eq!(
+        simple_eval("sort(list(shuffle(1 to 20, 7)))"),
+        simple_eval("list(1 to 20)")
+    );
+    assert_eq!(
+        simple_eval("sort(list(shuffle([3, 1, 3, 2], 5)))"),
+        simple_eval("[1, 2, 3, 3]")
+    );
+    assert_eq!(simple_eval("len(shuffle(1 to 20, 7) take 3)"), i(3));
+    assert_eq!(simple_eval("list(shuffle([], 7))"), simple_eval("[]"));
+    assert_eq!(
+        simple_eval("try shuffle(iota(0), 7) catch e -> e"),
+        Obj::from("shuffle: value error: 0 til ... by 1 is infinite, will not terminate")
+    );
+}
+
+#[test]
+fn factorize() {
+    assert_eq!(
+        simple_eval("list(factorize(360))"),
+        simple_eval("[[2, 3], [3, 2], [5, 1]]")
+    );
+    assert_eq!(simple_eval("list(factorize(1))"), simple_eval("[]"));
+    assert_eq!(simple_eval("list(factorize(97))"), simple_eval("[[97, 1]]"));
+    assert_eq!(
+        simple_eval("list(factorize(2 * (1000003 ^ 2)))"),
+        simple_eval("[[2, 1], [1000003, 2]]")
+    );
+    assert_eq!(
+        simple_eval("list(factorize(5 * 7 * 11 * 13 * 25))"),
+        simple_eval("[[5, 3], [7, 1], [11, 1], [13, 1]]")
+    );
+    assert_eq!(
+        simple_eval("factorize((2 ^ 64) + 1) take 1"),
+        simple_eval("[[274177, 1]]")
+    );
+    assert_eq!(
+        simple_eval("try factorize(0) catch e -> e"),
+        Obj::from("value error: can only factorize positive integers, got 0")
+    );
+    assert_eq!(
+        simple_eval("try factorize(-12) catch e -> e"),
+        Obj::from("value error: can only factorize positive integers, got -12")
+    );
+}
+
+#[test]
+fn digits() {
+    assert_eq!(
+        simple_eval("list(digits(1234))"),
+        simple_eval("[4, 3, 2, 1]")
+    );
+    assert_eq!(
+        simple_eval("list(digits(255, 16))"),
+        simple_eval("[15, 15]")
+    );
+    assert_eq!(
+        simple_eval("list(digits(1234, 10, true))"),
+        simple_eval("[1, 2, 3, 4]")
+    );
+    assert_eq!(
+        simple_eval("list(digits(6, 2, true))"),
+        simple_eval("[1, 1, 0]")
+    );
+    assert_eq!(simple_eval("list(digits(0))"), simple_eval("[0]"));
+    assert_eq!(simple_eval("len(digits(10 ^ 50))"), i(51));
+    assert_eq!(simple_eval("sum(digits(2 ^ 100))"), i(115));
+    assert_eq!(
+        simple_eval("list(digits((10 ^ 20) + 7, 10 ^ 10, true))"),
+        simple_eval("[1, 0, 7]")
+    );
+    assert_eq!(
+        simple_eval("try digits(10, 1) catch e -> e"),
+        Obj::from("digits: value error: digit base must be at least 2, got 1")
+    );
+    assert_eq!(
+        simple_eval("try digits(-5) catch e -> e"),
+        Obj::from("digits: value error: can't take digits of negative -5")
+    );
+}
+
+#[test]
+fn binomial_row() {
+    assert_eq!(
+        simple_eval("list(binomial_row(4))"),
+        simple_eval("[1, 4, 6, 4, 1]")
+    );
+    assert_eq!(simple_eval("list(binomial_row(0))"), simple_eval("[1]"));
+    assert_eq!(simple_eval("len(binomial_row(10))"), i(11));
+    assert_eq!(
+        simple_eval("sum(binomial_row(100))"),
+        simple_eval("2 ^ 100")
+    );
+    assert_eq!(
+        simple_eval("binomial_row(100)[50]"),
+        simple_eval("100891344545564193334812497256")
+    );
+    assert_eq!(simple_eval("binomial_row(6)[-2]"), i(6));
+    assert_eq!(simple_eval("(binomial_row(6) drop 2)[1]"), i(20));
+    assert_eq!(
+        simple_eval("try binomial_row(3)[4] catch e -> e"),
+        Obj::from("index error: Index out of bounds: 4")
+    );
+}
+
+#[test]
+fn reversed_combinatorics() {
+    assert_eq!(
+        simple_eval("reverse(permutations([1, 2, 3]))[0] == (permutations([1, 2, 3]))[-1]"),
+        Obj::from(true)
+    );
+    assert_eq!(
+        simple_eval("list(reverse(permutations(1 to 4))) == reverse(list(permutations(1 to 4)))"),
+        Obj::from(true)
+    );
+    assert_eq!(
+        simple_eval(
+            "list(reverse(combinations(1 to 5, 3))) == reverse(list(combinations(1 to 5, 3)))"
+        ),
+        Obj::from(true)
+    );
+    assert_eq!(
+        simple_eval("reverse(combinations(1 to 4, 2))[0]"),
+        simple_eval("[3, 4]")
+    );
+    assert_eq!(simple_eval("len(combinations(1 to 10, 4))"), i(210));
+    assert_eq!(
+        simple_eval("(combinations(1 to 10, 4))[-1]"),
+        simple_eval("[7, 8, 9, 10]")
+    );
+    assert_eq!(simple_eval("len(permutations(1 to 5) drop 7)"), i(113));
+    assert_eq!(
+        simple_eval("reverse(permutations(1 to 12))[0]"),
+        simple_eval("list(reverse(1 to 12))")
+    );
+    assert_eq!(simple_eval("len(reverse(combinations(1 to 3, 5)))"), i(0));
+}
+
+#[test]
+fn stream_clones_resume_together() {
+    // drop two elements by unconsing, then read the rest twice and uncons once more
+    let check = |stream: &str, expected: &str| {
+        assert_eq!(
+            simple_eval(&format!(
+                "r := uncons(uncons({})[1])[1]; [list(r take 3), list(r take 3), uncons(r)[0]]",
+                stream
+            )),
+            simple_eval(expected)
+        );
+    };
+    check("lazy_scan(iota(1), 0, +)", "[[3, 6, 10], [3, 6, 10], 3]");
+    check("iota(0) lazy_map (*2)", "[[4, 6, 8], [4, 6, 8], 4]");
+    check("iota(0) step_by 3", "[[6, 9, 12], [6, 9, 12], 6]");
+    check("cache(iota(0) lazy_map (+1))", "[[3, 4, 5], [3, 4, 5], 3]");
+    check("group_by(iota(0), \\x -> x // 2)", "[[[2, [4, 5]], [3, [6, 7]], [4, [8, 9]]], [[2, [4, 5]], [3, [6, 7]], [4, [8, 9]]], [2, [4, 5]]]");
+    check("intersperse(-1, iota(0))", "[[1, -1, 2], [1, -1, 2], 1]");
+    check(
+        "running_max([3, 1, 4, 1, 5, 9, 2, 6])",
+        "[[4, 4, 5], [4, 4, 5], 4]",
+    );
+    check("sliding_max(iota(0), 2)", "[[3, 4, 5], [3, 4, 5], 3]");
+    check("drop_last(1 to 10, 2)", "[[3, 4, 5], [3, 4, 5], 3]");
+    check(
+        "permutations([1, 2, 3])",
+        "[[[2, 1, 3], [2, 3, 1], [3, 1, 2]], [[2, 1, 3], [2, 3, 1], [3, 1, 2]], [2, 1, 3]]",
+    );
+}
+
+#[test]
+fn split_when() {
+    assert_eq!(
+        simple_eval("split_when(\\a, b -> b < a, [1, 2, 3, 1, 2, 5, 4])"),
+        simple_eval("[[1, 2, 3], [1, 2, 5], [4]]")
+    );
+    assert_eq!(
+        simple_eval("split_when(\\a, b -> b < a, [])"),
+        simple_eval("[]")
+    );
+    assert_eq!(simple_eval("[7] split_when !="), simple_eval("[[7]]"));
+    assert_eq!(
+        simple_eval("list(split_when(\\a, b -> b != (a + 1), chain([1, 2, 4, 5, 6, 9])))"),
+        simple_eval("[[1, 2], [4, 5, 6], [9]]")
+    );
+    assert_eq!(
+        simple_eval("split_when(iota(0), \\a, b -> b % 3 == 0) take 2"),
+        simple_eval("[[0, 1, 2], [3, 4, 5]]")
+    );
+    assert_eq!(
+        simple_eval("try split_when(\\a, b -> throw 'no', [1, 2]) catch e -> e"),
+        Obj::from("split_when: no")
+    );
+}
+
+#[test]
+fn to_json() {
+    assert_eq!(simple_eval("to_json(1 to 5)"), Obj::from("[1,2,3,4,5]"));
+    assert_eq!(
+        simple_eval("to_json(['a', 'b\"c'])"),
+        Obj::from("[\"a\",\"b\\\"c\"]")
+    );
+    assert_eq!(
+        simple_eval("to_json([[1, [2.5]], [], null])"),
+        Obj::from("[[1,[2.5]],[],null]")
+    );
+    assert_eq!(
+        simple_eval("to_json((1 to 3) lazy_map (^2))"),
+        Obj::from("[1,4,9]")
+    );
+    assert_eq!(
+        simple_eval("to_json([10 ^ 30])"),
+        Obj::from("[1000000000000000000000000000000]")
+    );
+    assert_eq!(
+        simple_eval("x := [1 to 3, ['s', [-(10 ^ 25)]]]; json_decode(to_json(x)) == [list(1 to 3), ['s', [-(10 ^ 25)]]]"),
+        Obj::from(true)
+    );
+    assert_eq!(
+        simple_eval("try to_json([iota(0)]) catch e -> e"),
+        Obj::from("json_encode(list): value error: json_encode: infinite, will not terminate")
+    );
+    assert_eq!(
+        simple_eval("json_decode('[2.5, 1e300]')"),
+        simple_eval("[2.5, 1e300]")
+    );
+    // how the number gets printed after that is up to serde_json
+    assert_eq!(
+        simple_eval("(try json_decode('[1e400]') catch e -> e) starts_with 'json_decode(str): value error: json decoding failed: number out of range: '"),
+        Obj::from(true)
+    );
+}
+
+#[test]
+fn lazy_cartesian_product() {
+    assert_eq!(
+        simple_eval("list(lazy_cartesian_product([1, 2], 'ab', 3 to 5)) == list(cartesian_product([1, 2], 'ab', 3 to 5))"),
+        Obj::from(true)
+    );
+    assert_eq!(
+        simple_eval("len(lazy_cartesian_product(0 til 1000000, 0 til 1000000))"),
+        simple_eval("10 ^ 12")
+    );
+    assert_eq!(
+        simple_eval("lazy_cartesian_product(0 til 1000000, [1, 2]) take 3"),
+        simple_eval("[[0, 1], [0, 2], [1, 1]]")
+    );
+    assert_eq!(
+        simple_eval("lazy_cartesian_product(0 til 1000000, 0 til 1000000)[-1]"),
+        simple_eval("[999999, 999999]")
+    );
+    assert_eq!(simple_eval("len(lazy_cartesian_product([1, 2], []))"), i(0));
+    assert_eq!(
+        simple_eval("list(lazy_cartesian_product())"),
+        simple_eval("[[]]")
+    );
+    assert_eq!(
+        simple_eval("try lazy_cartesian_product([1], iota(0)) catch e -> e"),
+        Obj::from("lazy_cartesian_product: value error: can't take a lazy product with 0 til ... by 1, length unknown")
+    );
+}
+
+#[test]
+fn cycle_from() {
+    assert_eq!(
+        simple_eval("cycle_from([1, 2, 3], 1) take 4"),
+        simple_eval("[2, 3, 1, 2]")
+    );
+    assert_eq!(
+        simple_eval("cycle_from([1, 2, 3], 7) take 4"),
+        simple_eval("[2, 3, 1, 2]")
+    );
+    assert_eq!(
+        simple_eval("cycle_from([1, 2, 3], -1) take 4"),
+        simple_eval("[3, 1, 2, 3]")
+    );
+    assert_eq!(simple_eval("cycle_from([1, 2, 3], 1)[3]"), i(2));
+    assert_eq!(simple_eval("cycle_from([1, 2, 3], 1)[-1]"), i(1));
+    assert_eq!(
+        simple_eval("reverse(cycle_from([1, 2, 3], 1)) take 4"),
+        simple_eval("[1, 3, 2, 1]")
+    );
+    assert_eq!(
+        simple_eval("try cycle_from([], 1) catch e -> e"),
+        Obj::from("cycle_from: value error: cannot cycle an empty sequence")
+    );
+}
+
+#[test]
+fn advance_by() {
+    assert_eq!(
+        simple_eval("(0 til (10 ^ 30)) drop (10 ^ 12) take 2"),
+        simple_eval("[10 ^ 12, (10 ^ 12) + 1]")
+    );
+    assert_eq!(simple_eval("(iota(5) drop 1000000)[0]"), i(1000005));
+    assert_eq!(
+        simple_eval("list((0 til 10 by 3)[2:])"),
+        simple_eval("[6, 9]")
+    );
+    assert_eq!(
+        simple_eval("list((10 til 0 by (-3)) drop 2)"),
+        simple_eval("[4, 1]")
+    );
+    assert_eq!(simple_eval("list((1 to 5) drop 99)"), simple_eval("[]"));
+    assert_eq!(
+        simple_eval("(cycle([1, 2, 3]) drop 1000000) take 3"),
+        simple_eval("[2, 3, 1]")
+    );
+    assert_eq!(
+        simple_eval("list(([0, 1, 2] ^^ 3) drop 5) == (list([0, 1, 2] ^^ 3) drop 5)"),
+        Obj::from(true)
+    );
+    assert_eq!(simple_eval("list(([0, 1] ^^ 2) drop 4)"), simple_eval("[]"));
+    assert_eq!(
+        simple_eval("list(cartesian_product([1, 2], 'abc') drop 4)"),
+        simple_eval("[[2, 'b'], [2, 'c']]")
+    );
+    assert_eq!(
+        simple_eval("list(binomial_row(6) drop 2)"),
+        simple_eval("[15, 20, 15, 6, 1]")
+    );
+    assert_eq!(
+        simple_eval("list(((1 to 10) lazy_map (*2)) drop 8)"),
+        simple_eval("[18, 20]")
+    );
+    assert_eq!(simple_eval("drop(4, drop(3, iota(0)))[0]"), i(7));
+}
+
+#[test]
+fn break_ends_function_streams() {
+    assert_eq!(
+        simple_eval("list(iota(0) lazy_map (\\x -> (x < 3 or break; x)))"),
+        simple_eval("[0, 1, 2]")
+    );
+    assert_eq!(
+        simple_eval("list(iota(0) lazy_map (\\x -> (x < 3 or break (-1); x)))"),
+        simple_eval("[0, 1, 2, -1]")
+    );
+    assert_eq!(
+        simple_eval("list(lazy_scan(iota(1), 0, \\a, x -> (x < 4 or break; a + x)))"),
+        simple_eval("[0, 1, 3, 6]")
+    );
+    assert_eq!(
+        simple_eval("list(heap_iterate(1, \\x -> (x < 3 or break; [x + 1])))"),
+        simple_eval("[[2], [3]]")
+    );
+    assert_eq!(
+        simple_eval("list(iterate(1, \\x -> (x < 8 or break 99; x * 2)))"),
+        simple_eval("[1, 2, 4, 8, 99]")
+    );
+    assert_eq!(
+        simple_eval("list(group_by(iota(0), \\x -> (x < 5 or break; x // 2)))"),
+        simple_eval("[[0, [0, 1]], [1, [2, 3]], [2, [4]]]")
+    );
+    assert_eq!(
+        simple_eval("list(split_when(iota(0), \\a, b -> (b < 5 or break; b % 2 == 0)))"),
+        simple_eval("[[0, 1], [2, 3], [4]]")
+    );
+    assert_eq!(
+        simple_eval("try list(iota(0) lazy_map (\\x -> (x < 3 or throw 'bad'; x))) catch e -> e"),
+        Obj::from("bad")
+    );
+}
+
+#[test]
+fn break_with_value() {
+    assert_eq!(
+        simple_eval("list(iota(0) lazy_map (\\x -> (x < 3 or break 99; x)))"),
+        simple_eval("[0, 1, 2, 99]")
+    );
+    assert_eq!(
+        simple_eval("[1, 2, 3, 4] take (\\x -> (x < 3 or break 99; true))"),
+        simple_eval("[1, 2, 99]")
+    );
+    assert_eq!(
+        simple_eval("iota(1) take (\\x -> (x < 3 or break 99; true))"),
+        simple_eval("[1, 2, 99]")
+    );
+    assert_eq!(
+        simple_eval("iota(1) take (\\x -> (x < 3 or break; true))"),
+        simple_eval("[1, 2]")
+    );
+    assert_eq!(
+        simple_eval("\"abcd\" take (\\c -> (c != 'c' or break \"!\"; true))"),
+        Obj::from("ab!")
+    );
+    assert_eq!(
+        simple_eval("[1, 2, 3, 4] drop (\\x -> (x < 3 or break 99; true))"),
+        simple_eval("[99]")
+    );
+    assert_eq!(
+        simple_eval("[1, 2, 3, 4] drop (\\x -> (x < 3 or break; true))"),
+        simple_eval("[]")
+    );
+    assert_eq!(
+        simple_eval("list(iota(1) drop (\\x -> x < 3) take 3)"),
+        simple_eval("[3, 4, 5]")
+    );
+    assert_eq!(
+        simple_eval("iota(1) drop (\\x -> (x < 3 or break 99; true))"),
+        simple_eval("[99]")
+    );
+}
+
+#[test]
+fn any_all_short_circuit() {
+    assert_eq!(simple_eval("any(\\x -> x > 100, iota(1))"), Obj::from(true));
+    assert_eq!(simple_eval("iota(1) any (\\x -> x > 100)"), Obj::from(true));
+    assert_eq!(
+        simple_eval("all(\\x -> x < 3, [1, 2, 5])"),
+        Obj::from(false)
+    );
+    assert_eq!(
+        simple_eval("c := 0; all(\\x -> (c += 1; x < 3), [1, 2, 5, 7, 9]); c"),
+        Obj::from(3usize)
+    );
+    assert_eq!(
+        simple_eval("c := 0; any(\\x -> (c += 1; x > 1), [1, 2, 5, 7, 9]); c"),
+        Obj::from(2usize)
+    );
+    assert_eq!(simple_eval("all(\\x -> x < 3, iota(1))"), Obj::from(false));
+    assert_eq!(simple_eval("any(\\x -> x > 3, [1, 2])"), Obj::from(false));
+    assert_eq!(
+        simple_eval("try any(\\x -> (x < 3 or throw 'bad'; false), iota(1)) catch e -> e"),
+        Obj::from("bad")
+    );
+}
+
+#[test]
+fn find_and_find_map() {
+    assert_eq!(
+        simple_eval("find(\\x -> x % 7 == 0, iota(1))"),
+        Obj::from(7usize)
+    );
+    assert_eq!(
+        simple_eval("iota(1) find (\\x -> x % 7 == 0)"),
+        Obj::from(7usize)
+    );
+    assert_eq!(simple_eval("find?(\\x -> x > 5, 1 to 3)"), Obj::Null);
+    assert_eq!(
+        simple_eval("try find(\\x -> x > 5, 1 to 3) catch e -> 'not found'"),
+        Obj::from("not found")
+    );
+    assert_eq!(
+        simple_eval("find?(\\x -> (x < 3 or break; false), iota(1))"),
+        Obj::Null
+    );
+    assert_eq!(
+        simple_eval("find?(\\x -> (x < 3 or break 99; false), iota(1))"),
+        Obj::from(99usize)
+    );
+    assert_eq!(
+        simple_eval("d := {6: 60, 4: 40}; find_map(\\x -> d !? x, iota(1))"),
+        Obj::from(40usize)
+    );
+    assert_eq!(simple_eval("[1, 2, 3] find_map (\\x -> null)"), Obj::Null);
+}
+
+#[test]
+fn intersperse_with() {
+    assert_eq!(
+        simple_eval("intersperse_with(\\a, b -> b - a, [1, 3, 6])"),
+        simple_eval("[1, 2, 3, 3, 6]")
+    );
+    assert_eq!(
+        simple_eval("[1, 3, 6] intersperse_with (\\a, b -> b - a)"),
+        simple_eval("[1, 2, 3, 3, 6]")
+    );
+    assert_eq!(
+        simple_eval("intersperse_with(\\a, b -> 0, [5])"),
+        simple_eval("[5]")
+    );
+    assert_eq!(
+        simple_eval("intersperse_with(\\a, b -> 0, [])"),
+        simple_eval("[]")
+    );
+    assert_eq!(
+        simple_eval("len(intersperse_with(\\a, b -> 0, 1 to 4 by 1))"),
+        Obj::from(7usize)
+    );
+    assert_eq!(
+        simple_eval("iota(1) intersperse_with (\\a, b -> a * b) take 5"),
+        simple_eval("[1, 2, 2, 6, 3]")
+    );
+    assert_eq!(
+        simple_eval("list(intersperse_with(\\a, b -> (b < 3 or break 0; a + b), iota(1)))"),
+        simple_eval("[1, 3, 2, 0]")
+    );
+    assert_eq!(
+        simple_eval("try intersperse_with(\\a, b -> throw 'bad', [1, 2]) catch e -> e"),
+        Obj::from("intersperse_with: bad")
+    );
+}
+
+#[test]
+fn permutations_by_comparator() {
+    assert_eq!(
+        simple_eval("list(permutations([1, 2, 3], \\a, b -> b - a))"),
+        simple_eval("[[3, 2, 1], [3, 1, 2], [2, 3, 1], [2, 1, 3], [1, 3, 2], [1, 2, 3]]")
+    );
+    assert_eq!(
+        simple_eval("list(permutations([2, 3, 1], \\a, b -> a - b))"),
+        simple_eval("list(permutations([1, 2, 3]))")
+    );
+    assert_eq!(
+        simple_eval("list(permutations([1, 2, 1], \\a, b -> a - b))"),
+        simple_eval("[[1, 1, 2], [1, 2, 1], [2, 1, 1]]")
+    );
+    assert_eq!(
+        simple_eval("list(permutations([], \\a, b -> a - b))"),
+        simple_eval("[[]]")
+    );
+    assert_eq!(
+        simple_eval("try list(permutations([1, 2], \\a, b -> throw 'bad')) catch e -> e"),
+        Obj::from("permutations: bad")
+    );
+}
+
+#[test]
+fn flatten_streams() {
+    assert_eq!(
+        simple_eval("flatten([1, 2, 3] map (\\n -> 0 til n))"),
+        simple_eval("[0, 0, 1, 0, 1, 2]")
+    );
+    assert_eq!(
+        simple_eval("flatten([[1, 2], 0 til 2, 'ab'])"),
+        simple_eval("[1, 2, 0, 1, 'a', 'b']")
+    );
+    assert_eq!(
+        simple_eval("flatten(iota(1) lazy_map (\\n -> 0 til n)) take 6"),
+        simple_eval("[0, 0, 1, 0, 1, 2]")
+    );
+    assert_eq!(
+        simple_eval("flatten(chain([[1], iota(5)])) take 4"),
+        simple_eval("[1, 5, 6, 7]")
+    );
+    assert_eq!(
+        simple_eval("list(flatten(chain([[1, 2], [], 3 til 5])))"),
+        simple_eval("[1, 2, 3, 4]")
+    );
+    assert_eq!(
+        simple_eval("len(flatten(chain([[1, 2], 3 til 5])))"),
+        Obj::from(4usize)
+    );
+    // asking for the length never makes the inner sequences
+    assert_eq!(
+        simple_eval(
+            "c := 0; s := flatten((1 to 3) lazy_map (\\n -> (c += 1; 0 til n))); [remaining(s), c]"
+        ),
+        simple_eval("[null, 0]")
+    );
+}
+
+#[test]
+fn split_into() {
+    assert_eq!(
+        simple_eval("split_into(3, 0 til 10)"),
+        simple_eval("[[0, 1, 2, 3], [4, 5, 6], [7, 8, 9]]")
+    );
+    assert_eq!(
+        simple_eval("[1, 2, 3, 4] split_into 2"),
+        simple_eval("[[1, 2], [3, 4]]")
+    );
+    assert_eq!(
+        simple_eval("split_into(4, [1, 2])"),
+        simple_eval("[[1], [2], [], []]")
+    );
+    assert_eq!(
+        simple_eval("split_into(2, 'abcde')"),
+        simple_eval("['abc', 'de']")
+    );
+    assert_eq!(
+        simple_eval("split_into(2, chain([1, 2, 3]))"),
+        simple_eval("[[1, 2], [3]]")
+    );
+    assert_eq!(
+        simple_eval("try split_into(0, [1, 2]) catch e -> 'error'"),
+        Obj::from("error")
+    );
+    assert_eq!(
+        simple_eval("try split_into(2, iota(0)) catch e -> 'error'"),
+        Obj::from("error")
+    );
+}
+
+#[test]
+fn min_max_by_key() {
+    assert_eq!(
+        simple_eval("max_by_key(len, ['ab', 'abcd', 'abc', 'wxyz'])"),
+        Obj::from("abcd")
+    );
+    assert_eq!(
+        simple_eval("['ab', 'abcd', 'abc', 'wxyz'] min_by_key len"),
+        Obj::from("ab")
+    );
+    assert_eq!(
+        simple_eval("min_by_key(len, ['xy', 'abcd', 'ab'])"),
+        Obj::from("xy")
+    );
+    assert_eq!(
+        simple_eval("max_by_key(\\x -> x % 5, 1 til 10)"),
+        Obj::from(4usize)
+    );
+    assert_eq!(
+        simple_eval("try max_by_key(len, []) catch e -> 'error'"),
+        Obj::from("error")
+    );
+    assert_eq!(
+        simple_eval("try max_by_key(\\x -> x, iota(0)) catch e -> 'error'"),
+        Obj::from("error")
+    );
+}
+
+#[test]
+fn total_order_sorting() {
+    assert_eq!(
+        simple_eval("sort([3, 1.5, 2, 1, 0.5])"),
+        simple_eval("[0.5, 1, 1.5, 2, 3]")
+    );
+    // plain sort still refuses values it can't compare; total_sort orders them anyway
+    assert!(
+        simple_eval("try sort(['b', 2, null, [1], 'a', 1]) catch e -> 'error'")
+            == Obj::from("error")
+    );
+    assert_eq!(
+        simple_eval("total_sort(['b', 2, null, [1], 'a', 1])"),
+        simple_eval("[null, 1, 2, [1], 'a', 'b']")
+    );
+    assert_eq!(
+        simple_eval("total_sort([[2, 'x'], [1], [2, 1], []])"),
+        simple_eval("[[], [1], [2, 1], [2, 'x']]")
+    );
+    assert_eq!(
+        simple_eval("total_sort(chain([3, 1, 2]))"),
+        simple_eval("[1, 2, 3]")
+    );
+    assert_eq!(
+        simple_eval("sort(chain([3, 1, 2]))"),
+        simple_eval("[1, 2, 3]")
+    );
+    assert_eq!(
+        simple_eval("try sort(iota(0)) catch e -> 'error'"),
+        Obj::from("error")
+    );
+    assert_eq!(
+        simple_eval("sort_by(\\a, b -> b - a, [1, 3, 2])"),
+        simple_eval("[3, 2, 1]")
+    );
+    assert_eq!(
+        simple_eval("[1, 3, 2] sort_by (\\a, b -> b - a)"),
+        simple_eval("[3, 2, 1]")
+    );
+    // stable: equal keys keep their order
+    assert_eq!(
+        simple_eval("sort_by_key(len, ['bb', 'a', 'cc', 'd', 'eee'])"),
+        simple_eval("['a', 'd', 'bb', 'cc', 'eee']")
+    );
+    assert_eq!(
+        simple_eval("'hello' sort_by_key (\\c -> 0 - ord(c))"),
+        Obj::from("ollhe")
+    );
+    assert_eq!(
+        simple_eval("list(heap_iterate(1, \\x -> (x < 3 or break; [x - 5, x + 1, 0.5])))"),
+        simple_eval("[[-4, 2, 0.5], [-3, 3, 0.5]]")
+    );
+}
+
+#[test]
+fn window_reduce() {
+    assert_eq!(
+        simple_eval("window_reduce(2, \\w -> w[0] + w[1], [1, 2, 3, 4])"),
+        simple_eval("[3, 5, 7]")
+    );
+    assert_eq!(
+        simple_eval("list(window_reduce(3, sum, 1 to 5))"),
+        simple_eval("[6, 9, 12]")
+    );
+    assert_eq!(
+        simple_eval("window_reduce(5, sum, [1, 2])"),
+        simple_eval("[]")
+    );
+    assert_eq!(
+        simple_eval("window_reduce(2, sum, iota(1)) take 3"),
+        simple_eval("[3, 5, 7]")
+    );
+    assert_eq!(
+        simple_eval("len(window_reduce(3, sum, 1 to 10 by 1))"),
+        i(8)
+    );
+    assert_eq!(simple_eval("len(window_reduce(3, sum, 1 to 2 by 1))"), i(0));
+    assert_eq!(
+        simple_eval("w := window_reduce(3, sum, 1 to 10 by 1); w take 2; len(w drop 2)"),
+        i(6)
+    );
+    assert_eq!(
+        simple_eval("try window_reduce(0, sum, [1]) catch e -> 'error'"),
+        Obj::from("error")
+    );
+}
+
+#[test]
+fn sorted_set_ops() {
+    assert_eq!(
+        simple_eval("list([1, 2, 3, 4] intersect_sorted [2, 4, 6])"),
+        simple_eval("[2, 4]")
+    );
+    assert_eq!(
+        simple_eval("list([1, 2, 3, 4] diff_sorted [2, 4, 6])"),
+        simple_eval("[1, 3]")
+    );
+    assert_eq!(
+        simple_eval("list(primes intersect_sorted [1, 2, 4, 7, 9, 11])"),
+        simple_eval("[2, 7, 11]")
+    );
+    assert_eq!(
+        simple_eval("list([4, 5, 6, 7, 8, 9, 10] intersect_sorted primes)"),
+        simple_eval("[5, 7]")
+    );
+    assert_eq!(
+        simple_eval("primes diff_sorted [3, 5] take 4"),
+        simple_eval("[2, 7, 11, 13]")
+    );
+    assert_eq!(
+        simple_eval("list([1, 2, 2, 2, 3] intersect_sorted [2, 2])"),
+        simple_eval("[2, 2]")
+    );
+    assert_eq!(
+        simple_eval("list([1, 2, 2, 2, 3] diff_sorted [2, 2])"),
+        simple_eval("[1, 2, 3]")
+    );
+    assert_eq!(
+        simple_eval("is_infinite(primes diff_sorted [3, 5])"),
+        Obj::from(true)
+    );
+    assert_eq!(
+        simple_eval("is_infinite(primes intersect_sorted [3, 5])"),
+        Obj::from(false)
+    );
+}
+
+#[test]
+fn repeat_n() {
+    assert_eq!(simple_eval("list(repeat(0, 3))"), simple_eval("[0, 0, 0]"));
+    assert_eq!(simple_eval("len(repeat('a', 5))"), i(5));
+    assert_eq!(simple_eval("list(repeat(0, 0))"), simple_eval("[]"));
+    assert_eq!(simple_eval("repeat(7, 4)[-4]"), i(7));
+    assert_eq!(
+        simple_eval("try repeat(7, 4)[4] catch e -> 'error'"),
+        Obj::from("error")
+    );
+    assert_eq!(
+        simple_eval("try repeat(7, 4)[-5] catch e -> 'error'"),
+        Obj::from("error")
+    );
+    assert_eq!(
+        simple_eval("list(repeat(1, 10)[2:5])"),
+        simple_eval("[1, 1, 1]")
+    );
+    assert_eq!(simple_eval("len(repeat(1, 10)[-3:])"), i(3));
+    assert_eq!(simple_eval("len(repeat(1, 10)[8:2])"), i(0));
+    assert_eq!(
+        simple_eval("len(repeat(1, 10 ^ 15)[5:])"),
+        simple_eval("(10 ^ 15) - 5")
+    );
+    assert_eq!(simple_eval("repeat(2) take 3"), simple_eval("[2, 2, 2]"));
+}
+
+#[test]
+fn indices_where() {
+    assert_eq!(
+        simple_eval("indices_where(\\x -> x % 2 == 0, [10, 11, 12, 13, 14])"),
+        simple_eval("[0, 2, 4]")
+    );
+    assert_eq!(
+        simple_eval("'banana' indices_where (== 'a')"),
+        simple_eval("[1, 3, 5]")
+    );
+    assert_eq!(
+        simple_eval("indices_where(\\x -> x % 3 == 0, iota(1)) take 3"),
+        simple_eval("[2, 5, 8]")
+    );
+    assert_eq!(
+        simple_eval("list(indices_where(\\x -> x > 1, chain([1, 2, 3])))"),
+        simple_eval("[1, 2]")
+    );
+    assert_eq!(
+        simple_eval("try indices_where(\\x -> throw 'bad', [1]) catch e -> e"),
+        Obj::from("indices_where: bad")
+    );
+}
+
+#[test]
+fn dedup_by() {
+    assert_eq!(
+        simple_eval("dedup_by(\\a, b -> (a % 10) == (b % 10), [1, 11, 21, 2, 12])"),
+        simple_eval("[1, 2]")
+    );
+    assert_eq!(
+        simple_eval("['apple', 'avocado', 'banana', 'blueberry', 'apricot'] dedup_by (\\a, b -> a[0] == b[0])"),
+        simple_eval("['apple', 'banana', 'apricot']")
+    );
+    assert_eq!(
+        simple_eval("dedup_by(\\a, b -> b - a < 3, iota(0)) take 3"),
+        simple_eval("[0, 3, 6]")
+    );
+    assert_eq!(
+        simple_eval("dedup_by(\\a, b -> true, [])"),
+        simple_eval("[]")
+    );
+    assert_eq!(
+        simple_eval("try dedup_by(\\a, b -> throw 'bad', [1, 2]) catch e -> e"),
+        Obj::from("dedup_by: bad")
+    );
+}
+
+#[test]
+fn join_streams() {
+    assert_eq!(
+        simple_eval("join(', ', (0 til 4) map str)"),
+        Obj::from("0, 1, 2, 3")
+    );
+    assert_eq!(simple_eval("join(', ', 0 til 4)"), Obj::from("0, 1, 2, 3"));
+    assert_eq!(simple_eval("chain(['a', 'b']) join '-'"), Obj::from("a-b"));
+    assert_eq!(simple_eval("'abc' join ','"), Obj::from("a,b,c"));
+    assert_eq!(
+        simple_eval("try iota(0) join ',' catch e -> 'error'"),
+        Obj::from("error")
+    );
+    assert_eq!(
+        simple_eval("try [1, \\x -> x] join ',' catch e -> 'error'"),
+        Obj::from("error")
+    );
+}
+
+#[test]
+fn element_budget() {
+    assert_eq!(
+        simple_eval("set_element_budget(1000); try list(iterate(1, \\x -> x + 1)) catch e -> e"),
+        Obj::from("value error: exceeded element budget of 1000")
+    );
+    assert_eq!(
+        simple_eval("n := 0; set_element_budget(1000); try sum(iterate(1, \\x -> (n += 1; x + 1))) catch e -> n"),
+        i(1000)
+    );
+    assert_eq!(
+        simple_eval("set_element_budget(1000); s := list(iterate(1, \\x -> x + 1) take 1000); set_element_budget(null); len(s)"),
+        i(1000)
+    );
+    assert_eq!(
+        simple_eval("set_element_budget(10); set_element_budget(null); len(list(iterate(1, \\x -> x + 1) take (\\x -> x < 100)))"),
+        i(99)
+    );
+    // each interpreter has its own, and it's gone when the evaluation ends
+    simple_eval("set_element_budget(10)");
+    assert_eq!(
+        simple_eval("len(list(iterate(1, \\x -> x + 1) take 100))"),
+        i(100)
+    );
+}
+
+#[test]
+fn reverse_chain() {
+    assert_eq!(
+        simple_eval("list(reverse(chain(0 til 3, [10, 11])))"),
+        simple_eval("[11, 10, 2, 1, 0]")
+    );
+    assert_eq!(
+        simple_eval("reverse(chain(0 til (10 ^ 18), [7])) take 3"),
+        simple_eval("[7, (10 ^ 18) - 1, (10 ^ 18) - 2]")
+    );
+    assert_eq!(
+        simple_eval("list(reverse(chain(10 til 0 by (0 - 3), 0 til 0)))"),
+        simple_eval("[1, 4, 7, 10]")
+    );
+    assert_eq!(
+        simple_eval("try reverse(chain([1], iota(0))) catch e -> 'error'"),
+        Obj::from("error")
+    );
+}
+
+#[test]
+fn chunk_by_size() {
+    assert_eq!(
+        simple_eval("chunk_by_size(4, ['ab', 'cd', 'efgh'])"),
+        simple_eval("[['ab', 'cd'], ['efgh']]")
+    );
+    assert_eq!(
+        simple_eval("['abcdef', 'a', 'b', 'cde'] chunk_by_size 3"),
+        simple_eval("[['abcdef'], ['a', 'b'], ['cde']]")
+    );
+    // counts chars, not bytes
+    assert_eq!(
+        simple_eval("chunk_by_size(2, ['éé', 'ü'])"),
+        simple_eval("[['éé'], ['ü']]")
+    );
+    assert_eq!(
+        simple_eval("chunk_by_size(5, iota(8) lazy_map str) take 3"),
+        simple_eval("[['8', '9', '10'], ['11', '12'], ['13', '14']]")
+    );
+    assert_eq!(simple_eval("chunk_by_size(4, [])"), simple_eval("[]"));
+    assert_eq!(
+        simple_eval("try chunk_by_size(4, [1, 2]) catch e -> 'error'"),
+        Obj::from("error")
+    );
+}
+
+#[test]
+fn transpose_rows() {
+    assert_eq!(
+        simple_eval("transpose([[1, 2, 3], [4, 5, 6]])"),
+        simple_eval("[[1, 4], [2, 5], [3, 6]]")
+    );
+    assert_eq!(
+        simple_eval("transpose(['ab', 'cd'])"),
+        simple_eval("[['a', 'c'], ['b', 'd']]")
+    );
+    assert_eq!(
+        simple_eval("transpose((1 to 2) lazy_map (\\x -> [x, x * 10]))"),
+        simple_eval("[[1, 2], [10, 20]]")
+    );
+    assert_eq!(simple_eval("transpose([])"), simple_eval("[]"));
+    assert_eq!(
+        simple_eval("try transpose([[1, 2], [3]]) catch e -> e"),
+        Obj::from("transpose: value error: row 1 has length 1, but row 0 has length 2")
+    );
+}
+
+#[test]
+fn scan1() {
+    assert_eq!(
+        simple_eval("scan1(\\a, x -> a + x, [1, 2, 3, 4])"),
+        simple_eval("[1, 3, 6, 10]")
+    );
+    assert_eq!(
+        simple_eval("[3, 1, 4, 1, 5] scan1 max"),
+        simple_eval("[3, 3, 4, 4, 5]")
+    );
+    assert_eq!(
+        simple_eval("scan1(*, iota(1)) take 5"),
+        simple_eval("[1, 2, 6, 24, 120]")
+    );
+    assert_eq!(simple_eval("scan1(+, [])"), simple_eval("[]"));
+    assert_eq!(
+        simple_eval("list(scan1(+, iota(1) take 0))"),
+        simple_eval("[]")
+    );
+}
+
+#[test]
+fn cycle_n() {
+    assert_eq!(
+        simple_eval("list(cycle([1, 2], 3))"),
+        simple_eval("[1, 2, 1, 2, 1, 2]")
+    );
+    assert_eq!(simple_eval("cycle([1, 2], 3)[4]"), i(1));
+    assert_eq!(simple_eval("cycle([1, 2], 3)[-1]"), i(2));
+    assert_eq!(simple_eval("len(cycle('abc', 4))"), i(12));
+    assert_eq!(
+        simple_eval("list(cycle([1, 2, 3], 2)[2:5])"),
+        simple_eval("[3, 1, 2]")
+    );
+    assert_eq!(
+        simple_eval("list(reverse(cycle([1, 2, 3], 2)[:5]))"),
+        simple_eval("[2, 1, 3, 2, 1]")
+    );
+    assert_eq!(simple_eval("list(cycle([1, 2], 0))"), simple_eval("[]"));
+    assert_eq!(simple_eval("list(cycle([], 5))"), simple_eval("[]"));
+    assert_eq!(
+        simple_eval("list(cycle([1, 2], 10 ^ 15) drop ((10 ^ 15) - 1) take 3)"),
+        simple_eval("[2, 1, 2]")
+    );
+}
+
+#[test]
+fn step_until() {
+    // inclusive: the element that satisfies f is the last one yielded
+    assert_eq!(
+        simple_eval("step_until(\\x -> x > 6, 2, 0 til 100)"),
+        simple_eval("[0, 2, 4, 6, 8]")
+    );
+    assert_eq!(
+        simple_eval("step_until(\\x -> x > 6, 3, [0, 1, 2, 3, 4, 5, 6, 7, 8])"),
+        simple_eval("[0, 3, 6]")
+    );
+    assert_eq!(
+        simple_eval("step_until(\\x -> x >= 20, 5, iota(0))"),
+        simple_eval("[0, 5, 10, 15, 20]")
+    );
+    assert_eq!(
+        simple_eval("step_until(\\x -> false, 2, [])"),
+        simple_eval("[]")
+    );
+}
+
+#[test]
+fn remaining() {
+    assert_eq!(
+        simple_eval("c := combinations([1, 2, 3, 4], 2); r := []; for (_ <- 1 to 3) (r append= remaining(c); c = uncons(c)[1]); r append= remaining(c); r"),
+        simple_eval("[6, 5, 4, 3]")
+    );
+    assert_eq!(
+        simple_eval("remaining(permutations([1, 2, 3]) drop 2)"),
+        i(4)
+    );
+    assert_eq!(
+        simple_eval("remaining(subsequences([1, 2, 3]) drop 5)"),
+        i(3)
+    );
+    assert_eq!(simple_eval("remaining(([1, 2] ^^ 3) drop 1)"), i(7));
+    assert_eq!(simple_eval("remaining(iota(0))"), Obj::Null);
+    assert_eq!(
+        simple_eval("remaining(iterate(1, \\x -> x + 1))"),
+        Obj::Null
+    );
+    assert_eq!(simple_eval("remaining([1, 2])"), i(2));
+    assert_eq!(simple_eval("remaining(dedup(iota(0)))"), Obj::Null);
+    // the function could still throw
+    assert_eq!(simple_eval("remaining((1 to 3) lazy_map (+1))"), Obj::Null);
+    // combinatorial streams count down as they go rather than recounting from where they are
+    assert_eq!(
+        simple_eval("remaining(drop(1, permutations([1, 2, 3], 2)))"),
+        i(5)
+    );
+    assert_eq!(
+        simple_eval("remaining(drop(2, combinations_with_replacement([1, 2, 3], 2)))"),
+        i(4)
+    );
+    assert_eq!(simple_eval("remaining(drop(3, compositions(5)))"), i(13));
+    assert_eq!(
+        simple_eval("remaining(drop(1, cartesian_product([1, 2], [3, 4, 5])))"),
+        i(5)
+    );
+    assert_eq!(
+        simple_eval("try remaining(subsequences(1 to 70)) catch e -> e"),
+        Obj::from("remaining(stream): value error: too many elements to count")
+    );
+}
+
+#[test]
+fn de_bruijn() {
+    assert_eq!(
+        simple_eval("list(de_bruijn(2, 3))"),
+        simple_eval("[0, 0, 0, 1, 0, 1, 1, 1]")
+    );
+    assert_eq!(simple_eval("len(de_bruijn(2, 3))"), i(8));
+    // every 3-bit window appears exactly once, cyclically
+    assert_eq!(
+        simple_eval("s := list(de_bruijn(2, 3)); sort((0 til 8) map (\\i -> (s ++ s)[i:i + 3]))"),
+        simple_eval("list([0, 1] ^^ 3)")
+    );
+    assert_eq!(
+        simple_eval("list(de_bruijn(3, 2))"),
+        simple_eval("[0, 0, 1, 0, 2, 1, 1, 2, 2]")
+    );
+    assert_eq!(simple_eval("len(list(de_bruijn(4, 4)))"), i(256));
+    assert_eq!(simple_eval("list(de_bruijn(1, 5))"), simple_eval("[0]"));
+    assert_eq!(simple_eval("list(de_bruijn(0, 2))"), simple_eval("[]"));
+    assert_eq!(simple_eval("remaining(de_bruijn(2, 3) drop 3)"), i(5));
+}
+
+#[test]
+fn set_partitions() {
+    assert_eq!(
+        simple_eval("list(set_partitions([1, 2, 3]))"),
+        simple_eval("[[[1, 2, 3]], [[1, 2], [3]], [[1, 3], [2]], [[1], [2, 3]], [[1], [2], [3]]]")
+    );
+    assert_eq!(
+        simple_eval("(1 to 8) map (\\n -> len(set_partitions(1 to n)))"),
+        simple_eval("[1, 2, 5, 15, 52, 203, 877, 4140]")
+    );
+    assert_eq!(
+        simple_eval("(0 til 15) map (\\k -> len(set_partitions(1 to 5) drop k))"),
+        simple_eval("list(52 to 38 by (0 - 1))")
+    );
+    assert_eq!(
+        simple_eval("len(list(set_partitions(1 to 6) drop 100))"),
+        i(103)
+    );
+    assert_eq!(simple_eval("list(set_partitions([]))"), simple_eval("[[]]"));
+    assert_eq!(
+        simple_eval("set_partitions('ab') map (\\p -> p map (\\b -> join('', b)))"),
+        simple_eval("[['ab'], ['a', 'b']]")
+    );
+    assert_eq!(
+        simple_eval("len(set_partitions(1 to 25)) == 4638590332229999353"),
+        i(1)
+    );
+    assert_eq!(
+        simple_eval("try len(set_partitions(0 til 30)) catch e -> e"),
+        Obj::from("len(stream): value error: too many elements to count")
+    );
+    assert_eq!(
+        simple_eval("try remaining(partitions(1000)) catch e -> e"),
+        Obj::from("remaining(stream): value error: too many elements to count")
+    );
+}
+
+#[test]
+fn cycle_detect() {
+    // 0 -> 1 -> 2 -> 3 -> 4 -> 2: two steps in, then a cycle of three
+    assert_eq!(
+        simple_eval("cycle_detect(\\x -> [1, 2, 3, 4, 2][x], 0)"),
+        simple_eval("[2, 3]")
+    );
+    assert_eq!(
+        simple_eval("cycle_detect(\\x -> (x * x + 1) % 255, 3)"),
+        simple_eval("[2, 6]")
+    );
+    assert_eq!(
+        simple_eval("cycle_detect(\\x -> x, 'a')"),
+        simple_eval("[0, 1]")
+    );
+    assert_eq!(
+        simple_eval("cycle_detect(\\x -> (x + 1) % 10, 0)"),
+        simple_eval("[0, 10]")
+    );
+    assert_eq!(
+        simple_eval("try cycle_detect(\\x -> x + 1, 0, 1000) catch e -> e"),
+        Obj::from("cycle_detect: value error: no cycle found within 1000 steps")
+    );
+}
+
+#[test]
+fn enumerate_step() {
+    assert_eq!(
+        simple_eval("enumerate(['a', 'b', 'c'], 10, 5)"),
+        simple_eval("[[10, 'a'], [15, 'b'], [20, 'c']]")
+    );
+    assert_eq!(
+        simple_eval("enumerate('ab')"),
+        simple_eval("[[0, 'a'], [1, 'b']]")
+    );
+    assert_eq!(
+        simple_eval("enumerate('ab', 1)"),
+        simple_eval("[[1, 'a'], [2, 'b']]")
+    );
+    assert_eq!(
+        simple_eval("enumerate([1, 2], 0, 0 - 2)"),
+        simple_eval("[[0, 1], [-2, 2]]")
+    );
+    assert_eq!(
+        simple_eval("enumerate(iota(7), 10 ^ 20, 2) drop 1 take 2"),
+        simple_eval("[[(10 ^ 20) + 2, 8], [(10 ^ 20) + 4, 9]]")
+    );
+    assert_eq!(simple_eval("len(enumerate(0 til 5, 1, 3))"), i(5));
+}
+
+#[test]
+fn zip_eq() {
+    assert_eq!(
+        simple_eval("zip_eq([1, 2], [3, 4])"),
+        simple_eval("[[1, 3], [2, 4]]")
+    );
+    assert_eq!(
+        simple_eval("zip_eq([1, 2], [3, 4], +)"),
+        simple_eval("[4, 6]")
+    );
+    assert_eq!(
+        simple_eval("[1, 2] zip_eq 'ab'"),
+        simple_eval("[[1, 'a'], [2, 'b']]")
+    );
+    assert_eq!(
+        simple_eval("try zip_eq([1, 2], [3]) catch e -> e"),
+        Obj::from("value error: zip_eq: argument 0 has 2 elements, but argument 1 has 1")
+    );
+    // lengths only discovered by running out
+    assert_eq!(
+        simple_eval("zip_eq([1, 2, 3], iterate(1, \\x -> (x < 3 or break; x + 1)))"),
+        simple_eval("[[1, 1], [2, 2], [3, 3]]")
+    );
+    assert_eq!(
+        simple_eval(
+            "try zip_eq(iterate(1, \\x -> (x < 2 or break; x + 1)), [1, 2, 3]) catch e -> e"
+        ),
+        Obj::from(
+            "value error: zip_eq: argument 0 ended after 2 elements, but argument 1 has more"
+        )
+    );
+    assert_eq!(
+        simple_eval("try zip_eq([1], iota(0)) catch e -> 'error'"),
+        Obj::from("error")
+    );
+    assert_eq!(
+        simple_eval("try zip_eq([1], dedup(iota(0))) catch e -> e"),
+        Obj::from(
+            "value error: zip_eq: argument 0 ended after 1 elements, but argument 1 has more"
+        )
+    );
+    assert_eq!(
+        simple_eval("zip_eq(dedup([1, 1, 2]), 'ab')"),
+        simple_eval("[[1, 'a'], [2, 'b']]")
+    );
+}
+
+#[test]
+fn to_dict() {
+    assert_eq!(
+        simple_eval("to_dict([['a', 1], ['b', 2]])"),
+        simple_eval("{'a': 1, 'b': 2}")
+    );
+    assert_eq!(
+        simple_eval("to_dict([['a', 1], ['a', 2]])"),
+        simple_eval("{'a': 2}")
+    );
+    assert_eq!(
+        simple_eval("to_dict(items({1: 2, 3: 4}))"),
+        simple_eval("{1: 2, 3: 4}")
+    );
+    assert_eq!(
+        simple_eval("to_dict((1 to 3) lazy_map (\\x -> [x, x * x]))"),
+        simple_eval("{1: 1, 2: 4, 3: 9}")
+    );
+    assert_eq!(
+        simple_eval("try to_dict([['a', 1], 'b']) catch e -> e"),
+        Obj::from("to_dict: type error: element 1 is not a [key, value] pair: \"b\"")
+    );
+    assert_eq!(
+        simple_eval("try to_dict([[1, 2, 3]]) catch e -> 'error'"),
+        Obj::from("error")
+    );
+    assert_eq!(
+        simple_eval("try to_dict([['a', 1], ['a', 2]], true) catch e -> e"),
+        Obj::from("to_dict: value error: duplicate key at element 1: \"a\"")
+    );
+}
+
+#[test]
+fn pforce() {
+    assert_eq!(
+        simple_eval("s := (0 til 1000) lazy_map (\\x -> x * x % 7); pforce(s, 4) == list(s)"),
+        Obj::from(true)
+    );
+    assert_eq!(
+        simple_eval("c := 0; s := (0 til 10) lazy_map (\\x -> (c += 1; x)); pforce(s, 2); c"),
+        i(10)
+    );
+    assert_eq!(simple_eval("pforce([1, 2], 2)"), simple_eval("[1, 2]"));
+    assert_eq!(
+        simple_eval("try pforce(0 til 3, 0) catch e -> 'error'"),
+        Obj::from("error")
+    );
+    assert_eq!(
+        simple_eval("try pforce(iota(0), 2) catch e -> 'error'"),
+        Obj::from("error")
+    );
+}
+
+#[test]
+fn tee() {
+    assert_eq!(
+        simple_eval("n := 0; s := (1 to 5) lazy_map (\\x -> (n += 1; x * 10)); [a, b] := tee(s, 2); [a take 2, list(b), list(a), n]"),
+        simple_eval("[[10, 20], [10, 20, 30, 40, 50], [10, 20, 30, 40, 50], 5]")
+    );
+    assert_eq!(
+        simple_eval("n := 0; [a, b, c] := tee(iota(1) lazy_map (\\x -> (n += 1; x)), 3); [a take 3, b take 5, c take 1, n]"),
+        simple_eval("[[1, 2, 3], [1, 2, 3, 4, 5], [1], 5]")
+    );
+    assert_eq!(
+        simple_eval("tee([1, 2], 2)"),
+        simple_eval("[[1, 2], [1, 2]]")
+    );
+    assert_eq!(simple_eval("tee(iota(0), 0)"), simple_eval("[]"));
+    // one cursor's mapper can read another cursor while the shared cache is being filled
+    assert_eq!(
+        simple_eval("a := null; t := tee(iota(0) lazy_map (\\x -> len(a take x)), 2); a = t[0]; [t[1] take 4, a take 4]"),
+        simple_eval("[[0, 1, 2, 3], [0, 1, 2, 3]]")
+    );
+    assert_eq!(
+        simple_eval("a := null; t := tee(iota(0) lazy_map (\\x -> a[x]), 2); a = t[0]; try t[1] take 1 catch e -> e"),
+        Obj::from("take: value error: cached stream needs its element 0 to compute itself")
+    );
+}
+
+#[test]
+fn longest_run() {
+    assert_eq!(simple_eval("longest_run([1, 1, 2, 2, 2, 1])"), i(3));
+    assert_eq!(simple_eval("longest_run('abbcccc')"), i(4));
+    assert_eq!(simple_eval("longest_run([])"), i(0));
+    assert_eq!(
+        simple_eval("longest_run((1 to 6) lazy_map (\\x -> x // 4))"),
+        i(3)
+    );
+    assert_eq!(
+        simple_eval("longest_run_where(\\x -> x > 0, [1, 2, -1, 3, 4, 5])"),
+        i(3)
+    );
+    assert_eq!(simple_eval("[0, 2, 4, 5, 6] longest_run_where even"), i(3));
+    assert_eq!(
+        simple_eval("longest_run_where(\\x -> x > 0, [-1, -2])"),
+        i(0)
+    );
+    assert_eq!(
+        simple_eval("try longest_run(iota(0)) catch e -> 'error'"),
+        Obj::from("error")
+    );
+    assert_eq!(
+        simple_eval("try longest_run_where(even, iota(0)) catch e -> 'error'"),
+        Obj::from("error")
+    );
+}
+
+#[test]
+fn reverse_infinite() {
+    assert_eq!(
+        simple_eval("try reverse(repeat(1)) catch e -> e"),
+        Obj::from("reverse(stream): value error: Cannot reverse repeat(1) because it's infinite")
+    );
+    assert_eq!(
+        simple_eval("try reverse(chain([1], repeat(2))) catch e -> 'error'"),
+        Obj::from("error")
+    );
+    assert_eq!(
+        simple_eval("reverse((1 to 4) lazy_map (\\x -> x * x))"),
+        simple_eval("[16, 9, 4, 1]")
+    );
+    assert_eq!(
+        simple_eval("list(reverse(repeat(1, 3)))"),
+        simple_eval("list(repeat(1, 3))")
+    );
+}
+
+#[test]
+fn replicate() {
+    assert_eq!(
+        simple_eval("list(replicate(3, [1, 2]))"),
+        simple_eval("[1, 2, 1, 2, 1, 2]")
+    );
+    assert_eq!(
+        simple_eval("list(replicate(3, 1 to 2))"),
+        simple_eval("[1, 2, 1, 2, 1, 2]")
+    );
+    assert_eq!(simple_eval("len(replicate(4, 0 til 5))"), i(20));
+    assert_eq!(simple_eval("len(replicate(4, 0 til 5) drop 7)"), i(13));
+    assert_eq!(
+        simple_eval("list((1 to 2) lazy_map (\\x -> x * 3) replicate 2)"),
+        simple_eval("[3, 6, 3, 6]")
+    );
+    assert_eq!(simple_eval("list(replicate(0, 1 to 3))"), simple_eval("[]"));
+    assert_eq!(
+        simple_eval("list(replicate(10 ^ 18, 1 til 1))"),
+        simple_eval("[]")
+    );
+    // each pass redoes the inner stream's work
+    assert_eq!(
+        simple_eval("n := 0; list(replicate(3, (1 to 2) lazy_map (\\x -> (n += 1; x)))); n"),
+        i(6)
+    );
+    assert_eq!(
+        simple_eval("try replicate(2, iota(0)) catch e -> 'error'"),
+        Obj::from("error")
+    );
+    // lengths come from the inner stream without walking it, and don't overflow
+    assert_eq!(
+        simple_eval("len(replicate(3, dedup(0 til 5)))"),
+        Obj::from(f64::INFINITY)
+    );
+    assert_eq!(
+        simple_eval("len(replicate(10 ^ 18, 0 til 100))"),
+        Obj::from(f64::INFINITY)
+    );
+    assert_eq!(
+        simple_eval("str(replicate(2, 1 to 2))"),
+        Obj::from("replicate(2, 1 til 3 by 1)")
+    );
+}
+
+#[test]
+fn argmin_argmax() {
+    assert_eq!(simple_eval("argmax([3, 1, 4, 1, 5, 9, 2, 6])"), i(5));
+    assert_eq!(simple_eval("argmin([3, 1, 4, 1, 5, 9, 2, 6])"), i(1));
+    assert_eq!(simple_eval("argmax([2, 7, 7, 1])"), i(1));
+    assert_eq!(simple_eval("argmin('banana')"), i(1));
+    assert_eq!(
+        simple_eval("argmax((1 to 10) lazy_map (\\x -> x % 4))"),
+        i(2)
+    );
+    assert_eq!(
+        simple_eval("argmin_by_key(\\x -> abs(x - 5), [1, 4, 6, 9])"),
+        i(1)
+    );
+    assert_eq!(
+        simple_eval("['aa', 'b', 'ccc', 'ddd'] argmax_by_key len"),
+        i(2)
+    );
+    assert_eq!(
+        simple_eval("try argmin([]) catch e -> 'error'"),
+        Obj::from("error")
+    );
+    assert_eq!(
+        simple_eval("try argmax(iota(0)) catch e -> 'error'"),
+        Obj::from("error")
+    );
+    // min and max still refuse incomparable arguments
+    assert_eq!(
+        simple_eval("try max(1, \"a\") catch e -> 'error'"),
+        Obj::from("error")
+    );
+    assert_eq!(
+        simple_eval("try min([1], 2) catch e -> 'error'"),
+        Obj::from("error")
+    );
+}
+
+#[test]
+fn prefix_sums() {
+    assert_eq!(
+        simple_eval("prefix_sums([1, 2, 3, 4])"),
+        simple_eval("[1, 3, 6, 10]")
+    );
+    assert_eq!(
+        simple_eval("prefix_sums([1, 2.5, 1])"),
+        simple_eval("[1, 3.5, 4.5]")
+    );
+    assert_eq!(
+        simple_eval("prefix_sums([2, 3]) map (\\x -> x is int)"),
+        simple_eval("[1, 1]")
+    );
+    assert_eq!(
+        simple_eval("prefix_sums([1, 2.5]) map (\\x -> x is float)"),
+        simple_eval("[0, 1]")
+    );
+    assert_eq!(
+        simple_eval("list(take(4, prefix_sums(iota(1))))"),
+        simple_eval("[1, 3, 6, 10]")
+    );
+    assert_eq!(simple_eval("prefix_sums([])"), simple_eval("[]"));
+    assert_eq!(
+        simple_eval("try prefix_sums([1, \"a\"]) catch e -> e"),
+        Obj::from("prefix_sums(list): type error: not a number: \"a\"")
+    );
+}